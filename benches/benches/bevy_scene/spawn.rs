@@ -0,0 +1,53 @@
+use bevy::{
+    ecs::{entity::EntityMap, world::World},
+    reflect::TypeRegistry,
+    scene::Scene,
+    transform::components::{GlobalTransform, Transform},
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+criterion_group!(benches, write_scene);
+criterion_main!(benches);
+
+const ENTITY_COUNT: usize = 5_000;
+const SPAWN_COUNT: usize = 100;
+
+fn setup() -> (Scene, TypeRegistry) {
+    let mut world = World::default();
+    world
+        .spawn_batch((0..ENTITY_COUNT).map(|_| (Transform::default(), GlobalTransform::default())));
+    let mut type_registry = TypeRegistry::default();
+    type_registry.register::<Transform>();
+    type_registry.register::<GlobalTransform>();
+    (Scene::new(world), type_registry)
+}
+
+fn write_scene(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("write_scene");
+    group.warm_up_time(std::time::Duration::from_millis(500));
+    group.measurement_time(std::time::Duration::from_secs(4));
+    let (scene, type_registry) = setup();
+
+    group.bench_function("prepare_each_spawn", |bencher| {
+        bencher.iter(|| {
+            let mut world = World::default();
+            for _ in 0..SPAWN_COUNT {
+                let plan = scene.prepare_copy_plan(&type_registry).unwrap();
+                scene.write_prepared(&plan, &mut world, &mut EntityMap::default());
+            }
+            black_box(world);
+        });
+    });
+    group.bench_function("prepared_once", |bencher| {
+        let plan = scene.prepare_copy_plan(&type_registry).unwrap();
+        bencher.iter(|| {
+            let mut world = World::default();
+            for _ in 0..SPAWN_COUNT {
+                scene.write_prepared(&plan, &mut world, &mut EntityMap::default());
+            }
+            black_box(world);
+        });
+    });
+
+    group.finish();
+}
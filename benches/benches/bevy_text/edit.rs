@@ -0,0 +1,113 @@
+use bevy::{
+    app::App,
+    asset::{AddAsset, AssetPlugin, Assets},
+    math::Size,
+    render::{color::Color, texture::Image},
+    sprite::TextureAtlas,
+    tasks::{IoTaskPool, TaskPool},
+    text::{Font, FontAtlasSet, FontSmoothing, TextEdit, TextPipeline, TextSection, TextStyle},
+};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+criterion_group!(benches, edit_line);
+criterion_main!(benches);
+
+fn document(lines: usize) -> String {
+    (0..lines)
+        .map(|line| format!("let line_{} = {};", line, line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Inserts and removes a character in turn in the middle line of documents of increasing
+/// length with [`TextPipeline::queue_text_edit`], comparing laying out only the edited paragraph
+/// with laying out the whole document again.
+fn edit_line(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("edit_line");
+    group.warm_up_time(std::time::Duration::from_millis(500));
+    group.measurement_time(std::time::Duration::from_secs(4));
+
+    let mut app = App::new();
+    app.insert_resource(IoTaskPool(TaskPool::new()))
+        .add_plugin(AssetPlugin)
+        .add_asset::<Font>()
+        .add_asset::<FontAtlasSet>()
+        .add_asset::<TextureAtlas>()
+        .add_asset::<Image>();
+    let world = &mut app.world;
+    let mut fonts = world.remove_resource::<Assets<Font>>().unwrap();
+    let mut font_atlas_sets = world.remove_resource::<Assets<FontAtlasSet>>().unwrap();
+    let mut texture_atlases = world.remove_resource::<Assets<TextureAtlas>>().unwrap();
+    let mut images = world.remove_resource::<Assets<Image>>().unwrap();
+    let font = fonts.add(
+        Font::try_from_bytes(include_bytes!("../../../assets/fonts/FiraMono-Medium.ttf").to_vec())
+            .unwrap(),
+    );
+    let bounds = Size::new(f32::MAX, f32::MAX);
+
+    for lines in [100, 1000, 10000] {
+        let mut section = TextSection {
+            value: document(lines),
+            style: TextStyle {
+                font: font.clone(),
+                font_size: 20.0,
+                color: Color::WHITE,
+            },
+            ..Default::default()
+        };
+        let at = section
+            .value
+            .find(&format!("let line_{} ", lines / 2))
+            .unwrap()
+            + 4;
+        let insert = TextEdit {
+            range: at..at,
+            inserted_len: 1,
+        };
+        let remove = TextEdit {
+            range: at..at + 1,
+            inserted_len: 0,
+        };
+        let mut pipeline = TextPipeline::<u32>::default();
+        let mut queue_text_edit = |section: &TextSection, edit: Option<&TextEdit>| {
+            pipeline
+                .queue_text_edit(
+                    0,
+                    &fonts,
+                    section,
+                    edit,
+                    1.0,
+                    bounds,
+                    FontSmoothing::default(),
+                    &mut font_atlas_sets,
+                    &mut texture_atlases,
+                    &mut images,
+                )
+                .unwrap();
+            black_box(pipeline.get_glyphs(&0).unwrap().glyphs.len());
+        };
+        queue_text_edit(&section, None);
+
+        group.bench_with_input(
+            BenchmarkId::new("incremental", lines),
+            &lines,
+            |bencher, _| {
+                bencher.iter(|| {
+                    let edit = if section.value.as_bytes()[at] == b'x' {
+                        section.value.remove(at);
+                        &remove
+                    } else {
+                        section.value.insert(at, 'x');
+                        &insert
+                    };
+                    queue_text_edit(&section, Some(edit));
+                });
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("full", lines), &lines, |bencher, _| {
+            bencher.iter(|| queue_text_edit(&section, None));
+        });
+    }
+
+    group.finish();
+}
@@ -70,7 +70,10 @@ impl Node for ClearPassNode {
                         load: LoadOp::Clear(0.0),
                         store: true,
                     }),
-                    stencil_ops: None,
+                    stencil_ops: Some(Operations {
+                        load: LoadOp::Clear(0),
+                        store: true,
+                    }),
                 }),
             };
 
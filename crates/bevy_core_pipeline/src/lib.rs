@@ -360,6 +360,12 @@ pub fn extract_core_pipeline_camera_phases(
     }
 }
 
+/// The format of the depth texture of the main 3d passes.
+///
+/// It has a stencil aspect, which the meshes can test and write to. The stencil values are cleared
+/// to `0` along with the depth.
+pub const MAIN_PASS_3D_DEPTH_FORMAT: TextureFormat = TextureFormat::Depth24PlusStencil8;
+
 pub fn prepare_core_views_system(
     mut commands: Commands,
     mut texture_cache: ResMut<TextureCache>,
@@ -387,8 +393,7 @@ pub fn prepare_core_views_system(
                 mip_level_count: 1,
                 sample_count: msaa.samples,
                 dimension: TextureDimension::D2,
-                format: TextureFormat::Depth32Float, /* PERF: vulkan docs recommend using 24
-                                                      * bit depth for better performance */
+                format: MAIN_PASS_3D_DEPTH_FORMAT,
                 usage: TextureUsages::RENDER_ATTACHMENT,
             },
         );
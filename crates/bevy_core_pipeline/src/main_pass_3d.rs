@@ -71,7 +71,11 @@ impl Node for MainPass3dNode {
                         load: LoadOp::Load,
                         store: true,
                     }),
-                    stencil_ops: None,
+                    // NOTE: The meshes can test and write the stencil buffer in all the passes
+                    stencil_ops: Some(Operations {
+                        load: LoadOp::Load,
+                        store: true,
+                    }),
                 }),
             };
 
@@ -105,7 +109,10 @@ impl Node for MainPass3dNode {
                         load: LoadOp::Load,
                         store: true,
                     }),
-                    stencil_ops: None,
+                    stencil_ops: Some(Operations {
+                        load: LoadOp::Load,
+                        store: true,
+                    }),
                 }),
             };
 
@@ -141,7 +148,10 @@ impl Node for MainPass3dNode {
                         load: LoadOp::Load,
                         store: false,
                     }),
-                    stencil_ops: None,
+                    stencil_ops: Some(Operations {
+                        load: LoadOp::Load,
+                        store: true,
+                    }),
                 }),
             };
 
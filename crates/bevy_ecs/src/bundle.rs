@@ -69,6 +69,20 @@ use std::{any::TypeId, collections::HashMap};
 /// }
 /// ```
 ///
+/// # Archetypes
+///
+/// The order of the components in a bundle doesn't matter: within a [`World`](crate::world::World),
+/// bundles with the same components in any order put their entities in the same archetype, whose
+/// components are sorted by [`ComponentId`]. A bundle with the same component twice panics when
+/// it is first used.
+///
+/// This doesn't hold across worlds. [`ComponentId`]s are assigned in the order components are
+/// first initialized in a world, and archetype ids in the order archetypes are first created. Two
+/// worlds, for example those of networked clients, only store the same components in the same
+/// order if they initialize them in the same order, like with
+/// [`World::init_component`](crate::world::World::init_component) before spawning anything, and
+/// only share archetype ids if they also create their archetypes in the same order.
+///
 /// # Safety
 ///
 /// - [`Bundle::component_ids`] must return the [`ComponentId`] for each component type in the
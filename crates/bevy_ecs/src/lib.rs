@@ -18,7 +18,7 @@ pub mod world;
 pub mod prelude {
     #[doc(hidden)]
     #[cfg(feature = "bevy_reflect")]
-    pub use crate::reflect::ReflectComponent;
+    pub use crate::reflect::{ReflectComponent, ReflectResource};
     #[doc(hidden)]
     pub use crate::{
         bundle::Bundle,
@@ -52,7 +52,7 @@ mod tests {
         query::{
             Added, ChangeTrackers, Changed, FilterFetch, FilteredAccess, With, Without, WorldQuery,
         },
-        world::{Mut, World},
+        world::{ChunkedSpawn, Mut, World},
     };
     use bevy_tasks::TaskPool;
     use parking_lot::Mutex;
@@ -694,6 +694,125 @@ mod tests {
         assert_eq!(values, expected);
     }
 
+    #[test]
+    fn spawn_batch_with_misleading_size_hint() {
+        struct Misleading(std::ops::Range<usize>);
+        impl Iterator for Misleading {
+            type Item = (A, TableStored);
+            fn next(&mut self) -> Option<Self::Item> {
+                self.0.next().map(|x| (A(x), TableStored("abc")))
+            }
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                (0, Some(usize::MAX / 2))
+            }
+        }
+
+        let mut world = World::new();
+        let entities = world.spawn_batch(Misleading(0..3)).collect::<Vec<_>>();
+        assert_eq!(entities.len(), 3);
+        let table_id = world.entity(entities[0]).archetype().table_id();
+        let capacity = world.storages().tables[table_id].capacity();
+        assert!(capacity <= crate::world::DEFAULT_SPAWN_BATCH_MAX_RESERVE);
+
+        let count = world
+            .spawn_batch_with_max_reserve(Misleading(0..100), 8)
+            .count();
+        assert_eq!(count, 100);
+    }
+
+    #[test]
+    fn spawn_batch_archetype_ignores_bundle_order() {
+        let mut world = World::new();
+        let forward = world
+            .spawn_batch((0..3).map(|i| (A(i), B(i), TableStored("abc"))))
+            .collect::<Vec<_>>();
+        let backward = world
+            .spawn_batch((0..3).map(|i| (TableStored("abc"), B(i), A(i))))
+            .collect::<Vec<_>>();
+
+        let forward_entity = world.entity(forward[0]);
+        let forward_archetype = forward_entity.archetype();
+        let backward_entity = world.entity(backward[0]);
+        let backward_archetype = backward_entity.archetype();
+        assert_eq!(forward_archetype.id(), backward_archetype.id());
+        assert_eq!(
+            forward_archetype.table_components(),
+            backward_archetype.table_components()
+        );
+        assert_eq!(world.entity(backward[2]).get::<A>(), Some(&A(2)));
+    }
+
+    #[test]
+    fn spawn_batch_with_id() {
+        #[derive(Component)]
+        struct Me(Entity);
+
+        let mut world = World::new();
+        world.spawn().insert(A(0));
+        let entities = world
+            .spawn_batch_with_id(100, |entity| (Me(entity), A(1)))
+            .collect::<Vec<_>>();
+        assert_eq!(entities.len(), 100);
+        for entity in entities {
+            assert_eq!(world.get::<Me>(entity).unwrap().0, entity);
+        }
+        assert_eq!(world.query::<&Me>().iter(&world).count(), 100);
+    }
+
+    #[test]
+    fn spawn_batch_with_id_panicking_bundle() {
+        let mut world = World::new();
+        world.spawn().insert(A(0));
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            world
+                .spawn_batch_with_id(100, |entity| {
+                    assert!(entity.id() < 3, "no bundle for {:?}", entity);
+                    (A(entity.id() as usize),)
+                })
+                .count()
+        }));
+        assert!(result.is_err());
+        // the entities spawned before the panic remain, the others aren't spawned when the
+        // iterator is dropped while unwinding
+        assert_eq!(world.entities().len(), 3);
+        assert_eq!(world.query::<&A>().iter(&world).count(), 3);
+        world.spawn().insert(A(3));
+        assert_eq!(world.entities().len(), 4);
+    }
+
+    #[test]
+    fn chunked_spawn() {
+        let mut world = World::new();
+        let mut chunked = ChunkedSpawn::new((0..10_000).map(|i| (A(i),)));
+        let mut remaining = Vec::new();
+        for _ in 0..10 {
+            assert_eq!(chunked.spawn_chunk(&mut world, 1_000).count(), 1_000);
+            remaining.push(chunked.remaining());
+        }
+        assert_eq!(
+            remaining,
+            (0..10).rev().map(|i| i * 1_000).collect::<Vec<_>>()
+        );
+        assert_eq!(chunked.spawn_chunk(&mut world, 1_000).count(), 0);
+        assert_eq!(world.query::<&A>().iter(&world).count(), 10_000);
+    }
+
+    #[test]
+    fn spawn_batch_for_each_spawned() {
+        let mut world = World::new();
+        let mut spawned = 0;
+        let mut last = None;
+        world
+            .spawn_batch((0..10_000).map(|i| (A(i),)))
+            .for_each_spawned(|entity| {
+                spawned += 1;
+                last = Some(entity);
+            });
+        assert_eq!(spawned, 10_000);
+        assert_eq!(world.entities().len(), 10_000);
+        assert_eq!(world.get::<A>(last.unwrap()), Some(&A(9_999)));
+    }
+
     #[test]
     fn query_get() {
         let mut world = World::new();
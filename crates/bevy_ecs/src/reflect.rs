@@ -2,33 +2,242 @@
 
 pub use crate::change_detection::ReflectMut;
 use crate::{
-    component::Component,
+    component::{Component, ComponentId, ComponentTicks, Components},
     entity::{Entity, EntityMap, MapEntities, MapEntitiesError},
-    world::{FromWorld, World},
+    system::Resource,
+    world::{EntityRef, FromWorld, World},
 };
 use bevy_reflect::{
-    impl_from_reflect_value, impl_reflect_value, FromType, Reflect, ReflectDeserialize,
+    impl_from_reflect_value, impl_reflect_value, FromType, GetPath, Reflect, ReflectDefault,
+    ReflectDeserialize, ReflectFromReflect, ReflectRef, TypeRegistry,
 };
+use bevy_utils::{tracing::warn, HashMap, HashSet};
+use std::{any::TypeId, mem::discriminant};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum InsertDefaultError {
+    #[error("the component `{0}` does not register `ReflectDefault` in the type registry")]
+    MissingReflectDefault(&'static str),
+}
+
+/// The error returned when a component or resource can't be built from a reflected value, see
+/// [`ReflectComponent::add_component`].
+#[derive(Error, Debug)]
+pub enum ReflectConstructError {
+    #[error("could not construct `{type_name}` from the reflected value `{value_type_name}`")]
+    IncompatibleValue {
+        type_name: &'static str,
+        value_type_name: String,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum SetFieldError {
+    #[error("the entity does not have the component")]
+    MissingComponent,
+    #[error("the path `{path}` does not lead to a field of the component: {message}")]
+    InvalidPath { path: String, message: String },
+    #[error("the field `{path}` is a `{expected}`, not a `{found}`")]
+    TypeMismatch {
+        path: String,
+        expected: String,
+        found: String,
+    },
+}
+
+/// A component added or applied to an entity through [`ReflectComponent`], recorded in
+/// [`ReflectEdits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReflectEdit {
+    pub entity: Entity,
+    /// The [`TypeId`] of the component.
+    pub type_id: TypeId,
+}
+
+/// Records the components written through [`ReflectComponent::add_component`],
+/// [`ReflectComponent::insert_default`] and [`ReflectComponent::apply_component`], for example
+/// for an editor to mark a document as modified.
+///
+/// Edits are only recorded in worlds where this resource was inserted, and they accumulate until
+/// they are drained.
+#[derive(Debug, Default)]
+pub struct ReflectEdits {
+    edits: Vec<ReflectEdit>,
+}
+
+impl ReflectEdits {
+    /// The edits recorded since the last drain, in order.
+    pub fn edits(&self) -> &[ReflectEdit] {
+        &self.edits
+    }
+
+    pub fn drain(&mut self) -> impl Iterator<Item = ReflectEdit> + '_ {
+        self.edits.drain(..)
+    }
+}
+
+fn record_reflect_edit<C: Component>(world: &mut World, entity: Entity) {
+    if let Some(mut edits) = world.get_resource_mut::<ReflectEdits>() {
+        edits.edits.push(ReflectEdit {
+            entity,
+            type_id: TypeId::of::<C>(),
+        });
+    }
+}
 
 #[derive(Clone)]
 pub struct ReflectComponent {
-    add_component: fn(&mut World, Entity, &dyn Reflect),
+    add_component:
+        fn(&mut World, Entity, &dyn Reflect, &TypeRegistry) -> Result<(), ReflectConstructError>,
+    insert_default: fn(&mut World, Entity, &TypeRegistry) -> Result<(), InsertDefaultError>,
     apply_component: fn(&mut World, Entity, &dyn Reflect),
     remove_component: fn(&mut World, Entity),
     reflect_component: fn(&World, Entity) -> Option<&dyn Reflect>,
-    reflect_component_mut: unsafe fn(&World, Entity) -> Option<ReflectMut>,
+    reflect_component_mut: unsafe fn(&World, Entity, ComponentId) -> Option<ReflectMut>,
     copy_component: fn(&World, &mut World, Entity, Entity),
+    component_id: fn(&Components) -> Option<ComponentId>,
+    set_ticks: fn(&mut World, Entity, u32, bool),
 }
 
 impl ReflectComponent {
-    pub fn add_component(&self, world: &mut World, entity: Entity, component: &dyn Reflect) {
-        (self.add_component)(world, entity, component);
+    /// Adds the component built from `component` to `entity`, replacing any existing value.
+    ///
+    /// The component is built with the [`ReflectFromReflect`] it registers in `type_registry` if
+    /// any, see [`FromReflect`](bevy_reflect::FromReflect). Otherwise `component` is applied to
+    /// the [`FromWorld`] value of the component, which fails if it is of another kind, for example
+    /// a list for a struct component. The entity is then left untouched.
+    pub fn add_component(
+        &self,
+        world: &mut World,
+        entity: Entity,
+        component: &dyn Reflect,
+        type_registry: &TypeRegistry,
+    ) -> Result<(), ReflectConstructError> {
+        (self.add_component)(world, entity, component, type_registry)
+    }
+
+    /// Adds the component to `entity` only if it doesn't have it yet, leaving an existing value
+    /// untouched.
+    pub fn insert_if_absent(
+        &self,
+        world: &mut World,
+        entity: Entity,
+        component: &dyn Reflect,
+        type_registry: &TypeRegistry,
+    ) -> Result<(), ReflectConstructError> {
+        if self.reflect_component(world, entity).is_none() {
+            self.add_component(world, entity, component, type_registry)?;
+        }
+        Ok(())
+    }
+
+    /// Applies `component` to the component of `entity` like
+    /// [`ReflectComponent::apply_component`], or adds it like [`ReflectComponent::add_component`]
+    /// if the entity doesn't have it yet.
+    pub fn apply_or_insert(
+        &self,
+        world: &mut World,
+        entity: Entity,
+        component: &dyn Reflect,
+        type_registry: &TypeRegistry,
+    ) -> Result<(), ReflectConstructError> {
+        if self.reflect_component(world, entity).is_some() {
+            self.apply_component(world, entity, component);
+            Ok(())
+        } else {
+            self.add_component(world, entity, component, type_registry)
+        }
+    }
+
+    /// Inserts the [`Default`] value of the component on `entity`, replacing any existing value.
+    ///
+    /// The component must register [`ReflectDefault`] in `type_registry`, for example with
+    /// `#[reflect(Component, Default)]`, otherwise an error is returned and the entity is left
+    /// untouched.
+    pub fn insert_default(
+        &self,
+        world: &mut World,
+        entity: Entity,
+        type_registry: &TypeRegistry,
+    ) -> Result<(), InsertDefaultError> {
+        (self.insert_default)(world, entity, type_registry)
     }
 
     pub fn apply_component(&self, world: &mut World, entity: Entity, component: &dyn Reflect) {
         (self.apply_component)(world, entity, component);
     }
 
+    /// Applies `component` like [`ReflectComponent::apply_component`], returning the paths of the
+    /// fields whose value changed, like `"transform.translation"`.
+    ///
+    /// Nested structs are compared field by field, and the fields missing from `component` are
+    /// left untouched. If no field changed, the component isn't applied, so it isn't marked as
+    /// changed. If the component is not a struct, the returned path is empty when it changed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `entity` doesn't have the component.
+    pub fn apply_component_tracked(
+        &self,
+        world: &mut World,
+        entity: Entity,
+        component: &dyn Reflect,
+    ) -> Vec<String> {
+        let mut changed_fields = Vec::new();
+        let current = self
+            .reflect_component(world, entity)
+            .expect("the entity should have the component to apply");
+        push_changed_fields(current, component, String::new(), &mut changed_fields);
+        if !changed_fields.is_empty() {
+            self.apply_component(world, entity, component);
+        }
+        changed_fields
+    }
+
+    /// Sets the field of the component of `entity` found at `path`, like `"translation.x"`, to
+    /// `value`, leaving the other fields untouched.
+    ///
+    /// See [`GetPath`] for the syntax of `path`. The component is marked as changed only if the
+    /// field was set, an invalid path or a `value` of another type than the field returning an
+    /// error instead.
+    pub fn set_field_by_path(
+        &self,
+        world: &mut World,
+        entity: Entity,
+        path: &str,
+        value: Box<dyn Reflect>,
+    ) -> Result<(), SetFieldError> {
+        let invalid_path = |error: bevy_reflect::ReflectPathError| SetFieldError::InvalidPath {
+            path: path.to_string(),
+            message: error.to_string(),
+        };
+        // Checked on a shared borrow first, so that a failure doesn't trigger change detection
+        let field = self
+            .reflect_component(world, entity)
+            .ok_or(SetFieldError::MissingComponent)?
+            .path(path)
+            .map_err(invalid_path)?;
+        if field.type_name() != value.type_name() {
+            return Err(SetFieldError::TypeMismatch {
+                path: path.to_string(),
+                expected: field.type_name().to_string(),
+                found: value.type_name().to_string(),
+            });
+        }
+
+        let mut component = self.reflect_component_mut(world, entity).unwrap();
+        let field = component.path_mut(path).map_err(invalid_path)?;
+        field
+            .set(value)
+            .map_err(|value| SetFieldError::TypeMismatch {
+                path: path.to_string(),
+                expected: field.type_name().to_string(),
+                found: value.type_name().to_string(),
+            })
+    }
+
+    /// Removes the component from `entity`. Does nothing if the entity doesn't have it.
     pub fn remove_component(&self, world: &mut World, entity: Entity) {
         (self.remove_component)(world, entity);
     }
@@ -47,7 +256,60 @@ impl ReflectComponent {
         entity: Entity,
     ) -> Option<ReflectMut<'a>> {
         // SAFE: unique world access
-        unsafe { (self.reflect_component_mut)(world, entity) }
+        unsafe { self.reflect_component_unchecked_mut(world, entity) }
+    }
+
+    /// Gets the reflected component of each of `entities` mutably at once, in the same order.
+    ///
+    /// Returns [`None`] if `entities` contains the same entity several times, which would alias
+    /// its component, or if one of them doesn't have the component. The component is only looked
+    /// up once, and then fetched from the storage of each entity.
+    pub fn reflect_component_many_mut<'a>(
+        &self,
+        world: &'a mut World,
+        entities: &[Entity],
+    ) -> Option<Vec<ReflectMut<'a>>> {
+        let mut unique_entities = HashSet::default();
+        if !entities
+            .iter()
+            .all(|entity| unique_entities.insert(*entity))
+        {
+            return None;
+        }
+        let component_id = self.component_id(world.components())?;
+        entities
+            .iter()
+            // SAFE: unique world access, and each entity is only accessed once
+            .map(|entity| unsafe { (self.reflect_component_mut)(world, *entity, component_id) })
+            .collect()
+    }
+
+    /// Iterates over all the entities of `world` with the component, with mutable access to it.
+    ///
+    /// Only the components which are mutably dereferenced are marked as changed.
+    pub fn reflect_component_iter_mut<'a>(
+        &self,
+        world: &'a mut World,
+    ) -> impl Iterator<Item = (Entity, ReflectMut<'a>)> + 'a {
+        let reflect_component_mut = self.reflect_component_mut;
+        let world: &'a World = world;
+        self.component_id(world.components())
+            .into_iter()
+            .flat_map(move |component_id| {
+                world
+                    .archetypes()
+                    .iter()
+                    .filter(move |archetype| archetype.contains(component_id))
+                    .flat_map(|archetype| archetype.entities().iter().copied())
+                    .map(move |entity| {
+                        // SAFE: unique world access, and each entity is only in a single
+                        // archetype, so it is yielded once
+                        let component =
+                            unsafe { reflect_component_mut(world, entity, component_id) }
+                                .expect("entities of archetypes with the component should have it");
+                        (entity, component)
+                    })
+            })
     }
 
     /// # Safety
@@ -61,7 +323,8 @@ impl ReflectComponent {
         world: &'a World,
         entity: Entity,
     ) -> Option<ReflectMut<'a>> {
-        (self.reflect_component_mut)(world, entity)
+        let component_id = self.component_id(world.components())?;
+        (self.reflect_component_mut)(world, entity, component_id)
     }
 
     pub fn copy_component(
@@ -78,19 +341,319 @@ impl ReflectComponent {
             destination_entity,
         );
     }
+
+    /// Copies the component of `source_entity` to `destination_entity`, both living in `world`.
+    ///
+    /// The component is inserted on `destination_entity`, replacing any existing value. Does
+    /// nothing if `source_entity` does not have the component.
+    pub fn copy_within_world(
+        &self,
+        world: &mut World,
+        source_entity: Entity,
+        destination_entity: Entity,
+        type_registry: &TypeRegistry,
+    ) {
+        // The source is read into a temporary value so that it doesn't alias the destination
+        if let Some(component) = self
+            .reflect_component(world, source_entity)
+            .map(Reflect::clone_value)
+        {
+            self.add_component(world, destination_entity, &*component, type_registry)
+                .expect("a clone of the component should be of the same kind as the component");
+        }
+    }
+
+    /// Marks the component of `entity` as added and changed at `change_tick`, for example for
+    /// components loaded from a save to be detected as added on the frame they were loaded.
+    ///
+    /// Does nothing if the entity doesn't have the component.
+    pub fn set_added_tick(&self, world: &mut World, entity: Entity, change_tick: u32) {
+        (self.set_ticks)(world, entity, change_tick, true);
+    }
+
+    /// Marks the component of `entity` as changed at `change_tick`, keeping the tick at which it
+    /// was added.
+    ///
+    /// Does nothing if the entity doesn't have the component.
+    pub fn set_changed_tick(&self, world: &mut World, entity: Entity, change_tick: u32) {
+        (self.set_ticks)(world, entity, change_tick, false);
+    }
+
+    /// Returns the [`ComponentId`] of the reflected component, or [`None`] if it was never
+    /// registered in `components`.
+    pub fn component_id(&self, components: &Components) -> Option<ComponentId> {
+        (self.component_id)(components)
+    }
+
+    /// Returns the ticks at which the reflected component of `entity` was added and last changed,
+    /// or [`None`] if the entity doesn't have it.
+    pub fn get_change_ticks(&self, mut entity: EntityRef) -> Option<ComponentTicks> {
+        let component_id = self.component_id(entity.world().components())?;
+        entity.get_change_ticks_by_id(component_id).cloned()
+    }
+
+    /// Returns the sorted set of [`ComponentId`]s `entity` would have once every component of
+    /// `reflect_components` is added to it.
+    ///
+    /// The entity moves to another archetype if this set differs from the components of its
+    /// current archetype. Returns [`None`] if `entity` does not exist, or if one of the
+    /// components was never registered in `world`, in which case adding it always moves the
+    /// entity.
+    pub fn component_ids_after_insert<'a>(
+        world: &World,
+        entity: Entity,
+        reflect_components: impl IntoIterator<Item = &'a ReflectComponent>,
+    ) -> Option<Vec<ComponentId>> {
+        let location = world.entities().get(entity)?;
+        let mut component_ids: Vec<_> = world.archetypes()[location.archetype_id]
+            .components()
+            .collect();
+        for reflect_component in reflect_components {
+            component_ids.push(reflect_component.component_id(world.components())?);
+        }
+        component_ids.sort_unstable();
+        component_ids.dedup();
+        Some(component_ids)
+    }
+}
+
+/// Iterates over the components of `entity` which registered [`ReflectComponent`] in
+/// `type_registry`, yielding their type name and reflected value.
+///
+/// Components without reflection data are skipped. Yields nothing if `entity` does not exist.
+pub fn reflect_entity_components<'a>(
+    world: &'a World,
+    entity: Entity,
+    type_registry: &'a TypeRegistry,
+) -> impl Iterator<Item = (&'static str, &'a dyn Reflect)> + 'a {
+    let components = world
+        .entities()
+        .get(entity)
+        .map(|location| world.archetypes()[location.archetype_id].components());
+    components
+        .into_iter()
+        .flatten()
+        .filter_map(move |component_id| {
+            let type_id = world.components().get_info(component_id)?.type_id()?;
+            let registration = type_registry.get(type_id)?;
+            let reflect_component = registration.data::<ReflectComponent>()?;
+            let component = reflect_component.reflect_component(world, entity)?;
+            Some((registration.name(), component))
+        })
+}
+
+/// A difference between the reflected components of two entities, found by
+/// [`reflect_entities_diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComponentDifference {
+    /// Both entities have the component, with values which are not equal, or which can't be
+    /// compared.
+    Mismatch(&'static str),
+    /// Only the second entity has the component.
+    MissingOnFirst(&'static str),
+    /// Only the first entity has the component.
+    MissingOnSecond(&'static str),
+}
+
+/// Compares the components of `a` and `b` which registered [`ReflectComponent`] in
+/// `type_registry`, with [`Reflect::reflect_partial_eq`], and returns their differences ordered
+/// by component type name.
+///
+/// Components without reflection data are ignored. Values for which `reflect_partial_eq`
+/// returns `None`, like `f32` fields which don't reflect `PartialEq`, are reported as mismatched.
+pub fn reflect_entities_diff(
+    world: &World,
+    a: Entity,
+    b: Entity,
+    type_registry: &TypeRegistry,
+) -> Vec<ComponentDifference> {
+    reflect_world_entities_diff(world, a, world, b, type_registry)
+}
+
+/// Compares the components of entity `a` of `a_world` and entity `b` of `b_world` like
+/// [`reflect_entities_diff`], for example to compare the same entity in two snapshots of a world.
+pub fn reflect_world_entities_diff(
+    a_world: &World,
+    a: Entity,
+    b_world: &World,
+    b: Entity,
+    type_registry: &TypeRegistry,
+) -> Vec<ComponentDifference> {
+    let mut b_components = reflect_entity_components(b_world, b, type_registry).collect::<Vec<_>>();
+    let mut differences = Vec::new();
+    for (name, a_component) in reflect_entity_components(a_world, a, type_registry) {
+        match b_components.iter().position(|(b_name, _)| *b_name == name) {
+            Some(index) => {
+                let (_, b_component) = b_components.swap_remove(index);
+                if a_component.reflect_partial_eq(b_component) != Some(true) {
+                    differences.push(ComponentDifference::Mismatch(name));
+                }
+            }
+            None => differences.push(ComponentDifference::MissingOnSecond(name)),
+        }
+    }
+    differences.extend(
+        b_components
+            .into_iter()
+            .map(|(name, _)| ComponentDifference::MissingOnFirst(name)),
+    );
+    differences.sort_by_key(|difference| match difference {
+        ComponentDifference::Mismatch(name)
+        | ComponentDifference::MissingOnFirst(name)
+        | ComponentDifference::MissingOnSecond(name) => *name,
+    });
+    differences
+}
+
+/// Returns whether `a` and `b` have the same components which registered [`ReflectComponent`] in
+/// `type_registry`, with equal values.
+///
+/// See [`reflect_entities_diff`] to find out which components differ.
+pub fn reflect_entities_eq(
+    world: &World,
+    a: Entity,
+    b: Entity,
+    type_registry: &TypeRegistry,
+) -> bool {
+    reflect_entities_diff(world, a, b, type_registry).is_empty()
+}
+
+/// Spawns a copy of `source` with each of its components which registered [`ReflectComponent`] in
+/// `type_registry`, and returns the new entity.
+///
+/// The other components can't be copied and are skipped with a warning. Components referring to
+/// other entities, like `Children`, are copied as is: it is up to the caller to map them, for
+/// example with [`ReflectMapEntities`].
+///
+/// # Panics
+///
+/// Panics if `source` does not exist.
+pub fn clone_entity_reflect(
+    world: &mut World,
+    source: Entity,
+    type_registry: &TypeRegistry,
+) -> Entity {
+    let location = world
+        .entities()
+        .get(source)
+        .expect("the entity to clone should exist");
+    let reflect_components = world.archetypes()[location.archetype_id]
+        .components()
+        .filter_map(|component_id| {
+            let component_info = world.components().get_info(component_id)?;
+            let reflect_component = component_info
+                .type_id()
+                .and_then(|type_id| type_registry.get(type_id))
+                .and_then(|registration| registration.data::<ReflectComponent>());
+            if reflect_component.is_none() {
+                warn!(
+                    "the component `{}` is not reflected, it is not copied to the clone of {:?}",
+                    component_info.name(),
+                    source
+                );
+            }
+            reflect_component.cloned()
+        })
+        .collect::<Vec<_>>();
+    let destination = world.spawn().id();
+    for reflect_component in reflect_components {
+        reflect_component.copy_within_world(world, source, destination, type_registry);
+    }
+    destination
+}
+
+/// What [`apply_reflect_snapshot`] does with the entities of a snapshot which don't exist in
+/// the world.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissingEntities {
+    /// Spawns them with the same id, see [`World::get_or_spawn`].
+    Spawn,
+    /// Ignores their components.
+    Skip,
+}
+
+/// Applies the reflected components of each entity of `snapshot` to it, inserting the ones it
+/// doesn't have yet.
+///
+/// The [`ReflectComponent`] of each component type is looked up once in `type_registry` by type
+/// name, and its values are then applied to all the entities at once. Values whose type is not
+/// registered with [`ReflectComponent`] are skipped with a warning, and so are entities whose id
+/// is in use with another generation.
+pub fn apply_reflect_snapshot(
+    world: &mut World,
+    snapshot: &HashMap<Entity, Vec<Box<dyn Reflect>>>,
+    type_registry: &TypeRegistry,
+    missing_entities: MissingEntities,
+) {
+    let mut components_by_type: HashMap<&str, Vec<(Entity, &dyn Reflect)>> = HashMap::default();
+    for (&entity, components) in snapshot.iter() {
+        let exists = match missing_entities {
+            MissingEntities::Spawn => world.get_or_spawn(entity).is_some(),
+            MissingEntities::Skip => world.entities().contains(entity),
+        };
+        if !exists {
+            continue;
+        }
+        for component in components {
+            components_by_type
+                .entry(component.type_name())
+                .or_default()
+                .push((entity, &**component));
+        }
+    }
+
+    for (type_name, components) in components_by_type {
+        let reflect_component = match type_registry
+            .get_with_name(type_name)
+            .and_then(|registration| registration.data::<ReflectComponent>())
+        {
+            Some(reflect_component) => reflect_component,
+            None => {
+                warn!(
+                    "the component `{}` is not registered with `ReflectComponent`, it is not applied",
+                    type_name
+                );
+                continue;
+            }
+        };
+        for (entity, component) in components {
+            if let Err(error) =
+                reflect_component.apply_or_insert(world, entity, component, type_registry)
+            {
+                warn!("{}, it is not applied to {:?}", error, entity);
+            }
+        }
+    }
 }
 
 impl<C: Component + Reflect + FromWorld> FromType<C> for ReflectComponent {
     fn from_type() -> Self {
         ReflectComponent {
-            add_component: |world, entity, reflected_component| {
-                let mut component = C::from_world(world);
-                component.apply(reflected_component);
+            add_component: |world, entity, reflected_component, type_registry| {
+                let component =
+                    from_reflect_or_world::<C>(reflected_component, world, type_registry)?;
                 world.entity_mut(entity).insert(component);
+                record_reflect_edit::<C>(world, entity);
+                Ok(())
+            },
+            insert_default: |world, entity, type_registry| {
+                let reflect_default = type_registry
+                    .get_type_data::<ReflectDefault>(TypeId::of::<C>())
+                    .ok_or_else(|| {
+                        InsertDefaultError::MissingReflectDefault(std::any::type_name::<C>())
+                    })?;
+                let component = match reflect_default.default().downcast::<C>() {
+                    Ok(component) => *component,
+                    Err(_) => unreachable!("ReflectDefault produced a value of the wrong type"),
+                };
+                world.entity_mut(entity).insert(component);
+                record_reflect_edit::<C>(world, entity);
+                Ok(())
             },
             apply_component: |world, entity, reflected_component| {
                 let mut component = world.get_mut::<C>(entity).unwrap();
                 component.apply(reflected_component);
+                record_reflect_edit::<C>(world, entity);
             },
             remove_component: |world, entity| {
                 world.entity_mut(entity).remove::<C>();
@@ -103,16 +666,32 @@ impl<C: Component + Reflect + FromWorld> FromType<C> for ReflectComponent {
                     .entity_mut(destination_entity)
                     .insert(destination_component);
             },
+            component_id: |components| components.get_id(TypeId::of::<C>()),
+            set_ticks: |world, entity, change_tick, added| {
+                if let Some(component) = world.get_mut::<C>(entity) {
+                    let ticks = component.ticks.component_ticks;
+                    if added {
+                        *ticks = ComponentTicks::new(change_tick);
+                    } else {
+                        ticks.set_changed(change_tick);
+                    }
+                }
+            },
             reflect_component: |world, entity| {
                 world
                     .get_entity(entity)?
                     .get::<C>()
                     .map(|c| c as &dyn Reflect)
             },
-            reflect_component_mut: |world, entity| unsafe {
+            reflect_component_mut: |world, entity, component_id| unsafe {
+                // SAFE: `component_id` is the id of `C` in `world`, as looked up by the callers
                 world
                     .get_entity(entity)?
-                    .get_unchecked_mut::<C>(world.last_change_tick(), world.read_change_tick())
+                    .get_unchecked_mut_by_id::<C>(
+                        component_id,
+                        world.last_change_tick(),
+                        world.read_change_tick(),
+                    )
                     .map(|c| ReflectMut {
                         value: c.value as &mut dyn Reflect,
                         ticks: c.ticks,
@@ -122,12 +701,139 @@ impl<C: Component + Reflect + FromWorld> FromType<C> for ReflectComponent {
     }
 }
 
+#[derive(Clone)]
+pub struct ReflectResource {
+    insert_resource:
+        fn(&mut World, &dyn Reflect, &TypeRegistry) -> Result<(), ReflectConstructError>,
+    remove_resource: fn(&mut World),
+    take_resource: fn(&mut World) -> Option<Box<dyn Reflect>>,
+    reflect_resource: fn(&World) -> Option<&dyn Reflect>,
+}
+
+impl ReflectResource {
+    /// Inserts the resource built from `resource` in `world`, replacing any existing value.
+    ///
+    /// The resource is built with the [`ReflectFromReflect`] it registers in `type_registry` if
+    /// any, like [`ReflectComponent::add_component`].
+    pub fn insert_resource(
+        &self,
+        world: &mut World,
+        resource: &dyn Reflect,
+        type_registry: &TypeRegistry,
+    ) -> Result<(), ReflectConstructError> {
+        (self.insert_resource)(world, resource, type_registry)
+    }
+
+    /// Removes the resource from `world`. Does nothing if it is absent.
+    pub fn remove_resource(&self, world: &mut World) {
+        (self.remove_resource)(world);
+    }
+
+    /// Removes the resource from `world` and returns its value, or [`None`] if it is absent.
+    pub fn take_resource(&self, world: &mut World) -> Option<Box<dyn Reflect>> {
+        (self.take_resource)(world)
+    }
+
+    pub fn reflect_resource<'a>(&self, world: &'a World) -> Option<&'a dyn Reflect> {
+        (self.reflect_resource)(world)
+    }
+}
+
+impl<C: Resource + Reflect + FromWorld> FromType<C> for ReflectResource {
+    fn from_type() -> Self {
+        ReflectResource {
+            insert_resource: |world, reflected_resource, type_registry| {
+                let resource =
+                    from_reflect_or_world::<C>(reflected_resource, world, type_registry)?;
+                world.insert_resource(resource);
+                Ok(())
+            },
+            remove_resource: |world| {
+                world.remove_resource::<C>();
+            },
+            take_resource: |world| {
+                world
+                    .remove_resource::<C>()
+                    .map(|resource| Box::new(resource) as Box<dyn Reflect>)
+            },
+            reflect_resource: |world| world.get_resource::<C>().map(|c| c as &dyn Reflect),
+        }
+    }
+}
+
+/// Pushes the paths of the fields of `source` that differ from the ones of `current` to
+/// `changed_fields`.
+fn push_changed_fields(
+    current: &dyn Reflect,
+    source: &dyn Reflect,
+    path: String,
+    changed_fields: &mut Vec<String>,
+) {
+    if let (ReflectRef::Struct(current), ReflectRef::Struct(source)) =
+        (current.reflect_ref(), source.reflect_ref())
+    {
+        for (i, source_field) in source.iter_fields().enumerate() {
+            let name = source.name_at(i).unwrap();
+            let field_path = if path.is_empty() {
+                name.to_string()
+            } else {
+                format!("{}.{}", path, name)
+            };
+            if let Some(current_field) = current.field(name) {
+                push_changed_fields(current_field, source_field, field_path, changed_fields);
+            }
+        }
+    } else if current.reflect_partial_eq(source) != Some(true) {
+        changed_fields.push(path);
+    }
+}
+
+/// Constructs a `C` from a reflected value.
+///
+/// If `C` registers [`ReflectFromReflect`] in `type_registry`, this uses
+/// [`FromReflect`](bevy_reflect::FromReflect), which builds a complete value even from dynamic
+/// representations. Otherwise, or if that conversion fails, `C` is created with [`FromWorld`] and
+/// the reflected value is applied onto it.
+///
+/// Returns an error if neither path can produce a `C`, i.e. `FromReflect` is unavailable or fails
+/// and the reflected value is of a different kind than `C` (for example a list applied to a
+/// struct).
+fn from_reflect_or_world<C: Reflect + FromWorld>(
+    reflected: &dyn Reflect,
+    world: &mut World,
+    type_registry: &TypeRegistry,
+) -> Result<C, ReflectConstructError> {
+    let from_reflect = type_registry.get_type_data::<ReflectFromReflect>(TypeId::of::<C>());
+    if let Some(value) = from_reflect.and_then(|from_reflect| from_reflect.from_reflect(reflected))
+    {
+        match value.downcast::<C>() {
+            Ok(component) => return Ok(*component),
+            Err(_) => unreachable!("ReflectFromReflect produced a value of the wrong type"),
+        }
+    }
+
+    let mut component = C::from_world(world);
+    let can_apply = match (component.reflect_ref(), reflected.reflect_ref()) {
+        (ReflectRef::Value(_), ReflectRef::Value(value)) => value.any().is::<C>(),
+        (target, value) => discriminant(&target) == discriminant(&value),
+    };
+    if !can_apply {
+        return Err(ReflectConstructError::IncompatibleValue {
+            type_name: std::any::type_name::<C>(),
+            value_type_name: reflected.type_name().to_string(),
+        });
+    }
+    component.apply(reflected);
+    Ok(component)
+}
+
 impl_reflect_value!(Entity(Hash, PartialEq, Serialize, Deserialize));
 impl_from_reflect_value!(Entity);
 
 #[derive(Clone)]
 pub struct ReflectMapEntities {
     map_entities: fn(&mut World, &EntityMap) -> Result<(), MapEntitiesError>,
+    map_entity: fn(&mut World, Entity, &EntityMap) -> Result<(), MapEntitiesError>,
 }
 
 impl ReflectMapEntities {
@@ -138,6 +844,17 @@ impl ReflectMapEntities {
     ) -> Result<(), MapEntitiesError> {
         (self.map_entities)(world, entity_map)
     }
+
+    /// Maps the entities the component of `entity` refers to, returning an error if one of them
+    /// is missing from `entity_map`. Does nothing if `entity` doesn't have the component.
+    pub fn map_entity(
+        &self,
+        world: &mut World,
+        entity: Entity,
+        entity_map: &EntityMap,
+    ) -> Result<(), MapEntitiesError> {
+        (self.map_entity)(world, entity, entity_map)
+    }
 }
 
 impl<C: Component + MapEntities> FromType<C> for ReflectMapEntities {
@@ -151,6 +868,779 @@ impl<C: Component + MapEntities> FromType<C> for ReflectMapEntities {
                 }
                 Ok(())
             },
+            map_entity: |world, entity, entity_map| {
+                if let Some(mut component) = world.get_mut::<C>(entity) {
+                    component.map_entities(entity_map)?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+/// A component which validates or normalizes its value once it was written by reflection, for
+/// example when it is loaded from a scene.
+///
+/// Register it with `#[reflect(Component, AfterInsert)]` for scenes to use it.
+pub trait AfterInsert {
+    fn after_insert(&mut self);
+}
+
+#[derive(Clone)]
+pub struct ReflectAfterInsert {
+    after_insert: fn(&mut World, Entity),
+}
+
+impl ReflectAfterInsert {
+    /// Runs [`AfterInsert::after_insert`] on the component of `entity`, if it has one.
+    pub fn after_insert(&self, world: &mut World, entity: Entity) {
+        (self.after_insert)(world, entity);
+    }
+}
+
+impl<C: Component + AfterInsert> FromType<C> for ReflectAfterInsert {
+    fn from_type() -> Self {
+        ReflectAfterInsert {
+            after_insert: |world, entity| {
+                if let Some(mut component) = world.get_mut::<C>(entity) {
+                    component.after_insert();
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        apply_reflect_snapshot, clone_entity_reflect, reflect_entities_diff, reflect_entities_eq,
+        reflect_entity_components, ComponentDifference, InsertDefaultError, MissingEntities,
+        ReflectComponent, ReflectConstructError, ReflectEdit, ReflectEdits, ReflectResource,
+        SetFieldError,
+    };
+    use crate as bevy_ecs;
+    use crate::{component::Component, entity::Entity, world::World};
+    use bevy_reflect::{
+        DynamicStruct, FromReflect, GetPath, Reflect, ReflectDefault, ReflectFromReflect, Struct,
+        TypeRegistration, TypeRegistryArc,
+    };
+    use bevy_utils::HashMap;
+
+    #[derive(Component, Reflect, FromReflect, Default, Debug, Clone, Copy, PartialEq)]
+    #[reflect(Component, FromReflect)]
+    struct Health {
+        current: u32,
+        max: u32,
+    }
+
+    #[derive(Component, Reflect, Default)]
+    #[reflect(Component)]
+    struct Player;
+
+    #[derive(Reflect, Default, Debug, Clone, Copy, PartialEq)]
+    struct Offset {
+        x: f32,
+        y: f32,
+    }
+
+    #[derive(Component, Reflect, Default, Debug, Clone, Copy, PartialEq)]
+    #[reflect(Component)]
+    struct Placement {
+        translation: Offset,
+        scale: f32,
+    }
+
+    #[derive(Component, Reflect, Debug, PartialEq)]
+    #[reflect(Component, Default)]
+    struct Speed {
+        value: f32,
+        boost: u32,
+    }
+
+    impl Default for Speed {
+        fn default() -> Self {
+            Speed {
+                value: 2.5,
+                boost: 3,
+            }
+        }
+    }
+
+    fn world_with_registry() -> World {
+        let mut world = World::new();
+        let registry = TypeRegistryArc::default();
+        registry.write().register::<Health>();
+        registry.write().register::<Player>();
+        registry.write().register::<Speed>();
+        registry.write().register::<Placement>();
+        world.insert_resource(registry);
+        world
+    }
+
+    fn reflect_component<T: Reflect>(world: &World) -> ReflectComponent {
+        world
+            .get_resource::<TypeRegistryArc>()
+            .unwrap()
+            .read()
+            .get(std::any::TypeId::of::<T>())
+            .and_then(TypeRegistration::data::<ReflectComponent>)
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn add_component_from_concrete_value() {
+        let mut world = world_with_registry();
+        let reflect_component = reflect_component::<Health>(&world);
+        let entity = world.spawn().id();
+
+        let value = Health {
+            current: 3,
+            max: 10,
+        };
+        let registry = world.get_resource::<TypeRegistryArc>().unwrap().clone();
+        reflect_component
+            .add_component(&mut world, entity, &value, &registry.read())
+            .unwrap();
+
+        assert_eq!(world.get::<Health>(entity), Some(&value));
+    }
+
+    #[test]
+    fn add_component_from_dynamic_value() {
+        let mut world = world_with_registry();
+        let reflect_component = reflect_component::<Health>(&world);
+        let entity = world.spawn().id();
+
+        let dynamic = Health {
+            current: 7,
+            max: 12,
+        }
+        .clone_dynamic();
+        let registry = world.get_resource::<TypeRegistryArc>().unwrap().clone();
+        // the registry is read while the component is added, as when spawning a scene
+        let type_registry = registry.read();
+        reflect_component
+            .add_component(&mut world, entity, &dynamic, &type_registry)
+            .unwrap();
+
+        assert_eq!(
+            world.get::<Health>(entity),
+            Some(&Health {
+                current: 7,
+                max: 12
+            })
+        );
+    }
+
+    #[test]
+    fn from_reflect_is_registered() {
+        let world = world_with_registry();
+        let registry = world.get_resource::<TypeRegistryArc>().unwrap().read();
+        let from_reflect = registry
+            .get_type_data::<ReflectFromReflect>(std::any::TypeId::of::<Health>())
+            .unwrap();
+        let value = from_reflect
+            .from_reflect(&Health { current: 1, max: 2 }.clone_dynamic())
+            .unwrap();
+        assert!(value.is::<Health>());
+    }
+
+    #[test]
+    fn add_component_from_incompatible_value() {
+        let mut world = world_with_registry();
+        let reflect_component = reflect_component::<Health>(&world);
+        let entity = world.spawn().id();
+
+        let registry = world.get_resource::<TypeRegistryArc>().unwrap().clone();
+        let result = reflect_component.add_component(
+            &mut world,
+            entity,
+            &vec![1u32, 2u32],
+            &registry.read(),
+        );
+        assert!(matches!(
+            result,
+            Err(ReflectConstructError::IncompatibleValue { type_name, .. })
+                if type_name == std::any::type_name::<Health>()
+        ));
+        assert!(world.get::<Health>(entity).is_none());
+    }
+
+    #[test]
+    fn component_id_matches_world() {
+        let mut world = world_with_registry();
+        let reflect_component = reflect_component::<Health>(&world);
+        assert_eq!(reflect_component.component_id(world.components()), None);
+
+        let health_id = world.init_component::<Health>();
+        assert_eq!(
+            reflect_component.component_id(world.components()),
+            Some(health_id)
+        );
+    }
+
+    #[test]
+    fn get_change_ticks() {
+        let mut world = world_with_registry();
+        let reflect_component = reflect_component::<Health>(&world);
+        let entity = world.spawn().insert(Health { current: 1, max: 2 }).id();
+        let other = world.spawn().id();
+        assert!(reflect_component
+            .get_change_ticks(world.entity(other))
+            .is_none());
+
+        let spawn_tick = world.change_tick();
+        world.increment_change_tick();
+        world.get_mut::<Health>(entity).unwrap().current = 2;
+        let change_tick = world.change_tick();
+
+        let ticks = reflect_component
+            .get_change_ticks(world.entity(entity))
+            .unwrap();
+        assert!(ticks.is_changed(spawn_tick, change_tick));
+        assert!(!ticks.is_added(spawn_tick, change_tick));
+    }
+
+    #[test]
+    fn component_ids_after_insert() {
+        let mut world = world_with_registry();
+        let reflect_health = reflect_component::<Health>(&world);
+        let reflect_player = reflect_component::<Player>(&world);
+        let entity = world.spawn().insert(Player).id();
+        let player_id = world.init_component::<Player>();
+
+        assert_eq!(
+            ReflectComponent::component_ids_after_insert(&world, entity, [&reflect_player]),
+            Some(vec![player_id])
+        );
+        assert_eq!(
+            ReflectComponent::component_ids_after_insert(&world, entity, [&reflect_health]),
+            None
+        );
+
+        let health_id = world.init_component::<Health>();
+        let mut expected = vec![player_id, health_id];
+        expected.sort_unstable();
+        assert_eq!(
+            ReflectComponent::component_ids_after_insert(&world, entity, [&reflect_health]),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn copy_within_world() {
+        let mut world = world_with_registry();
+        let reflect_component = reflect_component::<Health>(&world);
+        let value = Health { current: 4, max: 9 };
+        let source = world.spawn().insert(value).id();
+        let destination = world.spawn().id();
+
+        let registry = world.get_resource::<TypeRegistryArc>().unwrap().clone();
+        let registry = registry.read();
+        reflect_component.copy_within_world(&mut world, source, destination, &registry);
+        assert_eq!(world.get::<Health>(destination), Some(&value));
+        assert_eq!(world.get::<Health>(source), Some(&value));
+
+        let empty = world.spawn().id();
+        reflect_component.copy_within_world(&mut world, empty, destination, &registry);
+        assert_eq!(world.get::<Health>(destination), Some(&value));
+    }
+
+    #[test]
+    fn insert_if_absent() {
+        let mut world = world_with_registry();
+        let reflect_component = reflect_component::<Health>(&world);
+        let original = Health {
+            current: 2,
+            max: 10,
+        };
+        let present = world.spawn().insert(original).id();
+        let absent = world.spawn().id();
+
+        let prefab = Health {
+            current: 10,
+            max: 10,
+        };
+        let registry = world.get_resource::<TypeRegistryArc>().unwrap().clone();
+        let registry = registry.read();
+        reflect_component
+            .insert_if_absent(&mut world, present, &prefab, &registry)
+            .unwrap();
+        reflect_component
+            .insert_if_absent(&mut world, absent, &prefab, &registry)
+            .unwrap();
+
+        assert_eq!(world.get::<Health>(present), Some(&original));
+        assert_eq!(world.get::<Health>(absent), Some(&prefab));
+    }
+
+    #[test]
+    fn apply_or_insert() {
+        let mut world = world_with_registry();
+        let reflect_component = reflect_component::<Health>(&world);
+        let entity = world.spawn().id();
+        let registry = world.get_resource::<TypeRegistryArc>().unwrap().clone();
+        let registry = registry.read();
+
+        let inserted = Health {
+            current: 4,
+            max: 10,
+        };
+        reflect_component
+            .apply_or_insert(&mut world, entity, &inserted, &registry)
+            .unwrap();
+        assert_eq!(world.get::<Health>(entity), Some(&inserted));
+
+        let applied = Health {
+            current: 7,
+            max: 10,
+        };
+        reflect_component
+            .apply_or_insert(&mut world, entity, &applied, &registry)
+            .unwrap();
+        assert_eq!(world.get::<Health>(entity), Some(&applied));
+    }
+
+    #[test]
+    fn remove_component() {
+        let mut world = world_with_registry();
+        let reflect_component = reflect_component::<Health>(&world);
+        let entity = world.spawn().id();
+
+        let value = Health {
+            current: 3,
+            max: 10,
+        };
+        let registry = world.get_resource::<TypeRegistryArc>().unwrap().clone();
+        reflect_component
+            .add_component(&mut world, entity, &value, &registry.read())
+            .unwrap();
+        reflect_component.remove_component(&mut world, entity);
+        assert_eq!(world.get::<Health>(entity), None);
+        assert!(reflect_component
+            .reflect_component(&world, entity)
+            .is_none());
+
+        // removing a missing component is a no-op
+        reflect_component.remove_component(&mut world, entity);
+        assert_eq!(world.get::<Health>(entity), None);
+    }
+
+    #[test]
+    fn reflect_entity_components_skips_unreflected() {
+        #[derive(Component)]
+        struct Unreflected;
+
+        let mut world = world_with_registry();
+        let entity = world
+            .spawn()
+            .insert_bundle((Health { current: 3, max: 5 }, Player, Unreflected))
+            .id();
+
+        let registry = world.get_resource::<TypeRegistryArc>().unwrap().read();
+        let mut names: Vec<_> = reflect_entity_components(&world, entity, &registry)
+            .map(|(name, _)| name)
+            .collect();
+        names.sort_unstable();
+        assert_eq!(
+            names,
+            vec![
+                std::any::type_name::<Health>(),
+                std::any::type_name::<Player>()
+            ]
+        );
+
+        let (_, health) = reflect_entity_components(&world, entity, &registry)
+            .find(|(name, _)| *name == std::any::type_name::<Health>())
+            .unwrap();
+        assert_eq!(
+            Health::from_reflect(health),
+            Some(Health { current: 3, max: 5 })
+        );
+    }
+
+    #[test]
+    fn reflect_component_many_mut() {
+        let mut world = world_with_registry();
+        let entities: Vec<_> = (0..3)
+            .map(|i| world.spawn().insert(Health { current: i, max: 5 }).id())
+            .collect();
+        let reflect_health = reflect_component::<Health>(&world);
+
+        let components = reflect_health
+            .reflect_component_many_mut(&mut world, &entities)
+            .unwrap();
+        for mut component in components {
+            if let bevy_reflect::ReflectMut::Struct(health) = component.reflect_mut() {
+                *health
+                    .field_mut("current")
+                    .and_then(|current| current.downcast_mut::<u32>())
+                    .unwrap() += 10;
+            }
+        }
+        for (i, entity) in entities.iter().enumerate() {
+            assert_eq!(world.get::<Health>(*entity).unwrap().current, i as u32 + 10);
+        }
+
+        let overlapping = [entities[0], entities[1], entities[0]];
+        assert!(reflect_health
+            .reflect_component_many_mut(&mut world, &overlapping)
+            .is_none());
+        let without_health = world.spawn().insert(Player).id();
+        assert!(reflect_health
+            .reflect_component_many_mut(&mut world, &[entities[0], without_health])
+            .is_none());
+    }
+
+    #[test]
+    fn apply_component_tracked() {
+        let mut world = world_with_registry();
+        let entity = world.spawn().insert(Health { current: 5, max: 5 }).id();
+        let reflect_health = reflect_component::<Health>(&world);
+
+        let mut update = DynamicStruct::default();
+        update.insert("current", 3u32);
+        update.insert("max", 5u32);
+        let changed = reflect_health.apply_component_tracked(&mut world, entity, &update);
+        assert_eq!(changed, vec!["current".to_string()]);
+        assert_eq!(
+            *world.get::<Health>(entity).unwrap(),
+            Health { current: 3, max: 5 }
+        );
+
+        let mut partial = DynamicStruct::default();
+        partial.insert("max", 5u32);
+        let changed = reflect_health.apply_component_tracked(&mut world, entity, &partial);
+        assert!(changed.is_empty());
+    }
+
+    #[test]
+    fn insert_default() {
+        let mut world = world_with_registry();
+        let reflect_speed = reflect_component::<Speed>(&world);
+        let entity = world.spawn().id();
+
+        let registry = world.get_resource::<TypeRegistryArc>().unwrap().clone();
+        let registry = registry.read();
+        reflect_speed
+            .insert_default(&mut world, entity, &registry)
+            .unwrap();
+        assert_eq!(world.get::<Speed>(entity), Some(&Speed::default()));
+
+        let reflect_health = reflect_component::<Health>(&world);
+        assert!(matches!(
+            reflect_health.insert_default(&mut world, entity, &registry),
+            Err(InsertDefaultError::MissingReflectDefault(_))
+        ));
+        assert!(world.get::<Health>(entity).is_none());
+    }
+
+    #[test]
+    fn iter_mut_all_components() {
+        let mut world = world_with_registry();
+        let reflect_health = reflect_component::<Health>(&world);
+        let entities = (0..3)
+            .map(|i| {
+                world
+                    .spawn()
+                    .insert(Health {
+                        current: i,
+                        max: 10,
+                    })
+                    .id()
+            })
+            .collect::<Vec<_>>();
+        // in another archetype
+        let player = world
+            .spawn()
+            .insert_bundle((
+                Health {
+                    current: 7,
+                    max: 10,
+                },
+                Player,
+            ))
+            .id();
+        let without_health = world.spawn().insert(Player).id();
+        let change_tick = world.change_tick();
+        world.increment_change_tick();
+
+        let mut visited = Vec::new();
+        for (entity, mut health) in reflect_health.reflect_component_iter_mut(&mut world) {
+            *health
+                .path_mut("max")
+                .unwrap()
+                .downcast_mut::<u32>()
+                .unwrap() = 20;
+            visited.push(entity);
+        }
+        visited.sort();
+        let mut expected = entities.clone();
+        expected.push(player);
+        expected.sort();
+        assert_eq!(visited, expected);
+        assert!(!visited.contains(&without_health));
+
+        for (i, entity) in entities.iter().enumerate() {
+            assert_eq!(
+                world.get::<Health>(*entity),
+                Some(&Health {
+                    current: i as u32,
+                    max: 20
+                })
+            );
         }
+        assert_eq!(world.get::<Health>(player).unwrap().max, 20);
+        for entity in expected {
+            let ticks = reflect_health
+                .get_change_ticks(world.entity(entity))
+                .unwrap();
+            assert!(ticks.is_changed(change_tick, world.change_tick()));
+        }
+    }
+
+    #[test]
+    fn set_field_by_path() {
+        let mut world = world_with_registry();
+        let reflect_placement = reflect_component::<Placement>(&world);
+        let original = Placement {
+            translation: Offset { x: 1.0, y: 2.0 },
+            scale: 3.0,
+        };
+        let entity = world.spawn().insert(original).id();
+        let change_tick = world.change_tick();
+        world.increment_change_tick();
+
+        reflect_placement
+            .set_field_by_path(&mut world, entity, "translation.x", Box::new(5.0f32))
+            .unwrap();
+        let expected = Placement {
+            translation: Offset { x: 5.0, y: 2.0 },
+            ..original
+        };
+        assert_eq!(world.get::<Placement>(entity), Some(&expected));
+        let ticks = reflect_placement
+            .get_change_ticks(world.entity(entity))
+            .unwrap();
+        assert!(ticks.is_changed(change_tick, world.change_tick()));
+
+        let change_tick = world.change_tick();
+        world.increment_change_tick();
+        assert!(matches!(
+            reflect_placement.set_field_by_path(
+                &mut world,
+                entity,
+                "translation.z",
+                Box::new(1.0f32)
+            ),
+            Err(SetFieldError::InvalidPath { .. })
+        ));
+        assert!(matches!(
+            reflect_placement.set_field_by_path(&mut world, entity, "scale", Box::new(1u32)),
+            Err(SetFieldError::TypeMismatch { .. })
+        ));
+        assert_eq!(world.get::<Placement>(entity), Some(&expected));
+        let ticks = reflect_placement
+            .get_change_ticks(world.entity(entity))
+            .unwrap();
+        assert!(!ticks.is_changed(change_tick, world.change_tick()));
+
+        let empty = world.spawn().id();
+        assert!(matches!(
+            reflect_placement.set_field_by_path(&mut world, empty, "scale", Box::new(1.0f32)),
+            Err(SetFieldError::MissingComponent)
+        ));
+    }
+
+    #[derive(Component)]
+    struct Unreflected;
+
+    #[test]
+    fn clone_entity() {
+        let mut world = world_with_registry();
+        let health = Health { current: 3, max: 8 };
+        let placement = Placement {
+            translation: Offset { x: 1.0, y: -1.0 },
+            scale: 2.0,
+        };
+        let source = world
+            .spawn()
+            .insert_bundle((health, placement, Unreflected))
+            .id();
+
+        let registry = world.get_resource::<TypeRegistryArc>().unwrap().clone();
+        let clone = clone_entity_reflect(&mut world, source, &registry.read());
+
+        assert_ne!(clone, source);
+        assert_eq!(world.get::<Health>(clone), Some(&health));
+        assert_eq!(world.get::<Placement>(clone), Some(&placement));
+        assert!(world.get::<Unreflected>(clone).is_none());
+        assert_eq!(world.get::<Health>(source), Some(&health));
+    }
+
+    #[test]
+    fn compare_entities() {
+        let mut world = world_with_registry();
+        let health = Health { current: 3, max: 8 };
+        let a = world
+            .spawn()
+            .insert_bundle((health, Player, Unreflected))
+            .id();
+        // unreflected components are ignored
+        let b = world.spawn().insert_bundle((health, Player)).id();
+        let hurt = world
+            .spawn()
+            .insert_bundle((Health { current: 2, max: 8 }, Player))
+            .id();
+        let lone = world.spawn().insert(health).id();
+
+        let registry = world.get_resource::<TypeRegistryArc>().unwrap().clone();
+        let registry = registry.read();
+        assert!(reflect_entities_eq(&world, a, b, &registry));
+        assert!(reflect_entities_eq(&world, b, a, &registry));
+
+        let health_name = std::any::type_name::<Health>();
+        let player_name = std::any::type_name::<Player>();
+        assert!(!reflect_entities_eq(&world, a, hurt, &registry));
+        assert_eq!(
+            reflect_entities_diff(&world, a, hurt, &registry),
+            vec![ComponentDifference::Mismatch(health_name)]
+        );
+        assert_eq!(
+            reflect_entities_diff(&world, a, lone, &registry),
+            vec![ComponentDifference::MissingOnSecond(player_name)]
+        );
+        assert_eq!(
+            reflect_entities_diff(&world, lone, hurt, &registry),
+            vec![
+                ComponentDifference::Mismatch(health_name),
+                ComponentDifference::MissingOnFirst(player_name),
+            ]
+        );
+    }
+
+    #[test]
+    fn apply_snapshot() {
+        let mut world = world_with_registry();
+        let existing = world.spawn().insert(Health { current: 1, max: 8 }).id();
+        let missing = world.spawn().id();
+        world.despawn(missing);
+
+        let placement = Placement {
+            translation: Offset { x: 1.0, y: 2.0 },
+            scale: 3.0,
+        };
+        let mut health_patch = DynamicStruct::default();
+        health_patch.set_name(std::any::type_name::<Health>().to_string());
+        health_patch.insert("current", 5u32);
+        let snapshot: HashMap<Entity, Vec<Box<dyn Reflect>>> = [
+            (
+                existing,
+                vec![
+                    Box::new(health_patch) as Box<dyn Reflect>,
+                    Box::new(placement),
+                ],
+            ),
+            (
+                missing,
+                vec![
+                    Box::new(Health { current: 2, max: 4 }) as Box<dyn Reflect>,
+                    // not a registered component, skipped
+                    Box::new(Offset { x: 0.0, y: 0.0 }),
+                ],
+            ),
+        ]
+        .into_iter()
+        .collect();
+
+        let registry = world.get_resource::<TypeRegistryArc>().unwrap().clone();
+        let registry = registry.read();
+        apply_reflect_snapshot(&mut world, &snapshot, &registry, MissingEntities::Skip);
+        assert_eq!(
+            world.get::<Health>(existing),
+            Some(&Health { current: 5, max: 8 })
+        );
+        assert_eq!(world.get::<Placement>(existing), Some(&placement));
+        assert!(world.get_entity(missing).is_none());
+
+        apply_reflect_snapshot(&mut world, &snapshot, &registry, MissingEntities::Spawn);
+        assert_eq!(
+            world.get::<Health>(missing),
+            Some(&Health { current: 2, max: 4 })
+        );
+    }
+
+    #[derive(Reflect, Default, Debug, PartialEq)]
+    #[reflect(Resource)]
+    struct Score {
+        value: u32,
+    }
+
+    #[test]
+    fn take_and_remove_resource() {
+        let mut world = World::new();
+        let registration = <Score as bevy_reflect::GetTypeRegistration>::get_type_registration();
+        let reflect_score = registration.data::<ReflectResource>().unwrap();
+        let registry = TypeRegistryArc::default();
+        let registry = registry.read();
+
+        reflect_score
+            .insert_resource(&mut world, &Score { value: 4 }, &registry)
+            .unwrap();
+        assert_eq!(world.get_resource::<Score>(), Some(&Score { value: 4 }));
+        assert!(reflect_score.reflect_resource(&world).is_some());
+
+        let taken = reflect_score.take_resource(&mut world).unwrap();
+        assert_eq!(taken.downcast_ref::<Score>(), Some(&Score { value: 4 }));
+        assert!(world.get_resource::<Score>().is_none());
+        assert!(reflect_score.take_resource(&mut world).is_none());
+
+        // removing an absent resource does nothing
+        reflect_score.remove_resource(&mut world);
+        reflect_score
+            .insert_resource(&mut world, &Score { value: 1 }, &registry)
+            .unwrap();
+        reflect_score.remove_resource(&mut world);
+        assert!(world.get_resource::<Score>().is_none());
+    }
+
+    #[test]
+    fn record_reflect_edits() {
+        let mut world = World::new();
+        let registration = <Health as bevy_reflect::GetTypeRegistration>::get_type_registration();
+        let reflect_health = registration.data::<ReflectComponent>().unwrap();
+        let registry = TypeRegistryArc::default();
+        let registry = registry.read();
+        let entity = world.spawn().id();
+
+        // nothing is recorded without the resource
+        let health = Health { current: 3, max: 5 };
+        reflect_health
+            .add_component(&mut world, entity, &health, &registry)
+            .unwrap();
+
+        world.insert_resource(ReflectEdits::default());
+        let wounded = Health { current: 1, max: 5 };
+        reflect_health.apply_component(&mut world, entity, &wounded);
+        assert_eq!(world.get::<Health>(entity), Some(&wounded));
+        let other = world.spawn().id();
+        reflect_health
+            .add_component(&mut world, other, &health, &registry)
+            .unwrap();
+
+        let mut edits = world.get_resource_mut::<ReflectEdits>().unwrap();
+        let type_id = std::any::TypeId::of::<Health>();
+        assert_eq!(
+            edits.drain().collect::<Vec<_>>(),
+            vec![
+                ReflectEdit { entity, type_id },
+                ReflectEdit {
+                    entity: other,
+                    type_id
+                },
+            ]
+        );
+        assert!(edits.edits().is_empty());
     }
 }
@@ -70,6 +70,20 @@ impl<'w> EntityRef<'w> {
         }
     }
 
+    /// Retrieves the change ticks of the component with the given [`ComponentId`], or [`None`] if
+    /// the entity doesn't have it.
+    #[inline]
+    pub fn get_change_ticks_by_id(&self, component_id: ComponentId) -> Option<&'w ComponentTicks> {
+        if !self.contains_id(component_id) {
+            return None;
+        }
+        // SAFE: the entity has the component, so `component_id` is valid
+        unsafe {
+            get_component_and_ticks(self.world, component_id, self.entity, self.location)
+                .map(|(_, ticks)| &*ticks)
+        }
+    }
+
     /// # Safety
     /// This allows aliased mutability. You must make sure this call does not result in multiple
     /// mutable references to the same component
@@ -89,6 +103,31 @@ impl<'w> EntityRef<'w> {
                 },
             })
     }
+
+    /// Like [`EntityRef::get_unchecked_mut`], with the id of `T` already looked up, for example
+    /// to get it from many entities.
+    ///
+    /// # Safety
+    /// This allows aliased mutability, see [`EntityRef::get_unchecked_mut`]. `component_id` must
+    /// be the id of `T` in the world of the entity.
+    #[inline]
+    pub(crate) unsafe fn get_unchecked_mut_by_id<T: Component>(
+        &self,
+        component_id: ComponentId,
+        last_change_tick: u32,
+        change_tick: u32,
+    ) -> Option<Mut<'w, T>> {
+        get_component_and_ticks(self.world, component_id, self.entity, self.location).map(
+            |(value, ticks)| Mut {
+                value: &mut *value.cast::<T>(),
+                ticks: Ticks {
+                    component_ticks: &mut *ticks,
+                    last_change_tick,
+                    change_tick,
+                },
+            },
+        )
+    }
 }
 
 pub struct EntityMut<'w> {
@@ -382,12 +382,63 @@ impl World {
     ///
     /// assert_eq!(entities.len(), 2);
     /// ```
+    ///
+    /// At most [`DEFAULT_SPAWN_BATCH_MAX_RESERVE`] entities are reserved up front, see
+    /// [`World::spawn_batch_with_max_reserve`] to change this limit.
+    ///
+    /// All the entities go to the archetype of the components of the [Bundle], whatever their
+    /// order in it. Matching archetypes across worlds needs more care, see
+    /// [the `Bundle` docs](Bundle#archetypes).
     pub fn spawn_batch<I>(&mut self, iter: I) -> SpawnBatchIter<'_, I::IntoIter>
     where
         I: IntoIterator,
         I::Item: Bundle,
     {
-        SpawnBatchIter::new(self, iter.into_iter())
+        self.spawn_batch_with_max_reserve(iter, DEFAULT_SPAWN_BATCH_MAX_RESERVE)
+    }
+
+    /// Like [`World::spawn_batch`], but reserves space for at most `max_reserve` entities up
+    /// front, regardless of what the iterator's [`Iterator::size_hint`] reports.
+    pub fn spawn_batch_with_max_reserve<I>(
+        &mut self,
+        iter: I,
+        max_reserve: usize,
+    ) -> SpawnBatchIter<'_, I::IntoIter>
+    where
+        I: IntoIterator,
+        I::Item: Bundle,
+    {
+        SpawnBatchIter::new(self, iter.into_iter(), max_reserve)
+    }
+
+    /// Spawns a batch of `count` entities, building the [Bundle] of each one with `make_bundle`
+    /// from its own [Entity], for example to store the id in one of its components.
+    ///
+    /// If `make_bundle` panics, the entities of the batch which weren't spawned yet aren't.
+    ///
+    /// ```
+    /// use bevy_ecs::{component::Component, entity::Entity, world::World};
+    ///
+    /// #[derive(Component)]
+    /// struct Me(Entity);
+    ///
+    /// let mut world = World::new();
+    /// let entities = world
+    ///     .spawn_batch_with_id(2, |entity| (Me(entity),))
+    ///     .collect::<Vec<Entity>>();
+    ///
+    /// assert_eq!(world.get::<Me>(entities[1]).unwrap().0, entities[1]);
+    /// ```
+    pub fn spawn_batch_with_id<B, F>(
+        &mut self,
+        count: usize,
+        make_bundle: F,
+    ) -> SpawnBatchWithIdIter<'_, B, F>
+    where
+        B: Bundle,
+        F: FnMut(Entity) -> B,
+    {
+        SpawnBatchWithIdIter::new(self, count, make_bundle)
     }
 
     /// Retrieves a reference to the given `entity`'s [Component] of the given type.
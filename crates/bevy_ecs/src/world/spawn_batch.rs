@@ -1,9 +1,15 @@
 use crate::{
     bundle::{Bundle, BundleSpawner},
-    entity::Entity,
+    entity::{Entities, Entity},
     world::World,
 };
 
+/// The default maximum number of entities [`World::spawn_batch`] reserves up front.
+///
+/// Space is reserved based on the iterator's [`Iterator::size_hint`], which may be wildly
+/// overestimated. Storage still grows past this limit as entities are spawned.
+pub const DEFAULT_SPAWN_BATCH_MAX_RESERVE: usize = 1 << 16;
+
 pub struct SpawnBatchIter<'w, I>
 where
     I: Iterator,
@@ -19,32 +25,20 @@ where
     I::Item: Bundle,
 {
     #[inline]
-    pub(crate) fn new(world: &'w mut World, iter: I) -> Self {
-        // Ensure all entity allocations are accounted for so `self.entities` can realloc if
-        // necessary
-        world.flush();
-
+    pub(crate) fn new(world: &'w mut World, iter: I, max_reserve: usize) -> Self {
         let (lower, upper) = iter.size_hint();
-        let length = upper.unwrap_or(lower);
-
-        let bundle_info = world
-            .bundles
-            .init_info::<I::Item>(&mut world.components, &mut world.storages);
-        world.entities.reserve(length as u32);
-        let mut spawner = bundle_info.get_bundle_spawner(
-            &mut world.entities,
-            &mut world.archetypes,
-            &mut world.components,
-            &mut world.storages,
-            *world.change_tick.get_mut(),
-        );
-        spawner.reserve_storage(length);
-
+        let length = upper.unwrap_or(lower).min(max_reserve);
         Self {
             inner: iter,
-            spawner,
+            spawner: batch_spawner::<I::Item>(world, length),
         }
     }
+
+    /// Spawns every remaining entity of the batch, calling `f` with each of them as soon as it is
+    /// spawned, without collecting them.
+    pub fn for_each_spawned(self, f: impl FnMut(Entity)) {
+        Iterator::for_each(self, f);
+    }
 }
 
 impl<I> Drop for SpawnBatchIter<'_, I>
@@ -75,6 +69,26 @@ where
     }
 }
 
+fn batch_spawner<B: Bundle>(world: &mut World, length: usize) -> BundleSpawner<'_, '_> {
+    // Ensure all entity allocations are accounted for so `self.entities` can realloc if
+    // necessary
+    world.flush();
+
+    let bundle_info = world
+        .bundles
+        .init_info::<B>(&mut world.components, &mut world.storages);
+    world.entities.reserve(length as u32);
+    let mut spawner = bundle_info.get_bundle_spawner(
+        &mut world.entities,
+        &mut world.archetypes,
+        &mut world.components,
+        &mut world.storages,
+        *world.change_tick.get_mut(),
+    );
+    spawner.reserve_storage(length);
+    spawner
+}
+
 impl<I, T> ExactSizeIterator for SpawnBatchIter<'_, I>
 where
     I: ExactSizeIterator<Item = T>,
@@ -84,3 +98,157 @@ where
         self.inner.len()
     }
 }
+
+/// Spawns the entities of a batch a chunk at a time, for example to spread a large batch over
+/// several frames instead of spawning it all at once.
+///
+/// ```
+/// use bevy_ecs::{component::Component, world::{ChunkedSpawn, World}};
+///
+/// #[derive(Component)]
+/// struct Tree;
+///
+/// let mut world = World::new();
+/// let mut trees = ChunkedSpawn::new((0..250).map(|_| (Tree,)));
+/// // typically once per frame
+/// while trees.remaining() > 0 {
+///     trees.spawn_chunk(&mut world, 100);
+/// }
+/// assert_eq!(world.entities().len(), 250);
+/// ```
+pub struct ChunkedSpawn<I>
+where
+    I: Iterator,
+    I::Item: Bundle,
+{
+    inner: I,
+}
+
+impl<I> ChunkedSpawn<I>
+where
+    I: Iterator,
+    I::Item: Bundle,
+{
+    pub fn new(iter: impl IntoIterator<IntoIter = I>) -> Self {
+        Self {
+            inner: iter.into_iter(),
+        }
+    }
+
+    /// Spawns the next `chunk_size` entities of the batch, or the remaining ones if there are
+    /// less of them.
+    ///
+    /// Like with [`World::spawn_batch`], the entities are spawned as the returned iterator is
+    /// consumed or dropped, after reserving space for the whole chunk.
+    pub fn spawn_chunk<'w>(
+        &'w mut self,
+        world: &'w mut World,
+        chunk_size: usize,
+    ) -> SpawnBatchIter<'w, std::iter::Take<&'w mut I>> {
+        world.spawn_batch_with_max_reserve(self.inner.by_ref().take(chunk_size), chunk_size)
+    }
+}
+
+impl<I> ChunkedSpawn<I>
+where
+    I: ExactSizeIterator,
+    I::Item: Bundle,
+{
+    /// The number of entities of the batch left to spawn.
+    pub fn remaining(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+/// An iterator spawning a batch of entities, each with a [`Bundle`] built from its own [`Entity`].
+///
+/// Returned by [`World::spawn_batch_with_id`].
+pub struct SpawnBatchWithIdIter<'w, B, F>
+where
+    B: Bundle,
+    F: FnMut(Entity) -> B,
+{
+    remaining: usize,
+    make_bundle: F,
+    spawner: BundleSpawner<'w, 'w>,
+}
+
+impl<'w, B, F> SpawnBatchWithIdIter<'w, B, F>
+where
+    B: Bundle,
+    F: FnMut(Entity) -> B,
+{
+    #[inline]
+    pub(crate) fn new(world: &'w mut World, count: usize, make_bundle: F) -> Self {
+        Self {
+            remaining: count,
+            make_bundle,
+            spawner: batch_spawner::<B>(world, count),
+        }
+    }
+}
+
+impl<B, F> Drop for SpawnBatchWithIdIter<'_, B, F>
+where
+    B: Bundle,
+    F: FnMut(Entity) -> B,
+{
+    fn drop(&mut self) {
+        // Don't spawn the remaining entities when unwinding, as building their bundle may panic
+        // again, for example if the panic came from `make_bundle`. They are only allocated as
+        // they are spawned, so the world is left consistent.
+        if std::thread::panicking() {
+            return;
+        }
+        for _ in self {}
+    }
+}
+
+impl<B, F> Iterator for SpawnBatchWithIdIter<'_, B, F>
+where
+    B: Bundle,
+    F: FnMut(Entity) -> B,
+{
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Entity> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let entity = self.spawner.entities.alloc();
+        // The entity is freed again if building its bundle panics, so that it isn't left
+        // allocated without a location
+        let guard = FreeOnUnwind {
+            entities: self.spawner.entities,
+            entity,
+        };
+        let bundle = (self.make_bundle)(entity);
+        std::mem::forget(guard);
+        // SAFE: entity is allocated (but non-existent), and bundle matches spawner type
+        unsafe { self.spawner.spawn_non_existent(entity, bundle) };
+        Some(entity)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<B, F> ExactSizeIterator for SpawnBatchWithIdIter<'_, B, F>
+where
+    B: Bundle,
+    F: FnMut(Entity) -> B,
+{
+}
+
+struct FreeOnUnwind<'a> {
+    entities: &'a mut Entities,
+    entity: Entity,
+}
+
+impl Drop for FreeOnUnwind<'_> {
+    fn drop(&mut self) {
+        self.entities.free(self.entity);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{
-    AlphaMode, DrawMesh, MeshPipeline, MeshPipelineKey, MeshUniform, SetMeshBindGroup,
-    SetMeshViewBindGroup,
+    AlphaMode, DepthBias, DrawMesh, MeshFlags, MeshPipeline, MeshPipelineKey, MeshUniform,
+    PointSprites, SetMeshBindGroup, SetMeshFeaturesBindGroup, SetMeshViewBindGroup, StencilConfig,
+    ThickLines, VertexPulling, ViewDepth,
 };
 use bevy_app::{App, Plugin};
 use bevy_asset::{AddAsset, Asset, AssetServer, Handle};
@@ -65,6 +66,13 @@ pub trait Material: Asset + RenderAsset {
         AlphaMode::Opaque
     }
 
+    /// Returns whether this material skips lighting, in which case the `UNLIT` shader def is
+    /// pushed to its shaders. Defaults to `false`.
+    #[allow(unused_variables)]
+    fn unlit(material: &<Self as RenderAsset>::PreparedAsset) -> bool {
+        false
+    }
+
     /// The dynamic uniform indices to set for the given `material`'s [`BindGroup`].
     /// Defaults to an empty array / no dynamic uniform indices.
     #[allow(unused_variables)]
@@ -98,6 +106,11 @@ impl<M: Material> SpecializedMaterial for M {
         <M as Material>::alpha_mode(material)
     }
 
+    #[inline]
+    fn unlit(material: &<Self as RenderAsset>::PreparedAsset) -> bool {
+        <M as Material>::unlit(material)
+    }
+
     #[inline]
     fn vertex_shader(asset_server: &AssetServer) -> Option<Handle<Shader>> {
         <M as Material>::vertex_shader(asset_server)
@@ -158,6 +171,13 @@ pub trait SpecializedMaterial: Asset + RenderAsset {
         AlphaMode::Opaque
     }
 
+    /// Returns whether this material skips lighting, in which case the `UNLIT` shader def is
+    /// pushed to its shaders. Defaults to `false`.
+    #[allow(unused_variables)]
+    fn unlit(material: &<Self as RenderAsset>::PreparedAsset) -> bool {
+        false
+    }
+
     /// The dynamic uniform indices to set for the given `material`'s [`BindGroup`].
     /// Defaults to an empty array / no dynamic uniform indices.
     #[allow(unused_variables)]
@@ -194,6 +214,16 @@ impl<M: SpecializedMaterial> Plugin for MaterialPlugin<M> {
     }
 }
 
+/// The pipeline of the meshes with a material `M`.
+///
+/// To avoid a hitch the first time a combination of mesh and material is drawn, its pipeline can
+/// be compiled ahead of time with [`SpecializedPipelines::prewarm`], from a render app system
+/// while a loading screen is displayed. The keys are built the same way
+/// [`queue_material_meshes`] builds them: start from [`MeshPipelineKey::from_msaa_samples`] with
+/// the current [`Msaa`] samples, add [`MeshPipelineKey::from_primitive_topology`] for each
+/// topology the scene's meshes use and the flags matching their attributes and components, such
+/// as [`MeshPipelineKey::VERTEX_TANGENTS`], and pair it with [`SpecializedMaterial::key`] of each
+/// material, and the [`StencilConfig`] of the meshes, if any.
 pub struct MaterialPipeline<M: SpecializedMaterial> {
     pub mesh_pipeline: MeshPipeline,
     pub material_layout: BindGroupLayout,
@@ -203,10 +233,15 @@ pub struct MaterialPipeline<M: SpecializedMaterial> {
 }
 
 impl<M: SpecializedMaterial> SpecializedPipeline for MaterialPipeline<M> {
-    type Key = (MeshPipelineKey, M::Key);
+    /// The key of the mesh pipeline, of the material, and the stencil configuration of the mesh,
+    /// with [`MeshPipelineKey::STENCIL`].
+    type Key = (MeshPipelineKey, M::Key, Option<StencilConfig>);
 
     fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
-        let mut descriptor = self.mesh_pipeline.specialize(key.0);
+        let mut descriptor = match &key.2 {
+            Some(stencil) => self.mesh_pipeline.specialize_with_stencil(key.0, stencil),
+            None => self.mesh_pipeline.specialize(key.0),
+        };
         if let Some(vertex_shader) = &self.vertex_shader {
             descriptor.vertex.shader = vertex_shader.clone();
         }
@@ -214,11 +249,15 @@ impl<M: SpecializedMaterial> SpecializedPipeline for MaterialPipeline<M> {
         if let Some(fragment_shader) = &self.fragment_shader {
             descriptor.fragment.as_mut().unwrap().shader = fragment_shader.clone();
         }
-        descriptor.layout = Some(vec![
+        let mut layout = vec![
             self.mesh_pipeline.view_layout.clone(),
             self.material_layout.clone(),
             self.mesh_pipeline.mesh_layout.clone(),
-        ]);
+        ];
+        if key.0.uses_mesh_features() {
+            layout.push(self.mesh_pipeline.mesh_features_layout.clone());
+        }
+        descriptor.layout = Some(layout);
 
         M::specialize(key.1, &mut descriptor);
         descriptor
@@ -246,6 +285,7 @@ type DrawMaterial<M> = (
     SetMeshViewBindGroup<0>,
     SetMaterialBindGroup<M, 1>,
     SetMeshBindGroup<2>,
+    SetMeshFeaturesBindGroup<3>,
     DrawMesh,
 );
 
@@ -269,7 +309,7 @@ impl<M: SpecializedMaterial, const I: usize> EntityRenderCommand for SetMaterial
     }
 }
 
-#[allow(clippy::too_many_arguments)]
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn queue_material_meshes<M: SpecializedMaterial>(
     opaque_draw_functions: Res<DrawFunctions<Opaque3d>>,
     alpha_mask_draw_functions: Res<DrawFunctions<AlphaMask3d>>,
@@ -280,7 +320,16 @@ pub fn queue_material_meshes<M: SpecializedMaterial>(
     msaa: Res<Msaa>,
     render_meshes: Res<RenderAssets<Mesh>>,
     render_materials: Res<RenderAssets<M>>,
-    material_meshes: Query<(&Handle<M>, &Handle<Mesh>, &MeshUniform)>,
+    material_meshes: Query<(
+        &Handle<M>,
+        &Handle<Mesh>,
+        &MeshUniform,
+        Option<&DepthBias>,
+        Option<&StencilConfig>,
+        Option<&VertexPulling>,
+        Option<&PointSprites>,
+        Option<&ThickLines>,
+    )>,
     mut views: Query<(
         &ExtractedView,
         &VisibleEntities,
@@ -305,13 +354,20 @@ pub fn queue_material_meshes<M: SpecializedMaterial>(
             .get_id::<DrawMaterial<M>>()
             .unwrap();
 
-        let inverse_view_matrix = view.transform.compute_matrix().inverse();
-        let inverse_view_row_2 = inverse_view_matrix.row(2);
+        let view_depth = ViewDepth::new(view);
         let mesh_key = MeshPipelineKey::from_msaa_samples(msaa.samples);
 
         for visible_entity in &visible_entities.entities {
-            if let Ok((material_handle, mesh_handle, mesh_uniform)) =
-                material_meshes.get(*visible_entity)
+            if let Ok((
+                material_handle,
+                mesh_handle,
+                mesh_uniform,
+                depth_bias,
+                stencil,
+                vertex_pulling,
+                point_sprites,
+                thick_lines,
+            )) = material_meshes.get(*visible_entity)
             {
                 if let Some(material) = render_materials.get(material_handle) {
                     let mut mesh_key = mesh_key;
@@ -319,24 +375,41 @@ pub fn queue_material_meshes<M: SpecializedMaterial>(
                         if mesh.has_tangents {
                             mesh_key |= MeshPipelineKey::VERTEX_TANGENTS;
                         }
+                        if mesh.has_normals_2 {
+                            mesh_key |= MeshPipelineKey::VERTEX_NORMALS_2;
+                        }
                         mesh_key |=
                             MeshPipelineKey::from_primitive_topology(mesh.primitive_topology);
+                        mesh_key |= MeshPipelineKey::from_strip_index_format(
+                            mesh.primitive_topology,
+                            mesh.buffer_info.index_format(),
+                        );
                     }
                     let alpha_mode = M::alpha_mode(material);
                     if let AlphaMode::Blend = alpha_mode {
                         mesh_key |= MeshPipelineKey::TRANSPARENT_MAIN_PASS
                     }
+                    if M::unlit(material) {
+                        mesh_key |= MeshPipelineKey::UNLIT;
+                    }
+                    let mesh_key = main_pass_mesh_key(
+                        mesh_key,
+                        mesh_uniform,
+                        depth_bias,
+                        stencil,
+                        vertex_pulling,
+                        point_sprites,
+                        thick_lines,
+                    );
 
                     let specialized_key = M::key(material);
                     let pipeline_id = pipelines.specialize(
                         &mut pipeline_cache,
                         &material_pipeline,
-                        (mesh_key, specialized_key),
+                        (mesh_key, specialized_key, stencil.copied()),
                     );
 
-                    // NOTE: row 2 of the inverse view matrix dotted with column 3 of the model matrix
-                    // gives the z component of translation of the mesh in view space
-                    let mesh_z = inverse_view_row_2.dot(mesh_uniform.transform.col(3));
+                    let mesh_z = view_depth.mesh_z(mesh_uniform);
                     match alpha_mode {
                         AlphaMode::Opaque => {
                             opaque_phase.add(Opaque3d {
@@ -380,3 +453,142 @@ pub fn queue_material_meshes<M: SpecializedMaterial>(
         }
     }
 }
+
+/// Adds the bits selected by the components of a material mesh to its `mesh_key`, for the main 3d
+/// passes.
+///
+/// The bits which change how the mesh is drawn, [`MeshPipelineKey::VERTEX_PULLING`],
+/// [`MeshPipelineKey::POINT_SPRITES`] and [`MeshPipelineKey::THICK_LINES`], are derived from the
+/// same components [`DrawMesh`] reads, so that the pipeline matches the draw.
+fn main_pass_mesh_key(
+    mut mesh_key: MeshPipelineKey,
+    mesh_uniform: &MeshUniform,
+    depth_bias: Option<&DepthBias>,
+    stencil: Option<&StencilConfig>,
+    vertex_pulling: Option<&VertexPulling>,
+    point_sprites: Option<&PointSprites>,
+    thick_lines: Option<&ThickLines>,
+) -> MeshPipelineKey {
+    if stencil.is_some() {
+        mesh_key |= MeshPipelineKey::STENCIL;
+    }
+    if mesh_uniform.flags & MeshFlags::CLIP_PLANE.bits() != 0 {
+        mesh_key |= MeshPipelineKey::CLIP_PLANE;
+    }
+    if mesh_uniform.flags & MeshFlags::DITHERED_ALPHA.bits() != 0 {
+        mesh_key |= MeshPipelineKey::DITHERED_ALPHA;
+    }
+    if let Some(depth_bias) = depth_bias {
+        mesh_key |= MeshPipelineKey::from_depth_bias(*depth_bias);
+    }
+    if vertex_pulling.is_some() {
+        mesh_key |= MeshPipelineKey::VERTEX_PULLING;
+    }
+    if point_sprites.is_some() {
+        mesh_key |= MeshPipelineKey::POINT_SPRITES;
+    }
+    if thick_lines.is_some() {
+        mesh_key |= MeshPipelineKey::THICK_LINES;
+    }
+    mesh_key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::main_pass_mesh_key;
+    use crate::{
+        DepthBias, MeshFlags, MeshPipelineKey, MeshUniform, PointSprites, StencilConfig,
+        ThickLines, VertexPulling,
+    };
+    use bevy_math::Mat4;
+    use bevy_render::render_resource::{PrimitiveTopology, StencilFaceState};
+
+    fn mesh_uniform(flags: MeshFlags) -> MeshUniform {
+        MeshUniform {
+            transform: Mat4::IDENTITY,
+            inverse_transpose_model: Mat4::IDENTITY,
+            flags: flags.bits(),
+        }
+    }
+
+    #[test]
+    fn main_pass_stencil_meshes() {
+        let key = MeshPipelineKey::from_msaa_samples(4)
+            | MeshPipelineKey::from_primitive_topology(PrimitiveTopology::TriangleList);
+        let uniform = mesh_uniform(MeshFlags::SHADOW_RECEIVER | MeshFlags::CLIP_PLANE);
+
+        let mesh_key =
+            main_pass_mesh_key(key, &uniform, Some(&DepthBias(2)), None, None, None, None);
+        assert!(mesh_key.contains(MeshPipelineKey::CLIP_PLANE));
+        // the clip plane is in the mesh features uniform, bound at group 3
+        assert!(mesh_key.uses_mesh_features());
+        assert_eq!(mesh_key.depth_bias(), 2);
+        assert!(!mesh_key.contains(MeshPipelineKey::STENCIL));
+
+        // the meshes with a stencil configuration are drawn in the main passes, whose depth
+        // texture has a stencil aspect
+        let stencil = StencilConfig {
+            front: StencilFaceState::IGNORE,
+            back: StencilFaceState::IGNORE,
+            read_mask: 0xff,
+            write_mask: 0xff,
+            reference: 1,
+        };
+        let mesh_key = main_pass_mesh_key(key, &uniform, None, Some(&stencil), None, None, None);
+        assert!(mesh_key.contains(MeshPipelineKey::STENCIL));
+        assert!(mesh_key.contains(MeshPipelineKey::CLIP_PLANE));
+    }
+
+    #[test]
+    fn main_pass_vertex_pulling() {
+        let key = MeshPipelineKey::from_msaa_samples(1)
+            | MeshPipelineKey::from_primitive_topology(PrimitiveTopology::TriangleList);
+        let uniform = mesh_uniform(MeshFlags::SHADOW_RECEIVER);
+
+        let mesh_key = main_pass_mesh_key(key, &uniform, None, None, None, None, None);
+        assert!(!mesh_key.contains(MeshPipelineKey::VERTEX_PULLING));
+        // DrawMesh doesn't bind the vertex buffer of the meshes with VertexPulling
+        let mesh_key =
+            main_pass_mesh_key(key, &uniform, None, None, Some(&VertexPulling), None, None);
+        assert!(mesh_key.contains(MeshPipelineKey::VERTEX_PULLING));
+    }
+
+    #[test]
+    fn main_pass_point_sprites() {
+        let key = MeshPipelineKey::from_msaa_samples(1)
+            | MeshPipelineKey::from_primitive_topology(PrimitiveTopology::PointList);
+        let uniform = mesh_uniform(MeshFlags::SHADOW_RECEIVER);
+
+        let mesh_key = main_pass_mesh_key(key, &uniform, None, None, None, None, None);
+        assert_eq!(mesh_key.pipeline_topology(), PrimitiveTopology::PointList);
+        // DrawMesh draws each point of the meshes with PointSprites as an instance of a quad
+        let point_sprites = PointSprites { size: 4.0 };
+        let mesh_key =
+            main_pass_mesh_key(key, &uniform, None, None, None, Some(&point_sprites), None);
+        assert!(mesh_key.contains(MeshPipelineKey::POINT_SPRITES));
+        assert_eq!(
+            mesh_key.pipeline_topology(),
+            PrimitiveTopology::TriangleList
+        );
+    }
+
+    #[test]
+    fn main_pass_thick_lines() {
+        let key = MeshPipelineKey::from_msaa_samples(1)
+            | MeshPipelineKey::from_primitive_topology(PrimitiveTopology::LineList);
+        let uniform = mesh_uniform(MeshFlags::SHADOW_RECEIVER);
+
+        let mesh_key = main_pass_mesh_key(key, &uniform, None, None, None, None, None);
+        assert_eq!(mesh_key.pipeline_topology(), PrimitiveTopology::LineList);
+        // DrawMesh draws each segment of the meshes with ThickLines as an instance of a quad
+        let thick_lines = ThickLines { width: 3.0 };
+        let mesh_key =
+            main_pass_mesh_key(key, &uniform, None, None, None, None, Some(&thick_lines));
+        assert!(mesh_key.contains(MeshPipelineKey::THICK_LINES));
+        assert!(mesh_key.uses_mesh_features());
+        assert_eq!(
+            mesh_key.pipeline_topology(),
+            PrimitiveTopology::TriangleList
+        );
+    }
+}
@@ -477,4 +477,9 @@ impl SpecializedMaterial for StandardMaterial {
     fn alpha_mode(render_asset: &<Self as RenderAsset>::PreparedAsset) -> AlphaMode {
         render_asset.alpha_mode
     }
+
+    #[inline]
+    fn unlit(render_asset: &<Self as RenderAsset>::PreparedAsset) -> bool {
+        render_asset.flags.contains(StandardMaterialFlags::UNLIT)
+    }
 }
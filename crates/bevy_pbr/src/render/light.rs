@@ -1,7 +1,8 @@
+use super::mesh::mesh_vertex_attributes;
 use crate::{
     AmbientLight, Clusters, CubemapVisibleEntities, DirectionalLight, DirectionalLightShadowMap,
-    DrawMesh, MeshPipeline, NotShadowCaster, PointLight, PointLightShadowMap, SetMeshBindGroup,
-    VisiblePointLights, SHADOW_SHADER_HANDLE,
+    DrawMesh, MeshPipeline, NotShadowCaster, PointLight, PointLightShadowMap, PointSprites,
+    SetMeshBindGroup, ThickLines, VertexPulling, VisiblePointLights, SHADOW_SHADER_HANDLE,
 };
 use bevy_asset::Handle;
 use bevy_core::FloatOrd;
@@ -216,13 +217,17 @@ bitflags::bitflags! {
     pub struct ShadowPipelineKey: u32 {
         const NONE               = 0;
         const VERTEX_TANGENTS    = (1 << 0);
+        const VERTEX_NORMALS_2   = (1 << 1);
         const PRIMITIVE_TOPOLOGY_RESERVED_BITS = ShadowPipelineKey::PRIMITIVE_TOPOLOGY_MASK_BITS << ShadowPipelineKey::PRIMITIVE_TOPOLOGY_SHIFT_BITS;
+        const STRIP_INDEX_FORMAT_RESERVED_BITS = ShadowPipelineKey::STRIP_INDEX_FORMAT_MASK_BITS << ShadowPipelineKey::STRIP_INDEX_FORMAT_SHIFT_BITS;
     }
 }
 
 impl ShadowPipelineKey {
     const PRIMITIVE_TOPOLOGY_MASK_BITS: u32 = 0b111;
     const PRIMITIVE_TOPOLOGY_SHIFT_BITS: u32 = 32 - 3;
+    const STRIP_INDEX_FORMAT_MASK_BITS: u32 = 0b11;
+    const STRIP_INDEX_FORMAT_SHIFT_BITS: u32 = Self::PRIMITIVE_TOPOLOGY_SHIFT_BITS - 2;
 
     pub fn from_primitive_topology(primitive_topology: PrimitiveTopology) -> Self {
         let primitive_topology_bits = ((primitive_topology as u32)
@@ -243,68 +248,41 @@ impl ShadowPipelineKey {
             _ => PrimitiveTopology::default(),
         }
     }
+
+    /// Like [`MeshPipelineKey::from_strip_index_format`](crate::MeshPipelineKey::from_strip_index_format),
+    /// the index format is only kept for the strip topologies of indexed meshes.
+    pub fn from_strip_index_format(
+        primitive_topology: PrimitiveTopology,
+        index_format: Option<IndexFormat>,
+    ) -> Self {
+        let strip_index_format_bits = match index_format {
+            Some(_) if !primitive_topology.is_strip() => 0,
+            Some(IndexFormat::Uint16) => 1,
+            Some(IndexFormat::Uint32) => 2,
+            None => 0,
+        };
+        Self::from_bits(strip_index_format_bits << Self::STRIP_INDEX_FORMAT_SHIFT_BITS).unwrap()
+    }
+
+    pub fn strip_index_format(&self) -> Option<IndexFormat> {
+        let strip_index_format_bits =
+            (self.bits >> Self::STRIP_INDEX_FORMAT_SHIFT_BITS) & Self::STRIP_INDEX_FORMAT_MASK_BITS;
+        match strip_index_format_bits {
+            1 => Some(IndexFormat::Uint16),
+            2 => Some(IndexFormat::Uint32),
+            _ => None,
+        }
+    }
 }
 
 impl SpecializedPipeline for ShadowPipeline {
     type Key = ShadowPipelineKey;
 
     fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
-        let (vertex_array_stride, vertex_attributes) =
-            if key.contains(ShadowPipelineKey::VERTEX_TANGENTS) {
-                (
-                    48,
-                    vec![
-                        // Position (GOTCHA! Vertex_Position isn't first in the buffer due to how Mesh sorts attributes (alphabetically))
-                        VertexAttribute {
-                            format: VertexFormat::Float32x3,
-                            offset: 12,
-                            shader_location: 0,
-                        },
-                        // Normal
-                        VertexAttribute {
-                            format: VertexFormat::Float32x3,
-                            offset: 0,
-                            shader_location: 1,
-                        },
-                        // Uv (GOTCHA! uv is no longer third in the buffer due to how Mesh sorts attributes (alphabetically))
-                        VertexAttribute {
-                            format: VertexFormat::Float32x2,
-                            offset: 40,
-                            shader_location: 2,
-                        },
-                        // Tangent
-                        VertexAttribute {
-                            format: VertexFormat::Float32x4,
-                            offset: 24,
-                            shader_location: 3,
-                        },
-                    ],
-                )
-            } else {
-                (
-                    32,
-                    vec![
-                        // Position (GOTCHA! Vertex_Position isn't first in the buffer due to how Mesh sorts attributes (alphabetically))
-                        VertexAttribute {
-                            format: VertexFormat::Float32x3,
-                            offset: 12,
-                            shader_location: 0,
-                        },
-                        // Normal
-                        VertexAttribute {
-                            format: VertexFormat::Float32x3,
-                            offset: 0,
-                            shader_location: 1,
-                        },
-                        // Uv
-                        VertexAttribute {
-                            format: VertexFormat::Float32x2,
-                            offset: 24,
-                            shader_location: 2,
-                        },
-                    ],
-                )
-            };
+        let (vertex_array_stride, vertex_attributes) = mesh_vertex_attributes(
+            key.contains(ShadowPipelineKey::VERTEX_TANGENTS),
+            key.contains(ShadowPipelineKey::VERTEX_NORMALS_2),
+        );
         RenderPipelineDescriptor {
             vertex: VertexState {
                 shader: SHADOW_SHADER_HANDLE.typed::<Shader>(),
@@ -320,7 +298,7 @@ impl SpecializedPipeline for ShadowPipeline {
             layout: Some(vec![self.view_layout.clone(), self.mesh_layout.clone()]),
             primitive: PrimitiveState {
                 topology: key.primitive_topology(),
-                strip_index_format: None,
+                strip_index_format: key.strip_index_format(),
                 front_face: FrontFace::Ccw,
                 cull_mode: None,
                 unclipped_depth: false,
@@ -1086,11 +1064,24 @@ pub fn queue_shadow_view_bind_group(
     }
 }
 
+/// The meshes queued to the shadow phases.
+///
+/// [`DrawMesh`] doesn't bind the vertex buffer of the meshes with [`VertexPulling`], and draws the
+/// points of the meshes with [`PointSprites`] and the segments of the meshes with [`ThickLines`]
+/// as instances of a quad. The shadow pipeline isn't specialized for any of them, so these meshes
+/// don't cast shadows.
+type ShadowCasterFilter = (
+    Without<NotShadowCaster>,
+    Without<VertexPulling>,
+    Without<PointSprites>,
+    Without<ThickLines>,
+);
+
 #[allow(clippy::too_many_arguments)]
 pub fn queue_shadows(
     shadow_draw_functions: Res<DrawFunctions<Shadow>>,
     shadow_pipeline: Res<ShadowPipeline>,
-    casting_meshes: Query<&Handle<Mesh>, Without<NotShadowCaster>>,
+    casting_meshes: Query<&Handle<Mesh>, ShadowCasterFilter>,
     render_meshes: Res<RenderAssets<Mesh>>,
     mut pipelines: ResMut<SpecializedPipelines<ShadowPipeline>>,
     mut pipeline_cache: ResMut<RenderPipelineCache>,
@@ -1128,7 +1119,14 @@ pub fn queue_shadows(
                         if mesh.has_tangents {
                             key |= ShadowPipelineKey::VERTEX_TANGENTS;
                         }
+                        if mesh.has_normals_2 {
+                            key |= ShadowPipelineKey::VERTEX_NORMALS_2;
+                        }
                         key |= ShadowPipelineKey::from_primitive_topology(mesh.primitive_topology);
+                        key |= ShadowPipelineKey::from_strip_index_format(
+                            mesh.primitive_topology,
+                            mesh.buffer_info.index_format(),
+                        );
                     }
                     let pipeline_id =
                         pipelines.specialize(&mut pipeline_cache, &shadow_pipeline, key);
@@ -1279,3 +1277,61 @@ impl<const I: usize> EntityRenderCommand for SetShadowViewBindGroup<I> {
         RenderCommandResult::Success
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ShadowCasterFilter, ShadowPipelineKey};
+    use crate::{NotShadowCaster, PointSprites, ThickLines, VertexPulling};
+    use bevy_asset::Handle;
+    use bevy_ecs::{entity::Entity, world::World};
+    use bevy_render::{
+        mesh::Mesh,
+        render_resource::{IndexFormat, PrimitiveTopology},
+    };
+
+    #[test]
+    fn vertex_pulled_and_quad_expanded_meshes_cast_no_shadow() {
+        let mut world = World::new();
+        let caster = world.spawn().insert(Handle::<Mesh>::default()).id();
+        world
+            .spawn()
+            .insert_bundle((Handle::<Mesh>::default(), VertexPulling));
+        world
+            .spawn()
+            .insert_bundle((Handle::<Mesh>::default(), PointSprites { size: 4.0 }));
+        world
+            .spawn()
+            .insert_bundle((Handle::<Mesh>::default(), ThickLines { width: 4.0 }));
+        world
+            .spawn()
+            .insert_bundle((Handle::<Mesh>::default(), NotShadowCaster));
+        let casting_meshes = world
+            .query_filtered::<Entity, ShadowCasterFilter>()
+            .iter(&world)
+            .collect::<Vec<_>>();
+        assert_eq!(casting_meshes, vec![caster]);
+    }
+
+    #[test]
+    fn shadow_key_strip_index_format() {
+        for index_format in [IndexFormat::Uint16, IndexFormat::Uint32] {
+            for topology in [
+                PrimitiveTopology::TriangleStrip,
+                PrimitiveTopology::LineStrip,
+            ] {
+                let key = ShadowPipelineKey::VERTEX_TANGENTS
+                    | ShadowPipelineKey::from_primitive_topology(topology)
+                    | ShadowPipelineKey::from_strip_index_format(topology, Some(index_format));
+                assert_eq!(key.strip_index_format(), Some(index_format));
+                assert_eq!(key.primitive_topology(), topology);
+                assert!(key.contains(ShadowPipelineKey::VERTEX_TANGENTS));
+            }
+        }
+
+        let list_key = ShadowPipelineKey::from_strip_index_format(
+            PrimitiveTopology::TriangleList,
+            Some(IndexFormat::Uint32),
+        );
+        assert_eq!(list_key.strip_index_format(), None);
+    }
+}
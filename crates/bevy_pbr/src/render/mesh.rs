@@ -4,24 +4,31 @@ use crate::{
 };
 use bevy_app::Plugin;
 use bevy_asset::{Assets, Handle, HandleUntyped};
+use bevy_core_pipeline::MAIN_PASS_3D_DEPTH_FORMAT;
 use bevy_ecs::{
     prelude::*,
+    query::QueryItem,
     system::{lifetimeless::*, SystemParamItem},
 };
-use bevy_math::{Mat4, Size};
+use bevy_math::{Mat4, Size, Vec4};
 use bevy_reflect::TypeUuid;
 use bevy_render::{
-    mesh::{GpuBufferInfo, Mesh},
+    color::Color,
+    mesh::{GpuBufferInfo, Mesh, VertexFormatSize},
     render_asset::RenderAssets,
-    render_component::{ComponentUniforms, DynamicUniformIndex, UniformComponentPlugin},
+    render_component::{
+        ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+        UniformComponentPlugin,
+    },
     render_phase::{EntityRenderCommand, RenderCommandResult, TrackedRenderPass},
     render_resource::{std140::AsStd140, *},
     renderer::{RenderDevice, RenderQueue},
     texture::{BevyDefault, GpuImage, Image, TextureFormatPixelInfo},
-    view::{ComputedVisibility, ViewUniform, ViewUniformOffset, ViewUniforms},
+    view::{ComputedVisibility, ExtractedView, ViewUniform, ViewUniformOffset, ViewUniforms},
     RenderApp, RenderStage,
 };
 use bevy_transform::components::GlobalTransform;
+use std::ops::Range;
 
 #[derive(Default)]
 pub struct MeshRenderPlugin;
@@ -30,6 +37,8 @@ pub const MESH_VIEW_BIND_GROUP_HANDLE: HandleUntyped =
     HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 9076678235888822571);
 pub const MESH_STRUCT_HANDLE: HandleUntyped =
     HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 2506024101911992377);
+pub const MESH_FUNCTIONS_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 6300874327833745635);
 pub const MESH_SHADER_HANDLE: HandleUntyped =
     HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 3252377289100772450);
 
@@ -45,18 +54,32 @@ impl Plugin for MeshRenderPlugin {
             Shader::from_wgsl(include_str!("mesh_struct.wgsl"))
                 .with_import_path("bevy_pbr::mesh_struct"),
         );
+        shaders.set_untracked(
+            MESH_FUNCTIONS_HANDLE,
+            Shader::from_wgsl(include_str!("mesh_functions.wgsl"))
+                .with_import_path("bevy_pbr::mesh_functions"),
+        );
         shaders.set_untracked(
             MESH_VIEW_BIND_GROUP_HANDLE,
             Shader::from_wgsl(include_str!("mesh_view_bind_group.wgsl"))
                 .with_import_path("bevy_pbr::mesh_view_bind_group"),
         );
 
-        app.add_plugin(UniformComponentPlugin::<MeshUniform>::default());
+        app.add_plugin(UniformComponentPlugin::<MeshUniform>::default())
+            .add_plugin(UniformComponentPlugin::<MeshFeaturesUniform>::default())
+            .add_plugin(ExtractComponentPlugin::<MeshIndexRange>::default())
+            .add_plugin(ExtractComponentPlugin::<DepthBias>::default())
+            .add_plugin(ExtractComponentPlugin::<VertexPulling>::default())
+            .add_plugin(ExtractComponentPlugin::<PointSprites>::default())
+            .add_plugin(ExtractComponentPlugin::<ThickLines>::default())
+            .add_plugin(ExtractComponentPlugin::<StencilConfig>::default());
 
         if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
             render_app
+                .init_resource::<MeshViewBindingExtensions>()
                 .init_resource::<MeshPipeline>()
                 .add_system_to_stage(RenderStage::Extract, extract_meshes)
+                .add_system_to_stage(RenderStage::Extract, extract_changed_mesh_instances)
                 .add_system_to_stage(RenderStage::Queue, queue_mesh_bind_group)
                 .add_system_to_stage(RenderStage::Queue, queue_mesh_view_bind_groups);
         }
@@ -70,16 +93,272 @@ pub struct MeshUniform {
     pub flags: u32,
 }
 
+/// The data of the meshes with a [`ClipPlane`], an [`Outline`], [`PointSprites`], a [`Fade`] or
+/// [`ThickLines`], read by the pipelines specialized for them, see [`MeshPipelineKey::uses_mesh_features`].
+///
+/// It is kept out of [`MeshUniform`] so that the other meshes don't pay for it: they have no such
+/// component, and aren't bound one by [`SetMeshFeaturesBindGroup`].
+#[derive(Component, AsStd140, Clone)]
+pub struct MeshFeaturesUniform {
+    /// The plane the mesh is clipped against, only used if the mesh has a [`ClipPlane`].
+    pub clip_plane: Vec4,
+    /// The linear color of the outline of the mesh, only used if the mesh has an [`Outline`].
+    pub outline_color: Vec4,
+    pub outline_width: f32,
+    /// The size in pixels of the quads drawn for the points of the mesh, only used if the mesh
+    /// has [`PointSprites`].
+    pub point_size: f32,
+    /// The fraction of the pixels of the mesh which are drawn, only used if the mesh has a
+    /// [`Fade`].
+    pub fade: f32,
+    /// The width in pixels of the quads drawn for the segments of the mesh, only used if the mesh
+    /// has [`ThickLines`].
+    pub line_width: f32,
+}
+
+/// Computes the depth of meshes in the view space of a view, to sort the render phases of that
+/// view.
+///
+/// Each view has its own depth for a mesh, so that views looking from different positions or
+/// directions order the same meshes in their own way.
+#[derive(Clone, Copy, Debug)]
+pub struct ViewDepth {
+    inverse_view_row_2: Vec4,
+}
+
+impl ViewDepth {
+    pub fn new(view: &ExtractedView) -> Self {
+        ViewDepth {
+            inverse_view_row_2: view.transform.compute_matrix().inverse().row(2),
+        }
+    }
+
+    /// Returns the z coordinate of the translation of the mesh in view space. As the camera looks
+    /// towards -z, this decreases away from the camera.
+    pub fn mesh_z(&self, mesh_uniform: &MeshUniform) -> f32 {
+        // NOTE: row 2 of the inverse view matrix dotted with column 3 of the model matrix
+        // gives the z component of translation of the mesh in view space
+        self.inverse_view_row_2.dot(mesh_uniform.transform.col(3))
+    }
+}
+
+/// Clips a mesh against a plane: only the fragments on its positive side are rendered.
+///
+/// The plane is given by its equation `(a, b, c, d)` in world space, so that the point `p` is
+/// rendered if `a * p.x + b * p.y + c * p.z + d >= 0`.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ClipPlane(pub Vec4);
+
+/// Fades a mesh with screen-door transparency: only the given fraction of its pixels, from `0.0`
+/// to `1.0`, are drawn, in a dithered pattern.
+///
+/// Unlike blending, the mesh stays in the opaque phase and writes depth, so it doesn't need to be
+/// sorted, for example to cross-fade levels of detail. Pipelines drawing the mesh are specialized
+/// with [`MeshPipelineKey::DITHERED_ALPHA`].
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Fade(pub f32);
+
+/// The outline of a mesh drawn by pipelines specialized with [`MeshPipelineKey::INVERTED_HULL`].
+///
+/// The width is in world units.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Outline {
+    pub width: f32,
+    pub color: Color,
+}
+
+/// Only draws a sub-range of the indices of a mesh, or of its vertices if it is not indexed.
+///
+/// This allows several levels of detail to share the buffers of a single mesh, each using its own
+/// range of indices. The range is clamped to the indices of the mesh.
+#[derive(Component, Clone, Debug, PartialEq, Eq)]
+pub struct MeshIndexRange(pub Range<u32>);
+
+impl ExtractComponent for MeshIndexRange {
+    type Query = &'static MeshIndexRange;
+    type Filter = ();
+
+    fn extract_component(item: QueryItem<Self::Query>) -> Self {
+        item.clone()
+    }
+}
+
+/// Offsets the depth of a mesh, so that it is drawn in front of coplanar surfaces. This avoids
+/// z-fighting, for example for decals.
+///
+/// The bias is in units of the smallest depth difference at the depth of each fragment, positive
+/// values moving the mesh towards the camera. Each value uses its own pipeline.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DepthBias(pub i8);
+
+impl ExtractComponent for DepthBias {
+    type Query = &'static DepthBias;
+    type Filter = ();
+
+    fn extract_component(item: QueryItem<Self::Query>) -> Self {
+        *item
+    }
+}
+
+/// The stencil test and writes of a mesh, for pipelines specialized with
+/// [`MeshPipelineKey::STENCIL`].
+///
+/// Each distinct configuration uses its own pipeline. The stencil buffer is the stencil aspect of
+/// the depth texture of the pass, which requires a depth format with a stencil aspect such as
+/// [`MAIN_PASS_3D_DEPTH_FORMAT`]. The stencil values of the main 3d passes are cleared to `0`
+/// each frame, and the meshes are drawn in the order of their phase.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct StencilConfig {
+    /// The stencil state of the front-facing triangles.
+    pub front: StencilFaceState,
+    /// The stencil state of the back-facing triangles.
+    pub back: StencilFaceState,
+    /// The bits of the stencil values which are compared.
+    pub read_mask: u32,
+    /// The bits of the stencil values which are written.
+    pub write_mask: u32,
+    /// The value the stencil values are compared to, and which [`StencilOperation::Replace`]
+    /// writes. It is set when the mesh is drawn, so it doesn't need its own pipeline.
+    pub reference: u32,
+}
+
+impl ExtractComponent for StencilConfig {
+    type Query = &'static StencilConfig;
+    type Filter = ();
+
+    fn extract_component(item: QueryItem<Self::Query>) -> Self {
+        *item
+    }
+}
+
+/// Draws a mesh without binding its vertex buffer, for pipelines specialized with
+/// [`MeshPipelineKey::VERTEX_PULLING`].
+///
+/// The vertices of the mesh, or its indices if it is indexed, are still used to know how many
+/// vertices to draw. Material meshes with this component are queued with such a pipeline. The
+/// shadow pipeline isn't specialized for it, so these meshes don't cast shadows.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct VertexPulling;
+
+impl ExtractComponent for VertexPulling {
+    type Query = &'static VertexPulling;
+    type Filter = ();
+
+    fn extract_component(item: QueryItem<Self::Query>) -> Self {
+        *item
+    }
+}
+
+/// Draws each point of a mesh as a camera-facing quad, for pipelines specialized with
+/// [`MeshPipelineKey::POINT_SPRITES`].
+///
+/// The size is in pixels. The mesh must use [`PrimitiveTopology::PointList`] and must not be
+/// indexed, each of its vertices being drawn as an instance of the quad: [`DrawMesh`] fails to
+/// draw indexed meshes with point sprites, so they are not rendered. Material meshes with this
+/// component are queued with such a pipeline. The shadow and wireframe pipelines aren't
+/// specialized for it, so these meshes neither cast shadows nor have a wireframe.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct PointSprites {
+    pub size: f32,
+}
+
+impl ExtractComponent for PointSprites {
+    type Query = &'static PointSprites;
+    type Filter = ();
+
+    fn extract_component(item: QueryItem<Self::Query>) -> Self {
+        *item
+    }
+}
+
+/// Draws each segment of a mesh as a camera-facing quad, for pipelines specialized with
+/// [`MeshPipelineKey::THICK_LINES`], as the width of line primitives is always one pixel.
+///
+/// The width is in pixels. The mesh must use [`PrimitiveTopology::LineList`] and must not be
+/// indexed, each pair of its vertices being drawn as an instance of the quad: [`DrawMesh`] fails
+/// to draw indexed meshes with thick lines, so they are not rendered. Material meshes with this
+/// component are queued with such a pipeline. The shadow and wireframe pipelines aren't
+/// specialized for it, so these meshes neither cast shadows nor have a wireframe.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ThickLines {
+    pub width: f32,
+}
+
+impl ExtractComponent for ThickLines {
+    type Query = &'static ThickLines;
+    type Filter = ();
+
+    fn extract_component(item: QueryItem<Self::Query>) -> Self {
+        *item
+    }
+}
+
 // NOTE: These must match the bit flags in bevy_pbr2/src/render/mesh.wgsl!
 bitflags::bitflags! {
     #[repr(transparent)]
-    struct MeshFlags: u32 {
+    pub(crate) struct MeshFlags: u32 {
         const SHADOW_RECEIVER            = (1 << 0);
+        const CLIP_PLANE                 = (1 << 1);
+        const DITHERED_ALPHA             = (1 << 2);
         const NONE                       = 0;
         const UNINITIALIZED              = 0xFFFF;
     }
 }
 
+fn mesh_uniform(
+    transform: &GlobalTransform,
+    not_receiver: Option<&NotShadowReceiver>,
+    clip_plane: Option<&ClipPlane>,
+    fade: Option<&Fade>,
+) -> MeshUniform {
+    let mut flags = if not_receiver.is_some() {
+        MeshFlags::empty()
+    } else {
+        MeshFlags::SHADOW_RECEIVER
+    };
+    if clip_plane.is_some() {
+        flags |= MeshFlags::CLIP_PLANE;
+    }
+    if fade.is_some() {
+        flags |= MeshFlags::DITHERED_ALPHA;
+    }
+    let transform = transform.compute_matrix();
+    MeshUniform {
+        flags: flags.bits,
+        transform,
+        inverse_transpose_model: transform.inverse().transpose(),
+    }
+}
+
+/// The [`MeshFeaturesUniform`] of a mesh, if it has one of the components it stores.
+fn mesh_features_uniform(
+    clip_plane: Option<&ClipPlane>,
+    outline: Option<&Outline>,
+    point_sprites: Option<&PointSprites>,
+    fade: Option<&Fade>,
+    thick_lines: Option<&ThickLines>,
+) -> Option<MeshFeaturesUniform> {
+    if clip_plane.is_none()
+        && outline.is_none()
+        && point_sprites.is_none()
+        && fade.is_none()
+        && thick_lines.is_none()
+    {
+        return None;
+    }
+    Some(MeshFeaturesUniform {
+        clip_plane: clip_plane.map_or(Vec4::ZERO, |clip_plane| clip_plane.0),
+        outline_color: outline.map_or(Vec4::ZERO, |outline| {
+            outline.color.as_linear_rgba_f32().into()
+        }),
+        outline_width: outline.map_or(0.0, |outline| outline.width),
+        point_size: point_sprites.map_or(0.0, |point_sprites| point_sprites.size),
+        fade: fade.map_or(1.0, |fade| fade.0),
+        line_width: thick_lines.map_or(0.0, |thick_lines| thick_lines.width),
+    })
+}
+
+#[allow(clippy::type_complexity)]
 pub fn extract_meshes(
     mut commands: Commands,
     mut previous_caster_len: Local<usize>,
@@ -91,6 +370,11 @@ pub fn extract_meshes(
             &GlobalTransform,
             &Handle<Mesh>,
             Option<&NotShadowReceiver>,
+            Option<&ClipPlane>,
+            Option<&Outline>,
+            Option<&PointSprites>,
+            Option<&Fade>,
+            Option<&ThickLines>,
         ),
         Without<NotShadowCaster>,
     >,
@@ -101,29 +385,43 @@ pub fn extract_meshes(
             &GlobalTransform,
             &Handle<Mesh>,
             Option<&NotShadowReceiver>,
+            Option<&ClipPlane>,
+            Option<&Outline>,
+            Option<&PointSprites>,
+            Option<&Fade>,
+            Option<&ThickLines>,
         ),
         With<NotShadowCaster>,
     >,
 ) {
+    let mut features_values = Vec::new();
     let mut caster_values = Vec::with_capacity(*previous_caster_len);
-    for (entity, computed_visibility, transform, handle, not_receiver) in caster_query.iter() {
+    for (
+        entity,
+        computed_visibility,
+        transform,
+        handle,
+        not_receiver,
+        clip_plane,
+        outline,
+        point_sprites,
+        fade,
+        thick_lines,
+    ) in caster_query.iter()
+    {
         if !computed_visibility.is_visible {
             continue;
         }
-        let transform = transform.compute_matrix();
+        if let Some(features) =
+            mesh_features_uniform(clip_plane, outline, point_sprites, fade, thick_lines)
+        {
+            features_values.push((entity, (features,)));
+        }
         caster_values.push((
             entity,
             (
                 handle.clone_weak(),
-                MeshUniform {
-                    flags: if not_receiver.is_some() {
-                        MeshFlags::empty().bits
-                    } else {
-                        MeshFlags::SHADOW_RECEIVER.bits
-                    },
-                    transform,
-                    inverse_transpose_model: transform.inverse().transpose(),
-                },
+                mesh_uniform(transform, not_receiver, clip_plane, fade),
             ),
         ));
     }
@@ -131,150 +429,271 @@ pub fn extract_meshes(
     commands.insert_or_spawn_batch(caster_values);
 
     let mut not_caster_values = Vec::with_capacity(*previous_not_caster_len);
-    for (entity, computed_visibility, transform, handle, not_receiver) in not_caster_query.iter() {
+    for (
+        entity,
+        computed_visibility,
+        transform,
+        handle,
+        not_receiver,
+        clip_plane,
+        outline,
+        point_sprites,
+        fade,
+        thick_lines,
+    ) in not_caster_query.iter()
+    {
         if !computed_visibility.is_visible {
             continue;
         }
-        let transform = transform.compute_matrix();
+        if let Some(features) =
+            mesh_features_uniform(clip_plane, outline, point_sprites, fade, thick_lines)
+        {
+            features_values.push((entity, (features,)));
+        }
         not_caster_values.push((
             entity,
             (
                 handle.clone_weak(),
-                MeshUniform {
-                    flags: if not_receiver.is_some() {
-                        MeshFlags::empty().bits
-                    } else {
-                        MeshFlags::SHADOW_RECEIVER.bits
-                    },
-                    transform,
-                    inverse_transpose_model: transform.inverse().transpose(),
-                },
+                mesh_uniform(transform, not_receiver, clip_plane, fade),
                 NotShadowCaster,
             ),
         ));
     }
     *previous_not_caster_len = not_caster_values.len();
     commands.insert_or_spawn_batch(not_caster_values);
+    commands.insert_or_spawn_batch(features_values);
+}
+
+/// Enables the extraction of [`ChangedMeshInstances`] when inserted as a resource in the main
+/// world.
+#[derive(Default, Clone, Copy)]
+pub struct TrackChangedMeshInstances;
+
+/// The visible mesh entities whose [`GlobalTransform`] changed since the last frame.
+///
+/// This resource is only extracted to the render world when the [`TrackChangedMeshInstances`]
+/// resource exists, and is meant for debugging.
+#[derive(Default, Clone, Debug)]
+pub struct ChangedMeshInstances {
+    pub entities: Vec<Entity>,
+}
+
+pub fn extract_changed_mesh_instances(
+    mut commands: Commands,
+    track_changes: Option<Res<TrackChangedMeshInstances>>,
+    changed_query: Query<
+        (Entity, &ComputedVisibility),
+        (With<Handle<Mesh>>, Changed<GlobalTransform>),
+    >,
+) {
+    if track_changes.is_none() {
+        return;
+    }
+    let entities = changed_query
+        .iter()
+        .filter(|(_, computed_visibility)| computed_visibility.is_visible)
+        .map(|(entity, _)| entity)
+        .collect();
+    commands.insert_resource(ChangedMeshInstances { entities });
+}
+
+/// Returns the layout entries of the mesh view bind group: the bindings used by bevy's own
+/// shaders, followed by the ones registered in [`MeshViewBindingExtensions`].
+pub fn mesh_view_layout_entries(
+    extensions: &MeshViewBindingExtensions,
+) -> Vec<BindGroupLayoutEntry> {
+    let mut entries = vec![
+        // View
+        BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: BufferSize::new(ViewUniform::std140_size_static() as u64),
+            },
+            count: None,
+        },
+        // Lights
+        BindGroupLayoutEntry {
+            binding: 1,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: BufferSize::new(GpuLights::std140_size_static() as u64),
+            },
+            count: None,
+        },
+        // Point Shadow Texture Cube Array
+        BindGroupLayoutEntry {
+            binding: 2,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+                multisampled: false,
+                sample_type: TextureSampleType::Depth,
+                #[cfg(not(feature = "webgl"))]
+                view_dimension: TextureViewDimension::CubeArray,
+                #[cfg(feature = "webgl")]
+                view_dimension: TextureViewDimension::Cube,
+            },
+            count: None,
+        },
+        // Point Shadow Texture Array Sampler
+        BindGroupLayoutEntry {
+            binding: 3,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Sampler(SamplerBindingType::Comparison),
+            count: None,
+        },
+        // Directional Shadow Texture Array
+        BindGroupLayoutEntry {
+            binding: 4,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+                multisampled: false,
+                sample_type: TextureSampleType::Depth,
+                #[cfg(not(feature = "webgl"))]
+                view_dimension: TextureViewDimension::D2Array,
+                #[cfg(feature = "webgl")]
+                view_dimension: TextureViewDimension::D2,
+            },
+            count: None,
+        },
+        // Directional Shadow Texture Array Sampler
+        BindGroupLayoutEntry {
+            binding: 5,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Sampler(SamplerBindingType::Comparison),
+            count: None,
+        },
+        // PointLights
+        BindGroupLayoutEntry {
+            binding: 6,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                // NOTE: Static size for uniform buffers. GpuPointLight has a padded
+                // size of 64 bytes, so 16384 / 64 = 256 point lights max
+                min_binding_size: BufferSize::new(16384),
+            },
+            count: None,
+        },
+        // ClusteredLightIndexLists
+        BindGroupLayoutEntry {
+            binding: 7,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                // NOTE: With 256 point lights max, indices need 8 bits so use u8
+                min_binding_size: BufferSize::new(16384),
+            },
+            count: None,
+        },
+        // ClusterOffsetsAndCounts
+        BindGroupLayoutEntry {
+            binding: 8,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                // NOTE: The offset needs to address 16384 indices, which needs 14 bits.
+                // The count can be at most all 256 lights so 8 bits.
+                // Pack the offset into the upper 24 bits and the count into the
+                // lower 8 bits.
+                min_binding_size: BufferSize::new(16384),
+            },
+            count: None,
+        },
+    ];
+    entries.extend(
+        extensions
+            .extensions
+            .iter()
+            .map(|extension| extension.layout_entry),
+    );
+    entries
+}
+
+/// A binding appended to the mesh view bind group by a [`MeshViewBindingExtensions`].
+pub struct MeshViewBindingExtension {
+    pub layout_entry: BindGroupLayoutEntry,
+    /// The buffer bound for every view. Until it is set, mesh view bind groups are not created.
+    pub buffer: Option<Buffer>,
+}
+
+/// Extra bindings appended to the mesh view bind group (`group(0)`) after the ones used by
+/// bevy's own shaders, starting at [`MeshViewBindingExtensions::FIRST_BINDING`].
+///
+/// This resource lives in the render app. Since the view layout is created along with the
+/// [`MeshPipeline`], bindings must be added before it is initialized, by a plugin added before
+/// [`PbrPlugin`](crate::PbrPlugin). Their buffers can be provided later, typically during
+/// [`RenderStage::Prepare`].
+#[derive(Default)]
+pub struct MeshViewBindingExtensions {
+    extensions: Vec<MeshViewBindingExtension>,
+}
+
+impl MeshViewBindingExtensions {
+    /// The binding index of the first extension.
+    pub const FIRST_BINDING: u32 = 9;
+
+    /// Appends a binding of type `ty` to the view layout, and returns its binding index.
+    ///
+    /// Dynamic offsets are not supported for extension bindings.
+    pub fn add(&mut self, visibility: ShaderStages, ty: BindingType) -> u32 {
+        let binding = Self::FIRST_BINDING + self.extensions.len() as u32;
+        self.extensions.push(MeshViewBindingExtension {
+            layout_entry: BindGroupLayoutEntry {
+                binding,
+                visibility,
+                ty,
+                count: None,
+            },
+            buffer: None,
+        });
+        binding
+    }
+
+    /// Sets the buffer bound to `binding` for every view.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `binding` was not returned by [`MeshViewBindingExtensions::add`].
+    pub fn set_buffer(&mut self, binding: u32, buffer: Buffer) {
+        let extension = binding
+            .checked_sub(Self::FIRST_BINDING)
+            .and_then(|index| self.extensions.get_mut(index as usize))
+            .expect("no mesh view binding extension registered at this binding");
+        extension.buffer = Some(buffer);
+    }
+
+    pub fn extensions(&self) -> &[MeshViewBindingExtension] {
+        &self.extensions
+    }
 }
 
 #[derive(Clone)]
 pub struct MeshPipeline {
     pub view_layout: BindGroupLayout,
     pub mesh_layout: BindGroupLayout,
+    /// The layout of the [`MeshFeaturesUniform`] of the meshes, bound by
+    /// [`SetMeshFeaturesBindGroup`] right after the mesh bind group for the pipelines which
+    /// [use it](MeshPipelineKey::uses_mesh_features), at group 3 in the material pipelines.
+    pub mesh_features_layout: BindGroupLayout,
     // This dummy white texture is to be used in place of optional StandardMaterial textures
     pub dummy_white_gpu_image: GpuImage,
 }
 
 impl FromWorld for MeshPipeline {
     fn from_world(world: &mut World) -> Self {
+        let view_layout_entries =
+            mesh_view_layout_entries(world.get_resource::<MeshViewBindingExtensions>().unwrap());
         let render_device = world.get_resource::<RenderDevice>().unwrap();
         let view_layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-            entries: &[
-                // View
-                BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
-                    ty: BindingType::Buffer {
-                        ty: BufferBindingType::Uniform,
-                        has_dynamic_offset: true,
-                        min_binding_size: BufferSize::new(ViewUniform::std140_size_static() as u64),
-                    },
-                    count: None,
-                },
-                // Lights
-                BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Buffer {
-                        ty: BufferBindingType::Uniform,
-                        has_dynamic_offset: true,
-                        min_binding_size: BufferSize::new(GpuLights::std140_size_static() as u64),
-                    },
-                    count: None,
-                },
-                // Point Shadow Texture Cube Array
-                BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Texture {
-                        multisampled: false,
-                        sample_type: TextureSampleType::Depth,
-                        #[cfg(not(feature = "webgl"))]
-                        view_dimension: TextureViewDimension::CubeArray,
-                        #[cfg(feature = "webgl")]
-                        view_dimension: TextureViewDimension::Cube,
-                    },
-                    count: None,
-                },
-                // Point Shadow Texture Array Sampler
-                BindGroupLayoutEntry {
-                    binding: 3,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Sampler(SamplerBindingType::Comparison),
-                    count: None,
-                },
-                // Directional Shadow Texture Array
-                BindGroupLayoutEntry {
-                    binding: 4,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Texture {
-                        multisampled: false,
-                        sample_type: TextureSampleType::Depth,
-                        #[cfg(not(feature = "webgl"))]
-                        view_dimension: TextureViewDimension::D2Array,
-                        #[cfg(feature = "webgl")]
-                        view_dimension: TextureViewDimension::D2,
-                    },
-                    count: None,
-                },
-                // Directional Shadow Texture Array Sampler
-                BindGroupLayoutEntry {
-                    binding: 5,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Sampler(SamplerBindingType::Comparison),
-                    count: None,
-                },
-                // PointLights
-                BindGroupLayoutEntry {
-                    binding: 6,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Buffer {
-                        ty: BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        // NOTE: Static size for uniform buffers. GpuPointLight has a padded
-                        // size of 64 bytes, so 16384 / 64 = 256 point lights max
-                        min_binding_size: BufferSize::new(16384),
-                    },
-                    count: None,
-                },
-                // ClusteredLightIndexLists
-                BindGroupLayoutEntry {
-                    binding: 7,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Buffer {
-                        ty: BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        // NOTE: With 256 point lights max, indices need 8 bits so use u8
-                        min_binding_size: BufferSize::new(16384),
-                    },
-                    count: None,
-                },
-                // ClusterOffsetsAndCounts
-                BindGroupLayoutEntry {
-                    binding: 8,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Buffer {
-                        ty: BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        // NOTE: The offset needs to address 16384 indices, which needs 14 bits.
-                        // The count can be at most all 256 lights so 8 bits.
-                        // Pack the offset into the upper 24 bits and the count into the
-                        // lower 8 bits.
-                        min_binding_size: BufferSize::new(16384),
-                    },
-                    count: None,
-                },
-            ],
+            entries: &view_layout_entries,
             label: Some("mesh_view_layout"),
         });
 
@@ -291,6 +710,22 @@ impl FromWorld for MeshPipeline {
             }],
             label: Some("mesh_layout"),
         });
+        let mesh_features_layout =
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                entries: &[BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: BufferSize::new(
+                            MeshFeaturesUniform::std140_size_static() as u64,
+                        ),
+                    },
+                    count: None,
+                }],
+                label: Some("mesh_features_layout"),
+            });
         // A 1x1x1 'all 1.0' texture to use as a dummy texture to use in place of optional StandardMaterial textures
         let dummy_white_gpu_image = {
             let image = Image::new_fill(
@@ -339,6 +774,7 @@ impl FromWorld for MeshPipeline {
         MeshPipeline {
             view_layout,
             mesh_layout,
+            mesh_features_layout,
             dummy_white_gpu_image,
         }
     }
@@ -366,32 +802,79 @@ bitflags::bitflags! {
     #[repr(transparent)]
     // NOTE: Apparently quadro drivers support up to 64x MSAA.
     /// MSAA uses the highest 6 bits for the MSAA sample count - 1 to support up to 64x MSAA.
-    pub struct MeshPipelineKey: u32 {
+    pub struct MeshPipelineKey: u64 {
         const NONE                        = 0;
         const VERTEX_TANGENTS             = (1 << 0);
         const TRANSPARENT_MAIN_PASS       = (1 << 1);
+        const UNLIT                       = (1 << 2);
+        const CLIP_PLANE                  = (1 << 3);
+        /// Treats clockwise triangles as front-facing, for passes rendering a mirrored world.
+        const REVERSE_WINDING             = (1 << 4);
+        /// Writes depth even for the transparent pass, to debug sorting issues.
+        const FORCE_DEPTH_WRITE           = (1 << 5);
+        /// Only outputs the emissive contribution of the material, for example for a bloom
+        /// pre-pass.
+        const EMISSIVE_ONLY               = (1 << 6);
+        /// Outputs the object-space normal encoded as a color instead of the shaded mesh, for
+        /// example to bake normal maps. Materials using the default fragment shader output it
+        /// instead of their color.
+        const NORMAL_OUTPUT               = (1 << 7);
+        /// Binds no vertex buffer, the vertex shader reading the vertices of the mesh from a
+        /// storage buffer by their index instead, for example for geometry generated on the GPU.
+        ///
+        /// The storage buffer must be the first [`MeshViewBindingExtensions`] binding. The meshes
+        /// drawn with this pipeline need a [`VertexPulling`] component.
+        const VERTEX_PULLING              = (1 << 8);
+        /// Draws the back faces of the mesh inflated along its normals by the width of its
+        /// [`Outline`], in the color of the outline. Drawn along with the mesh, this gives it an
+        /// outline.
+        const INVERTED_HULL               = (1 << 9);
+        /// Reads the second set of normals of the mesh, [`Mesh::ATTRIBUTE_NORMAL_2`], at shader
+        /// location 4.
+        const VERTEX_NORMALS_2            = (1 << 10);
+        /// Expands each point of a [`PrimitiveTopology::PointList`] mesh into a camera-facing
+        /// quad, sized by its [`PointSprites`]. The pipeline draws triangles, reading the
+        /// vertices of the mesh once per instance of the quad.
+        const POINT_SPRITES               = (1 << 11);
+        /// Tests and writes the stencil buffer, with the stencil state set by
+        /// [`MeshPipeline::specialize_with_stencil`].
+        const STENCIL                     = (1 << 12);
+        /// Discards the pixels of the mesh in a dithered pattern according to its [`Fade`].
+        /// Pipelines only differ by this bit, not by the fade of the meshes.
+        const DITHERED_ALPHA              = (1 << 13);
+        /// Expands each segment of a [`PrimitiveTopology::LineList`] mesh into a camera-facing
+        /// quad, as wide as its [`ThickLines`]. The pipeline draws triangles, reading the two
+        /// vertices of a segment once per instance of the quad, the second one at shader
+        /// location 5.
+        const THICK_LINES                 = (1 << 14);
         const MSAA_RESERVED_BITS          = MeshPipelineKey::MSAA_MASK_BITS << MeshPipelineKey::MSAA_SHIFT_BITS;
         const PRIMITIVE_TOPOLOGY_RESERVED_BITS = MeshPipelineKey::PRIMITIVE_TOPOLOGY_MASK_BITS << MeshPipelineKey::PRIMITIVE_TOPOLOGY_SHIFT_BITS;
+        const STRIP_INDEX_FORMAT_RESERVED_BITS = MeshPipelineKey::STRIP_INDEX_FORMAT_MASK_BITS << MeshPipelineKey::STRIP_INDEX_FORMAT_SHIFT_BITS;
+        const DEPTH_BIAS_RESERVED_BITS    = MeshPipelineKey::DEPTH_BIAS_MASK_BITS << MeshPipelineKey::DEPTH_BIAS_SHIFT_BITS;
     }
 }
 
 impl MeshPipelineKey {
-    const MSAA_MASK_BITS: u32 = 0b111111;
-    const MSAA_SHIFT_BITS: u32 = 32 - 6;
-    const PRIMITIVE_TOPOLOGY_MASK_BITS: u32 = 0b111;
+    const MSAA_MASK_BITS: u64 = 0b111111;
+    const MSAA_SHIFT_BITS: u32 = 64 - 6;
+    const PRIMITIVE_TOPOLOGY_MASK_BITS: u64 = 0b111;
     const PRIMITIVE_TOPOLOGY_SHIFT_BITS: u32 = Self::MSAA_SHIFT_BITS - 3;
+    const STRIP_INDEX_FORMAT_MASK_BITS: u64 = 0b11;
+    const STRIP_INDEX_FORMAT_SHIFT_BITS: u32 = Self::PRIMITIVE_TOPOLOGY_SHIFT_BITS - 2;
+    const DEPTH_BIAS_MASK_BITS: u64 = 0xFF;
+    const DEPTH_BIAS_SHIFT_BITS: u32 = Self::STRIP_INDEX_FORMAT_SHIFT_BITS - 8;
 
     pub fn from_msaa_samples(msaa_samples: u32) -> Self {
-        let msaa_bits = ((msaa_samples - 1) & Self::MSAA_MASK_BITS) << Self::MSAA_SHIFT_BITS;
+        let msaa_bits = ((msaa_samples as u64 - 1) & Self::MSAA_MASK_BITS) << Self::MSAA_SHIFT_BITS;
         MeshPipelineKey::from_bits(msaa_bits).unwrap()
     }
 
     pub fn msaa_samples(&self) -> u32 {
-        ((self.bits >> Self::MSAA_SHIFT_BITS) & Self::MSAA_MASK_BITS) + 1
+        (((self.bits >> Self::MSAA_SHIFT_BITS) & Self::MSAA_MASK_BITS) + 1) as u32
     }
 
     pub fn from_primitive_topology(primitive_topology: PrimitiveTopology) -> Self {
-        let primitive_topology_bits = ((primitive_topology as u32)
+        let primitive_topology_bits = ((primitive_topology as u64)
             & Self::PRIMITIVE_TOPOLOGY_MASK_BITS)
             << Self::PRIMITIVE_TOPOLOGY_SHIFT_BITS;
         MeshPipelineKey::from_bits(primitive_topology_bits).unwrap()
@@ -401,147 +884,202 @@ impl MeshPipelineKey {
         let primitive_topology_bits =
             (self.bits >> Self::PRIMITIVE_TOPOLOGY_SHIFT_BITS) & Self::PRIMITIVE_TOPOLOGY_MASK_BITS;
         match primitive_topology_bits {
-            x if x == PrimitiveTopology::PointList as u32 => PrimitiveTopology::PointList,
-            x if x == PrimitiveTopology::LineList as u32 => PrimitiveTopology::LineList,
-            x if x == PrimitiveTopology::LineStrip as u32 => PrimitiveTopology::LineStrip,
-            x if x == PrimitiveTopology::TriangleList as u32 => PrimitiveTopology::TriangleList,
-            x if x == PrimitiveTopology::TriangleStrip as u32 => PrimitiveTopology::TriangleStrip,
+            x if x == PrimitiveTopology::PointList as u64 => PrimitiveTopology::PointList,
+            x if x == PrimitiveTopology::LineList as u64 => PrimitiveTopology::LineList,
+            x if x == PrimitiveTopology::LineStrip as u64 => PrimitiveTopology::LineStrip,
+            x if x == PrimitiveTopology::TriangleList as u64 => PrimitiveTopology::TriangleList,
+            x if x == PrimitiveTopology::TriangleStrip as u64 => PrimitiveTopology::TriangleStrip,
             _ => PrimitiveTopology::default(),
         }
     }
-}
 
-impl SpecializedPipeline for MeshPipeline {
-    type Key = MeshPipelineKey;
+    /// Strip topologies need the index format of indexed meshes to support primitive restart.
+    /// For other topologies, or for non-indexed meshes, this returns an empty key.
+    pub fn from_strip_index_format(
+        primitive_topology: PrimitiveTopology,
+        index_format: Option<IndexFormat>,
+    ) -> Self {
+        let strip_index_format_bits = match index_format {
+            Some(_) if !primitive_topology.is_strip() => 0,
+            Some(IndexFormat::Uint16) => 1,
+            Some(IndexFormat::Uint32) => 2,
+            None => 0,
+        };
+        MeshPipelineKey::from_bits(strip_index_format_bits << Self::STRIP_INDEX_FORMAT_SHIFT_BITS)
+            .unwrap()
+    }
 
-    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
-        let (vertex_array_stride, vertex_attributes) =
-            if key.contains(MeshPipelineKey::VERTEX_TANGENTS) {
-                (
-                    48,
-                    vec![
-                        // Position (GOTCHA! Vertex_Position isn't first in the buffer due to how Mesh sorts attributes (alphabetically))
-                        VertexAttribute {
-                            format: VertexFormat::Float32x3,
-                            offset: 12,
-                            shader_location: 0,
-                        },
-                        // Normal
-                        VertexAttribute {
-                            format: VertexFormat::Float32x3,
-                            offset: 0,
-                            shader_location: 1,
-                        },
-                        // Uv (GOTCHA! uv is no longer third in the buffer due to how Mesh sorts attributes (alphabetically))
-                        VertexAttribute {
-                            format: VertexFormat::Float32x2,
-                            offset: 40,
-                            shader_location: 2,
-                        },
-                        // Tangent
-                        VertexAttribute {
-                            format: VertexFormat::Float32x4,
-                            offset: 24,
-                            shader_location: 3,
-                        },
-                    ],
-                )
-            } else {
-                (
-                    32,
-                    vec![
-                        // Position (GOTCHA! Vertex_Position isn't first in the buffer due to how Mesh sorts attributes (alphabetically))
-                        VertexAttribute {
-                            format: VertexFormat::Float32x3,
-                            offset: 12,
-                            shader_location: 0,
-                        },
-                        // Normal
-                        VertexAttribute {
-                            format: VertexFormat::Float32x3,
-                            offset: 0,
-                            shader_location: 1,
-                        },
-                        // Uv
-                        VertexAttribute {
-                            format: VertexFormat::Float32x2,
-                            offset: 24,
-                            shader_location: 2,
-                        },
-                    ],
-                )
-            };
-        let mut shader_defs = Vec::new();
-        if key.contains(MeshPipelineKey::VERTEX_TANGENTS) {
-            shader_defs.push(String::from("VERTEX_TANGENTS"));
+    pub fn strip_index_format(&self) -> Option<IndexFormat> {
+        let strip_index_format_bits =
+            (self.bits >> Self::STRIP_INDEX_FORMAT_SHIFT_BITS) & Self::STRIP_INDEX_FORMAT_MASK_BITS;
+        match strip_index_format_bits {
+            1 => Some(IndexFormat::Uint16),
+            2 => Some(IndexFormat::Uint32),
+            _ => None,
+        }
+    }
+
+    /// Stores the [`DepthBias`] of a mesh in the key, as each bias needs its own pipeline.
+    pub fn from_depth_bias(depth_bias: DepthBias) -> Self {
+        let depth_bias_bits = (depth_bias.0 as u8 as u64) << Self::DEPTH_BIAS_SHIFT_BITS;
+        MeshPipelineKey::from_bits(depth_bias_bits).unwrap()
+    }
+
+    /// The depth bias stored by [`MeshPipelineKey::from_depth_bias`], in the units of the
+    /// `constant` of [`DepthBiasState`].
+    pub fn depth_bias(&self) -> i32 {
+        let depth_bias_bits =
+            (self.bits >> Self::DEPTH_BIAS_SHIFT_BITS) & Self::DEPTH_BIAS_MASK_BITS;
+        depth_bias_bits as u8 as i8 as i32
+    }
+
+    /// The winding of the front-facing triangles, flipped by [`MeshPipelineKey::REVERSE_WINDING`].
+    pub fn front_face(&self) -> FrontFace {
+        if self.contains(MeshPipelineKey::REVERSE_WINDING) {
+            FrontFace::Cw
+        } else {
+            FrontFace::Ccw
         }
+    }
 
-        let (label, blend, depth_write_enabled);
-        if key.contains(MeshPipelineKey::TRANSPARENT_MAIN_PASS) {
-            label = "transparent_mesh_pipeline".into();
-            blend = Some(BlendState::ALPHA_BLENDING);
-            // For the transparent pass, fragments that are closer will be alpha blended
-            // but their depth is not written to the depth buffer
-            depth_write_enabled = false;
+    /// The topology of the pipeline: the triangles of the quads expanded from the points with
+    /// [`MeshPipelineKey::POINT_SPRITES`] or from the segments with
+    /// [`MeshPipelineKey::THICK_LINES`], the topology of the mesh otherwise.
+    pub fn pipeline_topology(&self) -> PrimitiveTopology {
+        if self.intersects(MeshPipelineKey::POINT_SPRITES | MeshPipelineKey::THICK_LINES) {
+            PrimitiveTopology::TriangleList
         } else {
-            label = "opaque_mesh_pipeline".into();
-            blend = Some(BlendState::REPLACE);
-            // For the opaque and alpha mask passes, fragments that are closer will replace
-            // the current fragment value in the output and the depth is written to the
-            // depth buffer
-            depth_write_enabled = true;
+            self.primitive_topology()
+        }
+    }
+
+    /// The faces which are culled: the front ones with [`MeshPipelineKey::INVERTED_HULL`], the
+    /// back ones otherwise.
+    pub fn cull_face(&self) -> Face {
+        if self.contains(MeshPipelineKey::INVERTED_HULL) {
+            Face::Front
+        } else {
+            Face::Back
+        }
+    }
+
+    /// Whether the depth of the fragments is written to the depth buffer.
+    pub fn depth_write_enabled(&self) -> bool {
+        // For the transparent pass, fragments that are closer will be alpha blended
+        // but their depth is not written to the depth buffer. For the opaque and alpha mask
+        // passes, fragments that are closer will replace the current fragment value in the
+        // output and the depth is written to the depth buffer
+        !self.contains(MeshPipelineKey::TRANSPARENT_MAIN_PASS)
+            || self.contains(MeshPipelineKey::FORCE_DEPTH_WRITE)
+    }
+
+    /// Whether the shaders read the [`MeshFeaturesUniform`] of the meshes, with the
+    /// `MESH_FEATURES` shader def.
+    pub fn uses_mesh_features(&self) -> bool {
+        self.intersects(
+            MeshPipelineKey::CLIP_PLANE
+                | MeshPipelineKey::INVERTED_HULL
+                | MeshPipelineKey::POINT_SPRITES
+                | MeshPipelineKey::DITHERED_ALPHA
+                | MeshPipelineKey::THICK_LINES,
+        )
+    }
+
+    /// Returns the shader defs pushed to the mesh shaders for this key.
+    pub fn shader_defs(&self) -> Vec<String> {
+        let mut shader_defs = Vec::new();
+        if self.uses_mesh_features() {
+            shader_defs.push(String::from("MESH_FEATURES"));
+        }
+        if self.contains(MeshPipelineKey::VERTEX_TANGENTS) {
+            shader_defs.push(String::from("VERTEX_TANGENTS"));
+        }
+        if self.contains(MeshPipelineKey::UNLIT) {
+            shader_defs.push(String::from("UNLIT"));
+        }
+        if self.contains(MeshPipelineKey::CLIP_PLANE) {
+            shader_defs.push(String::from("CLIP_PLANE"));
+        }
+        if self.contains(MeshPipelineKey::EMISSIVE_ONLY) {
+            shader_defs.push(String::from("EMISSIVE_ONLY"));
+        }
+        if self.contains(MeshPipelineKey::NORMAL_OUTPUT) {
+            shader_defs.push(String::from("NORMAL_OUTPUT"));
+        }
+        if self.contains(MeshPipelineKey::VERTEX_PULLING) {
+            shader_defs.push(String::from("VERTEX_PULLING"));
+        }
+        if self.contains(MeshPipelineKey::INVERTED_HULL) {
+            shader_defs.push(String::from("INVERTED_HULL"));
+        }
+        if self.contains(MeshPipelineKey::VERTEX_NORMALS_2) {
+            shader_defs.push(String::from("VERTEX_NORMALS_2"));
+        }
+        if self.contains(MeshPipelineKey::POINT_SPRITES) {
+            shader_defs.push(String::from("POINT_SPRITES"));
+        }
+        if self.contains(MeshPipelineKey::DITHERED_ALPHA) {
+            shader_defs.push(String::from("DITHERED_ALPHA"));
+        }
+        if self.contains(MeshPipelineKey::THICK_LINES) {
+            shader_defs.push(String::from("THICK_LINES"));
         }
 
         #[cfg(feature = "webgl")]
         shader_defs.push(String::from("NO_ARRAY_TEXTURES_SUPPORT"));
 
+        shader_defs
+    }
+}
+
+impl SpecializedPipeline for MeshPipeline {
+    type Key = MeshPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        self.specialize_with_target_format(key, TextureFormat::bevy_default())
+    }
+}
+
+impl MeshPipeline {
+    /// Specializes the pipeline for a color target of the given format, instead of the default
+    /// swapchain format, for example to render meshes in a custom offscreen pass.
+    pub fn specialize_with_target_format(
+        &self,
+        key: MeshPipelineKey,
+        target_format: TextureFormat,
+    ) -> RenderPipelineDescriptor {
+        let shader_defs = key.shader_defs();
+
+        let label = if key.contains(MeshPipelineKey::TRANSPARENT_MAIN_PASS) {
+            "transparent_mesh_pipeline".into()
+        } else {
+            "opaque_mesh_pipeline".into()
+        };
+
         RenderPipelineDescriptor {
             vertex: VertexState {
                 shader: MESH_SHADER_HANDLE.typed::<Shader>(),
                 entry_point: "vertex".into(),
                 shader_defs: shader_defs.clone(),
-                buffers: vec![VertexBufferLayout {
-                    array_stride: vertex_array_stride,
-                    step_mode: VertexStepMode::Vertex,
-                    attributes: vertex_attributes,
-                }],
+                buffers: mesh_vertex_buffer_layouts(key),
             },
             fragment: Some(FragmentState {
                 shader: MESH_SHADER_HANDLE.typed::<Shader>(),
                 shader_defs,
                 entry_point: "fragment".into(),
-                targets: vec![ColorTargetState {
-                    format: TextureFormat::bevy_default(),
-                    blend,
-                    write_mask: ColorWrites::ALL,
-                }],
+                targets: vec![mesh_color_target(key, target_format)],
             }),
             layout: Some(vec![self.view_layout.clone(), self.mesh_layout.clone()]),
             primitive: PrimitiveState {
-                front_face: FrontFace::Ccw,
-                cull_mode: Some(Face::Back),
+                front_face: key.front_face(),
+                cull_mode: Some(key.cull_face()),
                 unclipped_depth: false,
                 polygon_mode: PolygonMode::Fill,
                 conservative: false,
-                topology: key.primitive_topology(),
-                strip_index_format: None,
+                topology: key.pipeline_topology(),
+                strip_index_format: key.strip_index_format(),
             },
-            depth_stencil: Some(DepthStencilState {
-                format: TextureFormat::Depth32Float,
-                depth_write_enabled,
-                depth_compare: CompareFunction::Greater,
-                stencil: StencilState {
-                    front: StencilFaceState::IGNORE,
-                    back: StencilFaceState::IGNORE,
-                    read_mask: 0,
-                    write_mask: 0,
-                },
-                bias: DepthBiasState {
-                    constant: 0,
-                    slope_scale: 0.0,
-                    clamp: 0.0,
-                },
-            }),
+            depth_stencil: Some(mesh_depth_stencil_state(key, None)),
             multisample: MultisampleState {
                 count: key.msaa_samples(),
                 mask: !0,
@@ -552,15 +1090,166 @@ impl SpecializedPipeline for MeshPipeline {
     }
 }
 
+impl MeshPipeline {
+    /// Specializes the pipeline for a mesh with a [`StencilConfig`], the key containing
+    /// [`MeshPipelineKey::STENCIL`].
+    pub fn specialize_with_stencil(
+        &self,
+        key: MeshPipelineKey,
+        stencil: &StencilConfig,
+    ) -> RenderPipelineDescriptor {
+        let mut descriptor = self.specialize(key);
+        descriptor.depth_stencil = Some(mesh_depth_stencil_state(key, Some(stencil)));
+        descriptor
+    }
+}
+
+/// The depth and stencil state of the mesh pipeline, the stencil being ignored without `stencil`.
+fn mesh_depth_stencil_state(
+    key: MeshPipelineKey,
+    stencil: Option<&StencilConfig>,
+) -> DepthStencilState {
+    let stencil = match stencil {
+        Some(stencil) => StencilState {
+            front: stencil.front,
+            back: stencil.back,
+            read_mask: stencil.read_mask,
+            write_mask: stencil.write_mask,
+        },
+        None => StencilState {
+            front: StencilFaceState::IGNORE,
+            back: StencilFaceState::IGNORE,
+            read_mask: 0,
+            write_mask: 0,
+        },
+    };
+    DepthStencilState {
+        format: MAIN_PASS_3D_DEPTH_FORMAT,
+        depth_write_enabled: key.depth_write_enabled(),
+        depth_compare: CompareFunction::Greater,
+        stencil,
+        bias: DepthBiasState {
+            constant: key.depth_bias(),
+            slope_scale: 0.0,
+            clamp: 0.0,
+        },
+    }
+}
+
+/// The layouts of the vertex buffers read by the mesh pipeline.
+///
+/// There are none with [`MeshPipelineKey::VERTEX_PULLING`], the vertex shader reading the vertices
+/// from a storage buffer instead. With [`MeshPipelineKey::POINT_SPRITES`], the vertices step per
+/// instance. With [`MeshPipelineKey::THICK_LINES`], the pairs of vertices do, the vertex buffer
+/// being read a second time one vertex later for the position of the second vertex of each pair.
+fn mesh_vertex_buffer_layouts(key: MeshPipelineKey) -> Vec<VertexBufferLayout> {
+    if key.contains(MeshPipelineKey::VERTEX_PULLING) {
+        return Vec::new();
+    }
+    let (array_stride, attributes) = mesh_vertex_attributes(
+        key.contains(MeshPipelineKey::VERTEX_TANGENTS),
+        key.contains(MeshPipelineKey::VERTEX_NORMALS_2),
+    );
+    if key.contains(MeshPipelineKey::THICK_LINES) {
+        let position = VertexAttribute {
+            shader_location: 5,
+            ..*attributes
+                .iter()
+                .find(|attribute| attribute.shader_location == 0)
+                .unwrap()
+        };
+        return vec![
+            VertexBufferLayout {
+                array_stride: array_stride * 2,
+                step_mode: VertexStepMode::Instance,
+                attributes,
+            },
+            VertexBufferLayout {
+                array_stride: array_stride * 2,
+                step_mode: VertexStepMode::Instance,
+                attributes: vec![position],
+            },
+        ];
+    }
+    let step_mode = if key.contains(MeshPipelineKey::POINT_SPRITES) {
+        VertexStepMode::Instance
+    } else {
+        VertexStepMode::Vertex
+    };
+    vec![VertexBufferLayout {
+        array_stride,
+        step_mode,
+        attributes,
+    }]
+}
+
+/// Returns the stride and attributes of the vertex buffer of a [`GpuMesh`](bevy_render::mesh::GpuMesh)
+/// with the given optional attributes.
+///
+/// The attributes are interleaved in the order of their names, see
+/// [`Mesh::get_vertex_buffer_data`], so their offsets depend on which ones the mesh has.
+pub(crate) fn mesh_vertex_attributes(
+    vertex_tangents: bool,
+    vertex_normals_2: bool,
+) -> (u64, Vec<VertexAttribute>) {
+    let mut attributes = vec![
+        (Mesh::ATTRIBUTE_POSITION, VertexFormat::Float32x3, 0),
+        (Mesh::ATTRIBUTE_NORMAL, VertexFormat::Float32x3, 1),
+        (Mesh::ATTRIBUTE_UV_0, VertexFormat::Float32x2, 2),
+    ];
+    if vertex_tangents {
+        attributes.push((Mesh::ATTRIBUTE_TANGENT, VertexFormat::Float32x4, 3));
+    }
+    if vertex_normals_2 {
+        attributes.push((Mesh::ATTRIBUTE_NORMAL_2, VertexFormat::Float32x3, 4));
+    }
+    attributes.sort_by_key(|(name, _, _)| *name);
+    let mut offset = 0;
+    let vertex_attributes = attributes
+        .into_iter()
+        .map(|(_, format, shader_location)| {
+            let attribute = VertexAttribute {
+                format,
+                offset,
+                shader_location,
+            };
+            offset += format.get_size();
+            attribute
+        })
+        .collect();
+    (offset, vertex_attributes)
+}
+
+fn mesh_color_target(key: MeshPipelineKey, format: TextureFormat) -> ColorTargetState {
+    // encoded normals must not be blended with what is already in the target
+    let blend = if key.contains(MeshPipelineKey::TRANSPARENT_MAIN_PASS)
+        && !key.contains(MeshPipelineKey::NORMAL_OUTPUT)
+    {
+        BlendState::ALPHA_BLENDING
+    } else {
+        BlendState::REPLACE
+    };
+    ColorTargetState {
+        format,
+        blend: Some(blend),
+        write_mask: ColorWrites::ALL,
+    }
+}
+
 pub struct MeshBindGroup {
     pub value: BindGroup,
 }
 
+pub struct MeshFeaturesBindGroup {
+    pub value: BindGroup,
+}
+
 pub fn queue_mesh_bind_group(
     mut commands: Commands,
     mesh_pipeline: Res<MeshPipeline>,
     render_device: Res<RenderDevice>,
     mesh_uniforms: Res<ComponentUniforms<MeshUniform>>,
+    mesh_features_uniforms: Res<ComponentUniforms<MeshFeaturesUniform>>,
 ) {
     if let Some(binding) = mesh_uniforms.uniforms().binding() {
         commands.insert_resource(MeshBindGroup {
@@ -574,6 +1263,18 @@ pub fn queue_mesh_bind_group(
             }),
         });
     }
+    if let Some(binding) = mesh_features_uniforms.uniforms().binding() {
+        commands.insert_resource(MeshFeaturesBindGroup {
+            value: render_device.create_bind_group(&BindGroupDescriptor {
+                entries: &[BindGroupEntry {
+                    binding: 0,
+                    resource: binding,
+                }],
+                label: Some("mesh_features_bind_group"),
+                layout: &mesh_pipeline.mesh_features_layout,
+            }),
+        });
+    }
 }
 
 #[derive(Component)]
@@ -590,65 +1291,82 @@ pub fn queue_mesh_view_bind_groups(
     light_meta: Res<LightMeta>,
     global_light_meta: Res<GlobalLightMeta>,
     view_uniforms: Res<ViewUniforms>,
+    binding_extensions: Res<MeshViewBindingExtensions>,
     views: Query<(Entity, &ViewShadowBindings, &ViewClusterBindings)>,
 ) {
-    if let (Some(view_binding), Some(light_binding), Some(point_light_binding)) = (
+    let extension_entries = binding_extensions
+        .extensions()
+        .iter()
+        .map(|extension| {
+            Some(BindGroupEntry {
+                binding: extension.layout_entry.binding,
+                resource: extension.buffer.as_ref()?.as_entire_binding(),
+            })
+        })
+        .collect::<Option<Vec<_>>>();
+    if let (
+        Some(view_binding),
+        Some(light_binding),
+        Some(point_light_binding),
+        Some(extension_entries),
+    ) = (
         view_uniforms.uniforms.binding(),
         light_meta.view_gpu_lights.binding(),
         global_light_meta.gpu_point_lights.binding(),
+        extension_entries,
     ) {
         for (entity, view_shadow_bindings, view_cluster_bindings) in views.iter() {
+            let mut entries = vec![
+                BindGroupEntry {
+                    binding: 0,
+                    resource: view_binding.clone(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: light_binding.clone(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::TextureView(
+                        &view_shadow_bindings.point_light_depth_texture_view,
+                    ),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: BindingResource::Sampler(&shadow_pipeline.point_light_sampler),
+                },
+                BindGroupEntry {
+                    binding: 4,
+                    resource: BindingResource::TextureView(
+                        &view_shadow_bindings.directional_light_depth_texture_view,
+                    ),
+                },
+                BindGroupEntry {
+                    binding: 5,
+                    resource: BindingResource::Sampler(&shadow_pipeline.directional_light_sampler),
+                },
+                BindGroupEntry {
+                    binding: 6,
+                    resource: point_light_binding.clone(),
+                },
+                BindGroupEntry {
+                    binding: 7,
+                    resource: view_cluster_bindings
+                        .cluster_light_index_lists
+                        .binding()
+                        .unwrap(),
+                },
+                BindGroupEntry {
+                    binding: 8,
+                    resource: view_cluster_bindings
+                        .cluster_offsets_and_counts
+                        .binding()
+                        .unwrap(),
+                },
+            ];
+            entries.extend(extension_entries.iter().cloned());
             let view_bind_group = render_device.create_bind_group(&BindGroupDescriptor {
-                entries: &[
-                    BindGroupEntry {
-                        binding: 0,
-                        resource: view_binding.clone(),
-                    },
-                    BindGroupEntry {
-                        binding: 1,
-                        resource: light_binding.clone(),
-                    },
-                    BindGroupEntry {
-                        binding: 2,
-                        resource: BindingResource::TextureView(
-                            &view_shadow_bindings.point_light_depth_texture_view,
-                        ),
-                    },
-                    BindGroupEntry {
-                        binding: 3,
-                        resource: BindingResource::Sampler(&shadow_pipeline.point_light_sampler),
-                    },
-                    BindGroupEntry {
-                        binding: 4,
-                        resource: BindingResource::TextureView(
-                            &view_shadow_bindings.directional_light_depth_texture_view,
-                        ),
-                    },
-                    BindGroupEntry {
-                        binding: 5,
-                        resource: BindingResource::Sampler(
-                            &shadow_pipeline.directional_light_sampler,
-                        ),
-                    },
-                    BindGroupEntry {
-                        binding: 6,
-                        resource: point_light_binding.clone(),
-                    },
-                    BindGroupEntry {
-                        binding: 7,
-                        resource: view_cluster_bindings
-                            .cluster_light_index_lists
-                            .binding()
-                            .unwrap(),
-                    },
-                    BindGroupEntry {
-                        binding: 8,
-                        resource: view_cluster_bindings
-                            .cluster_offsets_and_counts
-                            .binding()
-                            .unwrap(),
-                    },
-                ],
+                entries: &entries,
                 label: Some("mesh_view_bind_group"),
                 layout: &mesh_pipeline.view_layout,
             });
@@ -708,9 +1426,50 @@ impl<const I: usize> EntityRenderCommand for SetMeshBindGroup<I> {
     }
 }
 
+/// Binds the [`MeshFeaturesUniform`] of the mesh, if it has one.
+///
+/// The meshes drawn with a pipeline which [uses it](MeshPipelineKey::uses_mesh_features) must
+/// have the matching component, for example an [`Outline`] with
+/// [`MeshPipelineKey::INVERTED_HULL`].
+pub struct SetMeshFeaturesBindGroup<const I: usize>;
+impl<const I: usize> EntityRenderCommand for SetMeshFeaturesBindGroup<I> {
+    type Param = (
+        Option<SRes<MeshFeaturesBindGroup>>,
+        SQuery<Option<Read<DynamicUniformIndex<MeshFeaturesUniform>>>>,
+    );
+    #[inline]
+    fn render<'w>(
+        _view: Entity,
+        item: Entity,
+        (mesh_features_bind_group, mesh_query): SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        if let (Some(mesh_features_bind_group), Some(features_index)) =
+            (mesh_features_bind_group, mesh_query.get(item).unwrap())
+        {
+            pass.set_bind_group(
+                I,
+                &mesh_features_bind_group.into_inner().value,
+                &[features_index.index()],
+            );
+        }
+        RenderCommandResult::Success
+    }
+}
+
 pub struct DrawMesh;
 impl EntityRenderCommand for DrawMesh {
-    type Param = (SRes<RenderAssets<Mesh>>, SQuery<Read<Handle<Mesh>>>);
+    type Param = (
+        SRes<RenderAssets<Mesh>>,
+        SQuery<(
+            Read<Handle<Mesh>>,
+            Option<Read<MeshIndexRange>>,
+            Option<Read<VertexPulling>>,
+            Option<Read<PointSprites>>,
+            Option<Read<ThickLines>>,
+            Option<Read<StencilConfig>>,
+        )>,
+    );
     #[inline]
     fn render<'w>(
         _view: Entity,
@@ -718,20 +1477,48 @@ impl EntityRenderCommand for DrawMesh {
         (meshes, mesh_query): SystemParamItem<'w, '_, Self::Param>,
         pass: &mut TrackedRenderPass<'w>,
     ) -> RenderCommandResult {
-        let mesh_handle = mesh_query.get(item).unwrap();
+        let (mesh_handle, index_range, vertex_pulling, point_sprites, thick_lines, stencil) =
+            mesh_query.get(item).unwrap();
         if let Some(gpu_mesh) = meshes.into_inner().get(mesh_handle) {
-            pass.set_vertex_buffer(0, gpu_mesh.vertex_buffer.slice(..));
+            if let Some(stencil) = stencil {
+                pass.set_stencil_reference(stencil.reference);
+            }
+            if vertex_pulling.is_none() {
+                pass.set_vertex_buffer(0, gpu_mesh.vertex_buffer.slice(..));
+            }
             match &gpu_mesh.buffer_info {
+                // each point is an instance of the 6 vertices of a quad
+                GpuBufferInfo::NonIndexed { vertex_count } if point_sprites.is_some() => {
+                    pass.draw(0..6, draw_range(*vertex_count, index_range));
+                }
+                // each pair of vertices is an instance of the 6 vertices of a quad
+                GpuBufferInfo::NonIndexed { vertex_count } if thick_lines.is_some() => {
+                    let vertices = draw_range(*vertex_count, index_range);
+                    let segments = vertices.start / 2..vertices.end / 2;
+                    if !segments.is_empty() {
+                        // the second vertex of each pair, one vertex later in the same buffer
+                        let (vertex_size, _) =
+                            mesh_vertex_attributes(gpu_mesh.has_tangents, gpu_mesh.has_normals_2);
+                        pass.set_vertex_buffer(1, gpu_mesh.vertex_buffer.slice(vertex_size..));
+                        pass.draw(0..6, segments);
+                    }
+                }
+                // the indices would have to select the instances, which isn't supported
+                GpuBufferInfo::Indexed { .. }
+                    if point_sprites.is_some() || thick_lines.is_some() =>
+                {
+                    return RenderCommandResult::Failure;
+                }
                 GpuBufferInfo::Indexed {
                     buffer,
                     index_format,
                     count,
                 } => {
                     pass.set_index_buffer(buffer.slice(..), 0, *index_format);
-                    pass.draw_indexed(0..*count, 0, 0..1);
+                    pass.draw_indexed(draw_range(*count, index_range), 0, 0..1);
                 }
                 GpuBufferInfo::NonIndexed { vertex_count } => {
-                    pass.draw(0..*vertex_count, 0..1);
+                    pass.draw(draw_range(*vertex_count, index_range), 0..1);
                 }
             }
             RenderCommandResult::Success
@@ -741,13 +1528,635 @@ impl EntityRenderCommand for DrawMesh {
     }
 }
 
+/// The indices or vertices to draw out of the `count` of a mesh.
+fn draw_range(count: u32, index_range: Option<&MeshIndexRange>) -> Range<u32> {
+    match index_range {
+        Some(MeshIndexRange(range)) => {
+            let end = range.end.min(count);
+            range.start.min(end)..end
+        }
+        None => 0..count,
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::MeshPipelineKey;
+    use super::{
+        draw_range, extract_changed_mesh_instances, extract_meshes, mesh_color_target,
+        mesh_depth_stencil_state, mesh_vertex_buffer_layouts, mesh_view_layout_entries,
+        ChangedMeshInstances, ClipPlane, DepthBias, Fade, MeshFeaturesUniform, MeshFlags,
+        MeshIndexRange, MeshPipelineKey, MeshUniform, MeshViewBindingExtensions, Outline,
+        PointSprites, StencilConfig, ThickLines, TrackChangedMeshInstances, ViewDepth,
+    };
+    use bevy_asset::{Handle, HandleId};
+    use bevy_core::FloatOrd;
+    use bevy_core_pipeline::MAIN_PASS_3D_DEPTH_FORMAT;
+    use bevy_ecs::prelude::*;
+    use bevy_math::{Mat4, Quat, Vec3, Vec4};
+    use bevy_render::render_resource::{
+        BindingType, BlendState, BufferBindingType, CompareFunction, Face, FrontFace, IndexFormat,
+        PrimitiveTopology, Shader, ShaderProcessor, ShaderStages, StencilFaceState,
+        StencilOperation, TextureFormat, VertexFormat, VertexStepMode,
+    };
+    use bevy_render::{
+        color::Color,
+        mesh::Mesh,
+        view::{ComputedVisibility, ExtractedView},
+    };
+    use bevy_transform::components::GlobalTransform;
+    use bevy_utils::HashMap;
     #[test]
     fn mesh_key_msaa_samples() {
         for i in 1..=64 {
             assert_eq!(MeshPipelineKey::from_msaa_samples(i).msaa_samples(), i);
         }
     }
+
+    #[test]
+    fn mesh_key_strip_index_format() {
+        for index_format in [IndexFormat::Uint16, IndexFormat::Uint32] {
+            for topology in [
+                PrimitiveTopology::TriangleStrip,
+                PrimitiveTopology::LineStrip,
+            ] {
+                let key = MeshPipelineKey::from_msaa_samples(4)
+                    | MeshPipelineKey::from_primitive_topology(topology)
+                    | MeshPipelineKey::from_strip_index_format(topology, Some(index_format));
+                assert_eq!(key.strip_index_format(), Some(index_format));
+                assert_eq!(key.primitive_topology(), topology);
+                assert_eq!(key.msaa_samples(), 4);
+            }
+        }
+
+        let list_key = MeshPipelineKey::from_strip_index_format(
+            PrimitiveTopology::TriangleList,
+            Some(IndexFormat::Uint32),
+        );
+        assert_eq!(list_key.strip_index_format(), None);
+        let non_indexed_key =
+            MeshPipelineKey::from_strip_index_format(PrimitiveTopology::TriangleStrip, None);
+        assert_eq!(non_indexed_key.strip_index_format(), None);
+    }
+
+    #[test]
+    fn mesh_key_unlit_shader_def() {
+        let lit_defs = MeshPipelineKey::VERTEX_TANGENTS.shader_defs();
+        assert!(!lit_defs.iter().any(|def| def == "UNLIT"));
+
+        let unlit_defs = (MeshPipelineKey::VERTEX_TANGENTS | MeshPipelineKey::UNLIT).shader_defs();
+        assert!(unlit_defs.iter().any(|def| def == "UNLIT"));
+        assert!(unlit_defs.iter().any(|def| def == "VERTEX_TANGENTS"));
+    }
+
+    #[test]
+    fn mesh_view_layout_extension() {
+        let default_entries = mesh_view_layout_entries(&MeshViewBindingExtensions::default());
+        assert_eq!(
+            default_entries.len() as u32,
+            MeshViewBindingExtensions::FIRST_BINDING
+        );
+
+        let mut extensions = MeshViewBindingExtensions::default();
+        let ty = BindingType::Buffer {
+            ty: BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        };
+        let binding = extensions.add(ShaderStages::FRAGMENT, ty);
+        assert_eq!(binding, MeshViewBindingExtensions::FIRST_BINDING);
+
+        let entries = mesh_view_layout_entries(&extensions);
+        assert_eq!(entries.len(), default_entries.len() + 1);
+        let extension_entry = entries.last().unwrap();
+        assert_eq!(extension_entry.binding, binding);
+        assert_eq!(extension_entry.visibility, ShaderStages::FRAGMENT);
+        assert_eq!(extension_entry.ty, ty);
+        assert!(extensions.extensions()[0].buffer.is_none());
+    }
+
+    #[test]
+    fn changed_mesh_instances() {
+        let mut world = World::new();
+        world.insert_resource(TrackChangedMeshInstances);
+        let entities = (0..3)
+            .map(|_| {
+                world
+                    .spawn()
+                    .insert_bundle((
+                        Handle::<Mesh>::default(),
+                        GlobalTransform::default(),
+                        ComputedVisibility { is_visible: true },
+                    ))
+                    .id()
+            })
+            .collect::<Vec<_>>();
+        let mut stage = SystemStage::single(extract_changed_mesh_instances);
+
+        stage.run(&mut world);
+        let changed = &world
+            .get_resource::<ChangedMeshInstances>()
+            .unwrap()
+            .entities;
+        assert_eq!(changed.len(), entities.len());
+
+        world
+            .get_mut::<GlobalTransform>(entities[1])
+            .unwrap()
+            .translation
+            .x = 1.0;
+        stage.run(&mut world);
+        let changed = &world
+            .get_resource::<ChangedMeshInstances>()
+            .unwrap()
+            .entities;
+        assert_eq!(changed, &vec![entities[1]]);
+    }
+
+    #[test]
+    fn clip_plane_reaches_mesh_uniform() {
+        let mut world = World::new();
+        let plane = Vec4::new(0.0, 1.0, 0.0, -2.0);
+        let mut spawn_mesh = |clip_plane: Option<ClipPlane>| {
+            let mut entity = world.spawn();
+            entity.insert_bundle((
+                Handle::<Mesh>::default(),
+                GlobalTransform::default(),
+                ComputedVisibility { is_visible: true },
+            ));
+            if let Some(clip_plane) = clip_plane {
+                entity.insert(clip_plane);
+            }
+            entity.id()
+        };
+        let clipped = spawn_mesh(Some(ClipPlane(plane)));
+        let unclipped = spawn_mesh(None);
+
+        SystemStage::single(extract_meshes).run(&mut world);
+
+        let uniform = world.get::<MeshUniform>(clipped).unwrap();
+        assert_ne!(uniform.flags & MeshFlags::CLIP_PLANE.bits(), 0);
+        let features = world.get::<MeshFeaturesUniform>(clipped).unwrap();
+        assert_eq!(features.clip_plane, plane);
+        let uniform = world.get::<MeshUniform>(unclipped).unwrap();
+        assert_eq!(uniform.flags & MeshFlags::CLIP_PLANE.bits(), 0);
+        // meshes without the features don't have their uniform
+        assert!(world.get::<MeshFeaturesUniform>(unclipped).is_none());
+
+        let shader_defs = MeshPipelineKey::CLIP_PLANE.shader_defs();
+        assert!(shader_defs.iter().any(|def| def == "CLIP_PLANE"));
+        assert!(shader_defs.iter().any(|def| def == "MESH_FEATURES"));
+        process_shader(include_str!("mesh.wgsl"), &shader_defs);
+        process_shader(include_str!("pbr.wgsl"), &shader_defs);
+        assert!(!MeshPipelineKey::NONE
+            .shader_defs()
+            .iter()
+            .any(|def| def == "CLIP_PLANE"));
+    }
+
+    #[test]
+    fn fade_reaches_mesh_uniform() {
+        let mut world = World::new();
+        let mut spawn_mesh = |fade: Option<Fade>| {
+            let mut entity = world.spawn();
+            entity.insert_bundle((
+                Handle::<Mesh>::default(),
+                GlobalTransform::default(),
+                ComputedVisibility { is_visible: true },
+            ));
+            if let Some(fade) = fade {
+                entity.insert(fade);
+            }
+            entity.id()
+        };
+        let faded = spawn_mesh(Some(Fade(0.25)));
+        let opaque = spawn_mesh(None);
+
+        SystemStage::single(extract_meshes).run(&mut world);
+
+        let uniform = world.get::<MeshUniform>(faded).unwrap();
+        assert_ne!(uniform.flags & MeshFlags::DITHERED_ALPHA.bits(), 0);
+        assert_eq!(world.get::<MeshFeaturesUniform>(faded).unwrap().fade, 0.25);
+        let uniform = world.get::<MeshUniform>(opaque).unwrap();
+        assert_eq!(uniform.flags & MeshFlags::DITHERED_ALPHA.bits(), 0);
+        assert!(world.get::<MeshFeaturesUniform>(opaque).is_none());
+
+        let key = MeshPipelineKey::from_msaa_samples(1);
+        assert!(!key.shader_defs().iter().any(|def| def == "DITHERED_ALPHA"));
+        let dithered = key | MeshPipelineKey::DITHERED_ALPHA;
+        let shader_defs = dithered.shader_defs();
+        assert!(shader_defs.iter().any(|def| def == "DITHERED_ALPHA"));
+        process_shader(include_str!("mesh.wgsl"), &shader_defs);
+        process_shader(include_str!("pbr.wgsl"), &shader_defs);
+        // the mesh stays opaque
+        assert!(dithered.depth_write_enabled());
+        assert_eq!(
+            mesh_color_target(dithered, TextureFormat::Rgba8Unorm).blend,
+            mesh_color_target(key, TextureFormat::Rgba8Unorm).blend
+        );
+    }
+
+    #[test]
+    fn draw_range_uses_index_range() {
+        assert_eq!(draw_range(300, None), 0..300);
+        assert_eq!(draw_range(300, Some(&MeshIndexRange(120..240))), 120..240);
+        // out of bounds ranges are clamped to the indices of the mesh
+        assert_eq!(draw_range(300, Some(&MeshIndexRange(240..360))), 240..300);
+    }
+
+    #[test]
+    fn mesh_color_target_format() {
+        let key = MeshPipelineKey::from_msaa_samples(4);
+        let target = mesh_color_target(key, TextureFormat::Rg16Float);
+        assert_eq!(target.format, TextureFormat::Rg16Float);
+        assert_eq!(target.blend, Some(BlendState::REPLACE));
+
+        let transparent = key | MeshPipelineKey::TRANSPARENT_MAIN_PASS;
+        let target = mesh_color_target(transparent, TextureFormat::R8Unorm);
+        assert_eq!(target.format, TextureFormat::R8Unorm);
+        assert_eq!(target.blend, Some(BlendState::ALPHA_BLENDING));
+    }
+
+    #[test]
+    fn mesh_key_reverse_winding() {
+        let key = MeshPipelineKey::from_msaa_samples(4)
+            | MeshPipelineKey::from_primitive_topology(PrimitiveTopology::TriangleList);
+        assert_eq!(key.front_face(), FrontFace::Ccw);
+        let reversed = key | MeshPipelineKey::REVERSE_WINDING;
+        assert_eq!(reversed.front_face(), FrontFace::Cw);
+        assert_eq!(reversed.msaa_samples(), 4);
+        assert_eq!(
+            reversed.primitive_topology(),
+            PrimitiveTopology::TriangleList
+        );
+    }
+
+    #[test]
+    fn mesh_key_force_depth_write() {
+        let key = MeshPipelineKey::from_msaa_samples(1);
+        assert!(key.depth_write_enabled());
+        let transparent = key | MeshPipelineKey::TRANSPARENT_MAIN_PASS;
+        assert!(!transparent.depth_write_enabled());
+        let forced = transparent | MeshPipelineKey::FORCE_DEPTH_WRITE;
+        assert!(forced.depth_write_enabled());
+        // the blend state stays the transparent one
+        assert_eq!(
+            mesh_color_target(forced, TextureFormat::Rgba8Unorm).blend,
+            Some(BlendState::ALPHA_BLENDING)
+        );
+    }
+
+    #[test]
+    fn mesh_key_depth_bias() {
+        let key = MeshPipelineKey::from_msaa_samples(4)
+            | MeshPipelineKey::from_primitive_topology(PrimitiveTopology::TriangleStrip)
+            | MeshPipelineKey::from_strip_index_format(
+                PrimitiveTopology::TriangleStrip,
+                Some(IndexFormat::Uint32),
+            );
+        assert_eq!(key.depth_bias(), 0);
+
+        let front = key | MeshPipelineKey::from_depth_bias(DepthBias(4));
+        let back = key | MeshPipelineKey::from_depth_bias(DepthBias(-128));
+        assert_ne!(front, back);
+        assert_eq!(front.depth_bias(), 4);
+        assert_eq!(back.depth_bias(), -128);
+        assert_eq!(back.msaa_samples(), 4);
+        assert_eq!(back.primitive_topology(), PrimitiveTopology::TriangleStrip);
+        assert_eq!(back.strip_index_format(), Some(IndexFormat::Uint32));
+    }
+
+    #[test]
+    fn mesh_key_emissive_only_shader_def() {
+        let key = MeshPipelineKey::from_msaa_samples(1);
+        assert!(!key.shader_defs().iter().any(|def| def == "EMISSIVE_ONLY"));
+        let emissive_only = key | MeshPipelineKey::EMISSIVE_ONLY;
+        assert!(emissive_only
+            .shader_defs()
+            .iter()
+            .any(|def| def == "EMISSIVE_ONLY"));
+
+        // the pbr fragment shader is still valid with it
+        process_shader(include_str!("pbr.wgsl"), &emissive_only.shader_defs());
+    }
+
+    #[test]
+    fn mesh_key_normal_output() {
+        let key = MeshPipelineKey::from_msaa_samples(1)
+            | MeshPipelineKey::from_primitive_topology(PrimitiveTopology::TriangleList);
+        assert!(!key.shader_defs().iter().any(|def| def == "NORMAL_OUTPUT"));
+        for normal_output in [
+            key | MeshPipelineKey::NORMAL_OUTPUT,
+            key | MeshPipelineKey::NORMAL_OUTPUT | MeshPipelineKey::VERTEX_TANGENTS,
+        ] {
+            let shader_defs = normal_output.shader_defs();
+            assert!(shader_defs.iter().any(|def| def == "NORMAL_OUTPUT"));
+            process_shader(include_str!("mesh.wgsl"), &shader_defs);
+            process_shader(include_str!("pbr.wgsl"), &shader_defs);
+        }
+
+        let transparent = key | MeshPipelineKey::TRANSPARENT_MAIN_PASS;
+        let target = mesh_color_target(
+            transparent | MeshPipelineKey::NORMAL_OUTPUT,
+            TextureFormat::Rgba8Unorm,
+        );
+        assert_eq!(target.format, TextureFormat::Rgba8Unorm);
+        assert_eq!(target.blend, Some(BlendState::REPLACE));
+    }
+
+    #[test]
+    fn mesh_key_vertex_pulling() {
+        let key = MeshPipelineKey::from_msaa_samples(1)
+            | MeshPipelineKey::from_primitive_topology(PrimitiveTopology::TriangleList);
+        assert_eq!(mesh_vertex_buffer_layouts(key).len(), 1);
+        assert!(!key.shader_defs().iter().any(|def| def == "VERTEX_PULLING"));
+        for vertex_pulling in [
+            key | MeshPipelineKey::VERTEX_PULLING,
+            key | MeshPipelineKey::VERTEX_PULLING | MeshPipelineKey::VERTEX_TANGENTS,
+        ] {
+            assert!(mesh_vertex_buffer_layouts(vertex_pulling).is_empty());
+            let shader_defs = vertex_pulling.shader_defs();
+            assert!(shader_defs.iter().any(|def| def == "VERTEX_PULLING"));
+            process_shader(include_str!("mesh.wgsl"), &shader_defs);
+        }
+    }
+
+    #[test]
+    fn view_depth_per_view() {
+        let view = |transform: GlobalTransform| ExtractedView {
+            projection: Mat4::IDENTITY,
+            transform,
+            width: 1,
+            height: 1,
+            near: 0.1,
+            far: 100.0,
+        };
+        let front_view = view(GlobalTransform::from_xyz(0.0, 0.0, 10.0));
+        let back_view = view(
+            GlobalTransform::from_xyz(0.0, 0.0, -10.0)
+                .with_rotation(Quat::from_rotation_y(std::f32::consts::PI)),
+        );
+        let mesh = |z: f32| MeshUniform {
+            transform: Mat4::from_translation(Vec3::new(0.0, 0.0, z)),
+            inverse_transpose_model: Mat4::IDENTITY,
+            flags: 0,
+        };
+        let meshes = [("front", mesh(2.0)), ("back", mesh(-2.0))];
+
+        // The transparent phase sorts by ascending mesh_z, drawing back-to-front
+        let transparent_order = |view: &ExtractedView| {
+            let view_depth = ViewDepth::new(view);
+            let mut order = meshes
+                .iter()
+                .map(|(name, mesh)| (FloatOrd(view_depth.mesh_z(mesh)), *name))
+                .collect::<Vec<_>>();
+            order.sort_by_key(|(distance, _)| *distance);
+            order.into_iter().map(|(_, name)| name).collect::<Vec<_>>()
+        };
+        assert_eq!(transparent_order(&front_view), vec!["back", "front"]);
+        assert_eq!(transparent_order(&back_view), vec!["front", "back"]);
+    }
+
+    #[test]
+    fn mesh_key_inverted_hull() {
+        let key = MeshPipelineKey::from_msaa_samples(1)
+            | MeshPipelineKey::from_primitive_topology(PrimitiveTopology::TriangleList);
+        assert_eq!(key.cull_face(), Face::Back);
+        assert!(!key.shader_defs().iter().any(|def| def == "INVERTED_HULL"));
+
+        let inverted_hull = key | MeshPipelineKey::INVERTED_HULL;
+        assert_eq!(inverted_hull.cull_face(), Face::Front);
+        let shader_defs = inverted_hull.shader_defs();
+        assert!(shader_defs.iter().any(|def| def == "INVERTED_HULL"));
+        process_shader(include_str!("mesh.wgsl"), &shader_defs);
+        process_shader(include_str!("pbr.wgsl"), &shader_defs);
+
+        let mut world = World::new();
+        let entity = world
+            .spawn()
+            .insert_bundle((
+                Handle::<Mesh>::default(),
+                GlobalTransform::default(),
+                ComputedVisibility { is_visible: true },
+                Outline {
+                    width: 0.05,
+                    color: Color::BLACK,
+                },
+            ))
+            .id();
+        SystemStage::single(extract_meshes).run(&mut world);
+        let features = world.get::<MeshFeaturesUniform>(entity).unwrap();
+        assert_eq!(features.outline_width, 0.05);
+        assert_eq!(features.outline_color, Vec4::new(0.0, 0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn mesh_key_vertex_normals_2() {
+        let key = MeshPipelineKey::from_msaa_samples(1)
+            | MeshPipelineKey::from_primitive_topology(PrimitiveTopology::TriangleList);
+        let layout = &mesh_vertex_buffer_layouts(key)[0];
+        assert_eq!(layout.array_stride, 32);
+        assert!(!key
+            .shader_defs()
+            .iter()
+            .any(|def| def == "VERTEX_NORMALS_2"));
+
+        for (key, stride) in [
+            (key | MeshPipelineKey::VERTEX_NORMALS_2, 44),
+            (
+                key | MeshPipelineKey::VERTEX_NORMALS_2 | MeshPipelineKey::VERTEX_TANGENTS,
+                60,
+            ),
+        ] {
+            let layout = &mesh_vertex_buffer_layouts(key)[0];
+            assert_eq!(layout.array_stride, stride);
+            let location = |shader_location: u32| {
+                layout
+                    .attributes
+                    .iter()
+                    .find(|attribute| attribute.shader_location == shader_location)
+                    .unwrap()
+            };
+            // Vertex_Normal, Vertex_Normal_2, Vertex_Position, ...
+            assert_eq!(location(1).offset, 0);
+            assert_eq!(location(4).offset, 12);
+            assert_eq!(location(4).format, VertexFormat::Float32x3);
+            assert_eq!(location(0).offset, 24);
+            let mut locations = layout
+                .attributes
+                .iter()
+                .map(|attribute| attribute.shader_location)
+                .collect::<Vec<_>>();
+            locations.sort_unstable();
+            locations.dedup();
+            assert_eq!(locations.len(), layout.attributes.len());
+
+            let shader_defs = key.shader_defs();
+            assert!(shader_defs.iter().any(|def| def == "VERTEX_NORMALS_2"));
+            process_shader(include_str!("mesh.wgsl"), &shader_defs);
+        }
+    }
+
+    /// Runs `source` through the shader processor with the mesh imports, and validates the result
+    /// with naga.
+    fn process_shader(source: &'static str, shader_defs: &[String]) {
+        let imports = [
+            (
+                "bevy_pbr::mesh_view_bind_group",
+                include_str!("mesh_view_bind_group.wgsl"),
+            ),
+            ("bevy_pbr::mesh_struct", include_str!("mesh_struct.wgsl")),
+            (
+                "bevy_pbr::mesh_functions",
+                include_str!("mesh_functions.wgsl"),
+            ),
+        ];
+        let mut shaders = HashMap::default();
+        let mut import_handles = HashMap::default();
+        for (import_path, source) in imports {
+            let shader = Shader::from_wgsl(source).with_import_path(import_path);
+            let handle = Handle::weak(HandleId::random::<Shader>());
+            import_handles.insert(shader.import_path().unwrap().clone(), handle.clone());
+            shaders.insert(handle, shader);
+        }
+        let processed = ShaderProcessor::default()
+            .process(
+                &Shader::from_wgsl(source),
+                shader_defs,
+                &shaders,
+                &import_handles,
+            )
+            .unwrap();
+        processed.reflect().unwrap();
+    }
+
+    #[test]
+    fn mesh_key_point_sprites() {
+        let key = MeshPipelineKey::from_msaa_samples(1)
+            | MeshPipelineKey::from_primitive_topology(PrimitiveTopology::PointList);
+        assert_eq!(key.pipeline_topology(), PrimitiveTopology::PointList);
+        assert_eq!(
+            mesh_vertex_buffer_layouts(key)[0].step_mode,
+            VertexStepMode::Vertex
+        );
+        assert!(!key.shader_defs().iter().any(|def| def == "POINT_SPRITES"));
+
+        let point_sprites = key | MeshPipelineKey::POINT_SPRITES;
+        assert_eq!(
+            point_sprites.primitive_topology(),
+            PrimitiveTopology::PointList
+        );
+        assert_eq!(
+            point_sprites.pipeline_topology(),
+            PrimitiveTopology::TriangleList
+        );
+        assert_eq!(point_sprites.strip_index_format(), None);
+        let layout = &mesh_vertex_buffer_layouts(point_sprites)[0];
+        assert_eq!(layout.step_mode, VertexStepMode::Instance);
+        assert_eq!(layout.array_stride, 32);
+        let shader_defs = point_sprites.shader_defs();
+        assert!(shader_defs.iter().any(|def| def == "POINT_SPRITES"));
+        process_shader(include_str!("mesh.wgsl"), &shader_defs);
+
+        let mut world = World::new();
+        let entity = world
+            .spawn()
+            .insert_bundle((
+                Handle::<Mesh>::default(),
+                GlobalTransform::default(),
+                ComputedVisibility { is_visible: true },
+                PointSprites { size: 8.0 },
+            ))
+            .id();
+        SystemStage::single(extract_meshes).run(&mut world);
+        assert_eq!(
+            world.get::<MeshFeaturesUniform>(entity).unwrap().point_size,
+            8.0
+        );
+    }
+
+    #[test]
+    fn mesh_key_thick_lines() {
+        let key = MeshPipelineKey::from_msaa_samples(1)
+            | MeshPipelineKey::from_primitive_topology(PrimitiveTopology::LineList);
+        assert_eq!(key.pipeline_topology(), PrimitiveTopology::LineList);
+        assert_eq!(mesh_vertex_buffer_layouts(key).len(), 1);
+        assert!(!key.shader_defs().iter().any(|def| def == "THICK_LINES"));
+
+        let thick_lines = key | MeshPipelineKey::THICK_LINES;
+        assert_eq!(
+            thick_lines.pipeline_topology(),
+            PrimitiveTopology::TriangleList
+        );
+        // each instance reads a pair of vertices, the second one from the same buffer bound one
+        // vertex later
+        let layouts = mesh_vertex_buffer_layouts(thick_lines);
+        assert_eq!(layouts.len(), 2);
+        for layout in &layouts {
+            assert_eq!(layout.step_mode, VertexStepMode::Instance);
+            assert_eq!(layout.array_stride, 64);
+        }
+        assert_eq!(layouts[0].attributes.len(), 3);
+        let end_position = &layouts[1].attributes[0];
+        assert_eq!(end_position.shader_location, 5);
+        assert_eq!(end_position.format, VertexFormat::Float32x3);
+        // the position comes after the normal, attributes being sorted by name
+        assert_eq!(end_position.offset, 12);
+        let shader_defs = thick_lines.shader_defs();
+        assert!(shader_defs.iter().any(|def| def == "THICK_LINES"));
+        assert!(shader_defs.iter().any(|def| def == "MESH_FEATURES"));
+        process_shader(include_str!("mesh.wgsl"), &shader_defs);
+        process_shader(include_str!("pbr.wgsl"), &shader_defs);
+
+        let mut world = World::new();
+        let entity = world
+            .spawn()
+            .insert_bundle((
+                Handle::<Mesh>::default(),
+                GlobalTransform::default(),
+                ComputedVisibility { is_visible: true },
+                ThickLines { width: 3.0 },
+            ))
+            .id();
+        SystemStage::single(extract_meshes).run(&mut world);
+        assert_eq!(
+            world.get::<MeshFeaturesUniform>(entity).unwrap().line_width,
+            3.0
+        );
+    }
+
+    #[test]
+    fn mesh_stencil_state() {
+        let key = MeshPipelineKey::from_msaa_samples(1)
+            | MeshPipelineKey::from_primitive_topology(PrimitiveTopology::TriangleList);
+        let state = mesh_depth_stencil_state(key, None);
+        assert_eq!(state.format, MAIN_PASS_3D_DEPTH_FORMAT);
+        assert_eq!(state.stencil.front, StencilFaceState::IGNORE);
+        assert_eq!(state.stencil.write_mask, 0);
+
+        // writes the reference where the mesh is drawn, for example to mask a selection outline
+        let write = StencilFaceState {
+            compare: CompareFunction::Always,
+            fail_op: StencilOperation::Keep,
+            depth_fail_op: StencilOperation::Keep,
+            pass_op: StencilOperation::Replace,
+        };
+        let stencil = StencilConfig {
+            front: write,
+            back: StencilFaceState::IGNORE,
+            read_mask: 0xff,
+            write_mask: 0x0f,
+            reference: 1,
+        };
+        let stencil_key = key | MeshPipelineKey::STENCIL;
+        let state = mesh_depth_stencil_state(stencil_key, Some(&stencil));
+        assert_eq!(state.format, MAIN_PASS_3D_DEPTH_FORMAT);
+        assert_eq!(state.stencil.front, write);
+        assert_eq!(state.stencil.back, StencilFaceState::IGNORE);
+        assert_eq!(state.stencil.read_mask, 0xff);
+        assert_eq!(state.stencil.write_mask, 0x0f);
+        assert!(state.stencil.is_enabled());
+        // the depth state is unchanged
+        assert_eq!(state.depth_compare, CompareFunction::Greater);
+        assert!(state.depth_write_enabled);
+    }
 }
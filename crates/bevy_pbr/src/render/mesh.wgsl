@@ -1,6 +1,29 @@
 #import bevy_pbr::mesh_view_bind_group
 #import bevy_pbr::mesh_struct
+#import bevy_pbr::mesh_functions
 
+#ifdef VERTEX_PULLING
+// The vertices are read from the first mesh view binding extension, with the storage buffer
+// layout rules: the `vec3` fields are aligned to 16 bytes.
+struct Vertex {
+    position: vec3<f32>;
+    normal: vec3<f32>;
+    uv: vec2<f32>;
+#ifdef VERTEX_TANGENTS
+    tangent: vec4<f32>;
+#endif
+#ifdef VERTEX_NORMALS_2
+    normal_2: vec3<f32>;
+#endif
+};
+
+struct PulledVertices {
+    vertices: array<Vertex>;
+};
+
+[[group(0), binding(9)]]
+var<storage, read> pulled_vertices: PulledVertices;
+#else
 struct Vertex {
     [[location(0)]] position: vec3<f32>;
     [[location(1)]] normal: vec3<f32>;
@@ -8,7 +31,11 @@ struct Vertex {
 #ifdef VERTEX_TANGENTS
     [[location(3)]] tangent: vec4<f32>;
 #endif
+#ifdef VERTEX_NORMALS_2
+    [[location(4)]] normal_2: vec3<f32>;
+#endif
 };
+#endif
 
 struct VertexOutput {
     [[builtin(position)]] clip_position: vec4<f32>;
@@ -18,24 +45,109 @@ struct VertexOutput {
 #ifdef VERTEX_TANGENTS
     [[location(3)]] world_tangent: vec4<f32>;
 #endif
+#ifdef NORMAL_OUTPUT
+    [[location(4)]] object_normal: vec3<f32>;
+#endif
+#ifdef VERTEX_NORMALS_2
+    [[location(5)]] world_normal_2: vec3<f32>;
+#endif
 };
 
 [[group(2), binding(0)]]
 var<uniform> mesh: Mesh;
+#ifdef MESH_FEATURES
+[[group(3), binding(0)]]
+var<uniform> mesh_features: MeshFeatures;
+#endif
 
+#ifdef VERTEX_PULLING
+[[stage(vertex)]]
+fn vertex([[builtin(vertex_index)]] vertex_index: u32) -> VertexOutput {
+    let vertex = pulled_vertices.vertices[vertex_index];
+#else
+#ifdef POINT_SPRITES
+[[stage(vertex)]]
+fn vertex(vertex: Vertex, [[builtin(vertex_index)]] corner_index: u32) -> VertexOutput {
+#else
+#ifdef THICK_LINES
+[[stage(vertex)]]
+fn vertex(
+    vertex: Vertex,
+    [[location(5)]] end_position: vec3<f32>,
+    [[builtin(vertex_index)]] corner_index: u32
+) -> VertexOutput {
+#else
 [[stage(vertex)]]
 fn vertex(vertex: Vertex) -> VertexOutput {
+#endif
+#endif
+#endif
+    let world_normal = mat3x3<f32>(
+        mesh.inverse_transpose_model[0].xyz,
+        mesh.inverse_transpose_model[1].xyz,
+        mesh.inverse_transpose_model[2].xyz
+    ) * vertex.normal;
+#ifdef INVERTED_HULL
+    // inflates the mesh along its normals, so that its back faces show around the mesh
+    let world_position = mesh.model * vec4<f32>(vertex.position, 1.0)
+        + vec4<f32>(normalize(world_normal) * mesh_features.outline_width, 0.0);
+#else
     let world_position = mesh.model * vec4<f32>(vertex.position, 1.0);
+#endif
 
     var out: VertexOutput;
     out.uv = vertex.uv;
     out.world_position = world_position;
     out.clip_position = view.view_proj * world_position;
-    out.world_normal = mat3x3<f32>(
-        mesh.inverse_transpose_model[0].xyz,
-        mesh.inverse_transpose_model[1].xyz,
-        mesh.inverse_transpose_model[2].xyz
-    ) * vertex.normal;
+#ifdef POINT_SPRITES
+    // the two counter-clockwise triangles of the quad around the point
+    var corners: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0)
+    );
+    let corner = corners[corner_index];
+    // offsets the corner by half the point size in pixels, after the perspective division
+    let offset = corner * mesh_features.point_size / vec2<f32>(view.width, view.height);
+    out.clip_position = out.clip_position
+        + vec4<f32>(offset * out.clip_position.w, 0.0, 0.0);
+    out.uv = corner * vec2<f32>(0.5, -0.5) + 0.5;
+#endif
+#ifdef THICK_LINES
+    // the two counter-clockwise triangles of the quad along the segment, as the position along
+    // the segment and the side of the segment the corner is on
+    var corners: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0)
+    );
+    let corner = corners[corner_index];
+    let end_world_position = mesh.model * vec4<f32>(end_position, 1.0);
+    let end_clip_position = view.view_proj * end_world_position;
+    let view_size = vec2<f32>(view.width, view.height);
+    // the direction of the segment in pixels, after the perspective division
+    let direction = normalize(
+        (end_clip_position.xy / end_clip_position.w - out.clip_position.xy / out.clip_position.w)
+            * view_size
+    );
+    // the other attributes of the end of the segment are the ones of its start
+    if (corner.x > 0.5) {
+        out.world_position = end_world_position;
+        out.clip_position = end_clip_position;
+    }
+    // offsets the corner by half the line width in pixels, across the segment
+    let offset = vec2<f32>(-direction.y, direction.x) * corner.y * mesh_features.line_width
+        / view_size;
+    out.clip_position = out.clip_position
+        + vec4<f32>(offset * out.clip_position.w, 0.0, 0.0);
+#endif
+    out.world_normal = world_normal;
 #ifdef VERTEX_TANGENTS
     out.world_tangent = vec4<f32>(
         mat3x3<f32>(
@@ -45,21 +157,59 @@ fn vertex(vertex: Vertex) -> VertexOutput {
         ) * vertex.tangent.xyz,
         vertex.tangent.w
     );
+#endif
+#ifdef NORMAL_OUTPUT
+    out.object_normal = vertex.normal;
+#endif
+#ifdef VERTEX_NORMALS_2
+    out.world_normal_2 = mat3x3<f32>(
+        mesh.inverse_transpose_model[0].xyz,
+        mesh.inverse_transpose_model[1].xyz,
+        mesh.inverse_transpose_model[2].xyz
+    ) * vertex.normal_2;
 #endif
     return out;
 }
 
 struct FragmentInput {
     [[builtin(front_facing)]] is_front: bool;
+#ifdef DITHERED_ALPHA
+    [[builtin(position)]] frag_coord: vec4<f32>;
+#endif
     [[location(0)]] world_position: vec4<f32>;
     [[location(1)]] world_normal: vec3<f32>;
     [[location(2)]] uv: vec2<f32>;
 #ifdef VERTEX_TANGENTS
     [[location(3)]] world_tangent: vec4<f32>;
 #endif
+#ifdef NORMAL_OUTPUT
+    [[location(4)]] object_normal: vec3<f32>;
+#endif
+#ifdef VERTEX_NORMALS_2
+    [[location(5)]] world_normal_2: vec3<f32>;
+#endif
 };
 
 [[stage(fragment)]]
 fn fragment(in: FragmentInput) -> [[location(0)]] vec4<f32> {
+#ifdef CLIP_PLANE
+    if (dot(mesh_features.clip_plane, vec4<f32>(in.world_position.xyz, 1.0)) < 0.0) {
+        discard;
+    }
+#endif
+#ifdef DITHERED_ALPHA
+    if (is_dithered_out(in.frag_coord, mesh_features.fade)) {
+        discard;
+    }
+#endif
+#ifdef INVERTED_HULL
+    return mesh_features.outline_color;
+#else
+#ifdef NORMAL_OUTPUT
+    // maps the [-1, 1] components of the normal to [0, 1]
+    return vec4<f32>(normalize(in.object_normal) * 0.5 + 0.5, 1.0);
+#else
     return vec4<f32>(1.0, 0.0, 1.0, 1.0);
+#endif
+#endif
 }
\ No newline at end of file
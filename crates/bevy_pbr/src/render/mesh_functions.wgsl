@@ -0,0 +1,12 @@
+// Whether the pixel at `frag_coord` is discarded by screen-door transparency for a mesh with
+// the given fade, used with the DITHERED_ALPHA shader def.
+//
+// The threshold of the pixel is its value in a 4x4 Bayer matrix, built by interleaving the bits
+// of x ^ y and y in reverse order.
+fn is_dithered_out(frag_coord: vec4<f32>, fade: f32) -> bool {
+    let x = u32(frag_coord.x) % 4u;
+    let y = u32(frag_coord.y) % 4u;
+    let xy = x ^ y;
+    let bayer = ((xy & 1u) << 3u) | ((y & 1u) << 2u) | (xy & 2u) | ((y & 2u) >> 1u);
+    return fade <= (f32(bayer) + 0.5) / 16.0;
+}
@@ -5,4 +5,21 @@ struct Mesh {
     flags: u32;
 };
 
+// Only bound with the MESH_FEATURES shader def
+struct MeshFeatures {
+    // Only used with the CLIP_PLANE shader def
+    clip_plane: vec4<f32>;
+    // Only used with the INVERTED_HULL shader def
+    outline_color: vec4<f32>;
+    outline_width: f32;
+    // Only used with the POINT_SPRITES shader def
+    point_size: f32;
+    // Only used with the DITHERED_ALPHA shader def
+    fade: f32;
+    // Only used with the THICK_LINES shader def
+    line_width: f32;
+};
+
 let MESH_FLAGS_SHADOW_RECEIVER_BIT: u32 = 1u;
+let MESH_FLAGS_CLIP_PLANE_BIT: u32 = 2u;
+let MESH_FLAGS_DITHERED_ALPHA_BIT: u32 = 4u;
@@ -34,9 +34,14 @@
 
 #import bevy_pbr::mesh_view_bind_group
 #import bevy_pbr::mesh_struct
+#import bevy_pbr::mesh_functions
 
 [[group(2), binding(0)]]
 var<uniform> mesh: Mesh;
+#ifdef MESH_FEATURES
+[[group(3), binding(0)]]
+var<uniform> mesh_features: MeshFeatures;
+#endif
 
 struct StandardMaterial {
     base_color: vec4<f32>;
@@ -456,15 +461,39 @@ struct FragmentInput {
 #ifdef VERTEX_TANGENTS
     [[location(3)]] world_tangent: vec4<f32>;
 #endif
+#ifdef NORMAL_OUTPUT
+    [[location(4)]] object_normal: vec3<f32>;
+#endif
 };
 
 [[stage(fragment)]]
 fn fragment(in: FragmentInput) -> [[location(0)]] vec4<f32> {
+#ifdef CLIP_PLANE
+    if (dot(mesh_features.clip_plane, vec4<f32>(in.world_position.xyz, 1.0)) < 0.0) {
+        discard;
+    }
+#endif
+#ifdef DITHERED_ALPHA
+    if (is_dithered_out(in.frag_coord, mesh_features.fade)) {
+        discard;
+    }
+#endif
+
+#ifdef INVERTED_HULL
+    // the inflated back faces are flat in the color of the outline, like in mesh.wgsl
+    return mesh_features.outline_color;
+#else
+#ifdef NORMAL_OUTPUT
+    // maps the [-1, 1] components of the normal to [0, 1], like mesh.wgsl
+    return vec4<f32>(normalize(in.object_normal) * 0.5 + 0.5, 1.0);
+#else
     var output_color: vec4<f32> = material.base_color;
     if ((material.flags & STANDARD_MATERIAL_FLAGS_BASE_COLOR_TEXTURE_BIT) != 0u) {
         output_color = output_color * textureSample(base_color_texture, base_color_sampler, in.uv);
     }
 
+    // NOTE: Unlit pipelines skip lighting entirely
+#ifndef UNLIT
     // // NOTE: Unlit bit not set means == 0 is true, so the true case is if lit
     if ((material.flags & STANDARD_MATERIAL_FLAGS_UNLIT_BIT) == 0u) {
         // TODO use .a for exposure compensation in HDR
@@ -593,11 +622,15 @@ fn fragment(in: FragmentInput) -> [[location(0)]] vec4<f32> {
         let diffuse_ambient = EnvBRDFApprox(diffuse_color, 1.0, NdotV);
         let specular_ambient = EnvBRDFApprox(F0, perceptual_roughness, NdotV);
 
+#ifdef EMISSIVE_ONLY
+        output_color = vec4<f32>(emissive.rgb * output_color.a, output_color.a);
+#else
         output_color = vec4<f32>(
             light_accum +
                 (diffuse_ambient + specular_ambient) * lights.ambient_color.rgb * occlusion +
                 emissive.rgb * output_color.a,
             output_color.a);
+#endif // EMISSIVE_ONLY
 
         // Cluster allocation debug (using 'over' alpha blending)
 #ifdef CLUSTERED_FORWARD_DEBUG_Z_SLICES
@@ -640,6 +673,9 @@ fn fragment(in: FragmentInput) -> [[location(0)]] vec4<f32> {
         // Not needed with sRGB buffer
         // output_color.rgb = pow(output_color.rgb, vec3(1.0 / 2.2));
     }
+#endif // UNLIT
 
     return output_color;
+#endif // NORMAL_OUTPUT
+#endif // INVERTED_HULL
 }
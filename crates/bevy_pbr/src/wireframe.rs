@@ -1,5 +1,8 @@
 use crate::MeshPipeline;
-use crate::{DrawMesh, MeshPipelineKey, MeshUniform, SetMeshBindGroup, SetMeshViewBindGroup};
+use crate::{
+    DrawMesh, MeshPipelineKey, MeshUniform, PointSprites, SetMeshBindGroup, SetMeshViewBindGroup,
+    ThickLines, VertexPulling, ViewDepth,
+};
 use bevy_app::Plugin;
 use bevy_asset::{Assets, Handle, HandleUntyped};
 use bevy_core_pipeline::Opaque3d;
@@ -92,6 +95,14 @@ impl SpecializedPipeline for WireframePipeline {
     }
 }
 
+/// The wireframe pipeline isn't specialized for [`VertexPulling`], [`PointSprites`] or
+/// [`ThickLines`], which [`DrawMesh`] draws differently, so their meshes have no wireframe.
+type WireframeMeshFilter = (
+    Without<VertexPulling>,
+    Without<PointSprites>,
+    Without<ThickLines>,
+);
+
 #[allow(clippy::too_many_arguments)]
 fn queue_wireframes(
     opaque_3d_draw_functions: Res<DrawFunctions<Opaque3d>>,
@@ -102,8 +113,8 @@ fn queue_wireframes(
     mut specialized_pipelines: ResMut<SpecializedPipelines<WireframePipeline>>,
     msaa: Res<Msaa>,
     mut material_meshes: QuerySet<(
-        QueryState<(Entity, &Handle<Mesh>, &MeshUniform)>,
-        QueryState<(Entity, &Handle<Mesh>, &MeshUniform), With<Wireframe>>,
+        QueryState<(Entity, &Handle<Mesh>, &MeshUniform), WireframeMeshFilter>,
+        QueryState<(Entity, &Handle<Mesh>, &MeshUniform), (With<Wireframe>, WireframeMeshFilter)>,
     )>,
     mut views: Query<(&ExtractedView, &mut RenderPhase<Opaque3d>)>,
 ) {
@@ -113,14 +124,17 @@ fn queue_wireframes(
         .unwrap();
     let key = MeshPipelineKey::from_msaa_samples(msaa.samples);
     for (view, mut transparent_phase) in views.iter_mut() {
-        let view_matrix = view.transform.compute_matrix();
-        let view_row_2 = view_matrix.row(2);
+        let view_depth = ViewDepth::new(view);
 
         let add_render_phase =
             |(entity, mesh_handle, mesh_uniform): (Entity, &Handle<Mesh>, &MeshUniform)| {
                 if let Some(mesh) = render_meshes.get(mesh_handle) {
-                    let key =
-                        key | MeshPipelineKey::from_primitive_topology(mesh.primitive_topology);
+                    let key = key
+                        | MeshPipelineKey::from_primitive_topology(mesh.primitive_topology)
+                        | MeshPipelineKey::from_strip_index_format(
+                            mesh.primitive_topology,
+                            mesh.buffer_info.index_format(),
+                        );
                     transparent_phase.add(Opaque3d {
                         entity,
                         pipeline: specialized_pipelines.specialize(
@@ -129,7 +143,8 @@ fn queue_wireframes(
                             key,
                         ),
                         draw_function: draw_custom,
-                        distance: view_row_2.dot(mesh_uniform.transform.col(3)),
+                        // NOTE: Front-to-back ordering for opaque, like for the materials
+                        distance: -view_depth.mesh_z(mesh_uniform),
                     });
                 }
             };
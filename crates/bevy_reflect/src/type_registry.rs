@@ -1,17 +1,44 @@
-use crate::Reflect;
+use crate::{FromReflect, Reflect};
 use bevy_utils::{HashMap, HashSet};
 use downcast_rs::{impl_downcast, Downcast};
 use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 use serde::Deserialize;
-use std::{any::TypeId, fmt::Debug, sync::Arc};
+use std::{
+    any::TypeId,
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
 /// A registry of reflected types.
-#[derive(Default)]
 pub struct TypeRegistry {
     registrations: HashMap<TypeId, TypeRegistration>,
     short_name_to_id: HashMap<String, TypeId>,
     full_name_to_id: HashMap<String, TypeId>,
     ambiguous_names: HashSet<String>,
+    generation: u64,
+}
+
+/// The source of [`TypeRegistry::generation`], shared by all the registries so that no two of
+/// them ever have the same generation.
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn next_generation() -> u64 {
+    NEXT_GENERATION.fetch_add(1, Ordering::Relaxed)
+}
+
+impl Default for TypeRegistry {
+    fn default() -> Self {
+        TypeRegistry {
+            registrations: Default::default(),
+            short_name_to_id: Default::default(),
+            full_name_to_id: Default::default(),
+            ambiguous_names: Default::default(),
+            generation: next_generation(),
+        }
+    }
 }
 
 // TODO:  remove this wrapper once we migrate to Atelier Assets and the Scene AssetLoader doesn't
@@ -61,6 +88,7 @@ impl TypeRegistry {
             .insert(registration.name.to_string(), registration.type_id);
         self.registrations
             .insert(registration.type_id, registration);
+        self.generation = next_generation();
     }
 
     /// Returns a reference to the [`TypeRegistration`] of the type with the
@@ -80,7 +108,10 @@ impl TypeRegistry {
     ///
     /// [`TypeId`]: std::any::TypeId
     pub fn get_mut(&mut self, type_id: TypeId) -> Option<&mut TypeRegistration> {
-        self.registrations.get_mut(&type_id)
+        let registration = self.registrations.get_mut(&type_id)?;
+        // the registration may be changed through the reference
+        self.generation = next_generation();
+        Some(registration)
     }
 
     /// Returns a reference to the [`TypeRegistration`] of the type with the
@@ -134,6 +165,15 @@ impl TypeRegistry {
     pub fn iter(&self) -> impl Iterator<Item = &TypeRegistration> {
         self.registrations.values()
     }
+
+    /// Returns a generation which changes each time a type is registered, or a registration is
+    /// accessed mutably, for example to know when data derived from the registry is outdated.
+    ///
+    /// Generations are unique across all the registries, so data derived from another registry
+    /// never has the same generation.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
 }
 
 impl TypeRegistryArc {
@@ -350,9 +390,90 @@ impl<T: for<'a> Deserialize<'a> + Reflect> FromType<T> for ReflectDeserialize {
     }
 }
 
+/// A struct used to construct reflected instances of a type from a reflected value.
+///
+/// A `ReflectFromReflect` for type `T` can be obtained via
+/// [`FromType::from_type`].
+#[derive(Clone)]
+pub struct ReflectFromReflect {
+    pub func: fn(&dyn Reflect) -> Option<Box<dyn Reflect>>,
+}
+
+impl ReflectFromReflect {
+    /// Constructs a concrete instance of the underlying type from a reflected value.
+    ///
+    /// The returned value is boxed as a `dyn Reflect` but is always of the type used to
+    /// construct this `ReflectFromReflect` value, so it can be downcast to that type.
+    /// Returns `None` if the value could not be converted.
+    pub fn from_reflect(&self, reflect: &dyn Reflect) -> Option<Box<dyn Reflect>> {
+        (self.func)(reflect)
+    }
+}
+
+impl<T: FromReflect> FromType<T> for ReflectFromReflect {
+    fn from_type() -> Self {
+        ReflectFromReflect {
+            func: |reflect| {
+                T::from_reflect(reflect).map(|value| Box::new(value) as Box<dyn Reflect>)
+            },
+        }
+    }
+}
+
+/// A struct used to construct the default value of a reflected type.
+///
+/// A `ReflectDefault` for type `T` can be obtained via
+/// [`FromType::from_type`].
+#[derive(Clone)]
+pub struct ReflectDefault {
+    pub func: fn() -> Box<dyn Reflect>,
+}
+
+impl ReflectDefault {
+    /// Constructs the [`Default`] value of the underlying type, boxed as a `dyn Reflect`.
+    pub fn default(&self) -> Box<dyn Reflect> {
+        (self.func)()
+    }
+}
+
+impl<T: Reflect + Default> FromType<T> for ReflectDefault {
+    fn from_type() -> Self {
+        ReflectDefault {
+            func: || Box::new(T::default()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::TypeRegistration;
+    use crate::{TypeRegistration, TypeRegistry};
+
+    #[test]
+    fn generation_changes_on_registration() {
+        let mut registry = TypeRegistry::default();
+        let generation = registry.generation();
+        registry.register::<u32>();
+        assert_ne!(registry.generation(), generation);
+
+        let generation = registry.generation();
+        registry.get(std::any::TypeId::of::<u32>()).unwrap();
+        assert_eq!(registry.generation(), generation);
+        registry.get_mut(std::any::TypeId::of::<u32>()).unwrap();
+        assert_ne!(registry.generation(), generation);
+
+        // looking an unregistered type up mutably changes nothing
+        let generation = registry.generation();
+        assert!(registry.get_mut(std::any::TypeId::of::<u64>()).is_none());
+        assert_eq!(registry.generation(), generation);
+
+        let mut other_registry = TypeRegistry::default();
+        assert_ne!(
+            other_registry.generation(),
+            TypeRegistry::default().generation()
+        );
+        other_registry.register::<u32>();
+        assert_ne!(other_registry.generation(), registry.generation());
+    }
 
     #[test]
     fn test_get_short_name() {
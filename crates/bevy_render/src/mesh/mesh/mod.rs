@@ -55,6 +55,9 @@ impl Mesh {
     /// The direction the vertex normal is facing in.
     /// Use in conjunction with [`Mesh::set_attribute`]
     pub const ATTRIBUTE_NORMAL: &'static str = "Vertex_Normal";
+    /// A second set of normals, for example smoothed ones to blend with the geometric ones.
+    /// Use in conjunction with [`Mesh::set_attribute`]
+    pub const ATTRIBUTE_NORMAL_2: &'static str = "Vertex_Normal_2";
     /// The direction of the vertex tangent. Used for normal mapping
     pub const ATTRIBUTE_TANGENT: &'static str = "Vertex_Tangent";
 
@@ -593,6 +596,7 @@ pub struct GpuMesh {
     pub vertex_buffer: Buffer,
     pub buffer_info: GpuBufferInfo,
     pub has_tangents: bool,
+    pub has_normals_2: bool,
     pub primitive_topology: PrimitiveTopology,
 }
 
@@ -610,6 +614,16 @@ pub enum GpuBufferInfo {
     },
 }
 
+impl GpuBufferInfo {
+    /// Returns the format of the index buffer, or [`None`] if the mesh is not indexed.
+    pub fn index_format(&self) -> Option<IndexFormat> {
+        match self {
+            GpuBufferInfo::Indexed { index_format, .. } => Some(*index_format),
+            GpuBufferInfo::NonIndexed { .. } => None,
+        }
+    }
+}
+
 impl RenderAsset for Mesh {
     type ExtractedAsset = Mesh;
     type PreparedAsset = GpuMesh;
@@ -651,6 +665,7 @@ impl RenderAsset for Mesh {
             vertex_buffer,
             buffer_info,
             has_tangents: mesh.attributes.contains_key(Mesh::ATTRIBUTE_TANGENT),
+            has_normals_2: mesh.attributes.contains_key(Mesh::ATTRIBUTE_NORMAL_2),
             primitive_topology: mesh.primitive_topology(),
         })
     }
@@ -26,9 +26,107 @@ impl<S: SpecializedPipeline> SpecializedPipelines<S> {
             cache.queue(descriptor)
         })
     }
+
+    /// Specializes and queues the pipelines for `keys` ahead of time, so that they are already
+    /// compiled when they are first needed instead of causing a hitch on that frame, for example
+    /// while a loading screen is displayed.
+    ///
+    /// The keys must be the ones the systems queuing the draws will later pass to
+    /// [`SpecializedPipelines::specialize`], which then returns the same ids. The pipelines are
+    /// compiled the next time the [`RenderPipelineCache`] processes its queue.
+    pub fn prewarm(
+        &mut self,
+        cache: &mut RenderPipelineCache,
+        specialize_pipeline: &S,
+        keys: impl IntoIterator<Item = S::Key>,
+    ) -> Vec<CachedPipelineId> {
+        keys.into_iter()
+            .map(|key| self.specialize(cache, specialize_pipeline, key))
+            .collect()
+    }
 }
 
 pub trait SpecializedPipeline {
     type Key: Clone + Hash + PartialEq + Eq;
     fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{SpecializedPipeline, SpecializedPipelines};
+    use crate::{
+        render_resource::{RenderPipelineCache, RenderPipelineDescriptor, VertexState},
+        renderer::RenderDevice,
+    };
+    use std::{cell::RefCell, sync::Arc};
+
+    #[derive(Default)]
+    struct TestPipeline {
+        specialized: RefCell<Vec<u32>>,
+    }
+
+    impl SpecializedPipeline for TestPipeline {
+        type Key = u32;
+
+        fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+            self.specialized.borrow_mut().push(key);
+            RenderPipelineDescriptor {
+                label: None,
+                layout: None,
+                vertex: VertexState {
+                    shader: Default::default(),
+                    shader_defs: Vec::new(),
+                    entry_point: "vertex".into(),
+                    buffers: Vec::new(),
+                },
+                primitive: Default::default(),
+                depth_stencil: None,
+                multisample: Default::default(),
+                fragment: None,
+            }
+        }
+    }
+
+    /// Requests a device from any available adapter, falling back to a software one, or returns
+    /// `None` on machines without either.
+    fn render_device() -> Option<RenderDevice> {
+        let instance = wgpu::Instance::new(wgpu::Backends::all());
+        futures_lite::future::block_on(async {
+            let adapter = instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    force_fallback_adapter: true,
+                    ..Default::default()
+                })
+                .await?;
+            let (device, _queue) = adapter
+                .request_device(&Default::default(), None)
+                .await
+                .ok()?;
+            Some(RenderDevice::from(Arc::new(device)))
+        })
+    }
+
+    #[test]
+    fn prewarmed_pipelines_are_reused() {
+        let device = match render_device() {
+            Some(device) => device,
+            None => return,
+        };
+        let mut cache = RenderPipelineCache::new(device);
+        let mut pipelines = SpecializedPipelines::<TestPipeline>::default();
+        let pipeline = TestPipeline::default();
+
+        let ids = pipelines.prewarm(&mut cache, &pipeline, [1, 2, 1]);
+        assert_eq!(*pipeline.specialized.borrow(), vec![1, 2]);
+        assert_ne!(ids[0], ids[1]);
+        assert_eq!(ids[0], ids[2]);
+
+        for (key, prewarmed_id) in [(1, ids[0]), (2, ids[1])] {
+            assert_eq!(
+                pipelines.specialize(&mut cache, &pipeline, key),
+                prewarmed_id
+            );
+        }
+        assert_eq!(*pipeline.specialized.borrow(), vec![1, 2]);
+    }
+}
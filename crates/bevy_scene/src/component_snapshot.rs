@@ -0,0 +1,371 @@
+use crate::{
+    scene_patch::reflect_component_named,
+    serde::{ComponentVecDeserializer, ComponentsSerializer},
+    serialize_ron, SceneSpawnError,
+};
+use bevy_ecs::{entity::Entity, world::World};
+use bevy_reflect::{Reflect, TypeRegistry, TypeRegistryArc};
+use serde::{
+    de::{DeserializeSeed, Error, MapAccess, SeqAccess, Visitor},
+    ser::{SerializeSeq, SerializeStruct},
+    Deserialize, Serialize,
+};
+use thiserror::Error;
+
+/// An error while taking or applying a snapshot of components with [`serialize_components_ron`]
+/// and [`apply_components_ron`].
+#[derive(Error, Debug)]
+pub enum ComponentSnapshotError {
+    /// A component type can't be reflected.
+    #[error(transparent)]
+    Unregistered(#[from] SceneSpawnError),
+    #[error("invalid component snapshot: {0}")]
+    Ron(#[from] ron::Error),
+}
+
+/// The components of an entity in a snapshot.
+type SnapshotEntity = (Entity, Vec<Box<dyn Reflect>>);
+
+/// Serializes the components of `entities` whose type name, as given by
+/// [`std::any::type_name`], is in `component_types` into rust object notation (ron), for example
+/// to debug them or to save a part of the state of the game.
+///
+/// Unlike a [`DynamicScene`](crate::DynamicScene) of the whole world, only the listed components
+/// are taken, in the order of `component_types`. Missing components and entities are skipped. The
+/// snapshot lists the entities like a scene, along with the generation of their id, and is
+/// applied back with [`apply_components_ron`].
+pub fn serialize_components_ron(
+    world: &World,
+    entities: &[Entity],
+    component_types: &[&str],
+    registry: &TypeRegistryArc,
+) -> Result<String, ComponentSnapshotError> {
+    let snapshot = {
+        let type_registry = registry.read();
+        let reflect_components = component_types
+            .iter()
+            .map(|type_name| reflect_component_named(&type_registry, type_name))
+            .collect::<Result<Vec<_>, _>>()?;
+        entities
+            .iter()
+            .filter(|entity| world.get_entity(**entity).is_some())
+            .map(|entity| {
+                let components = reflect_components
+                    .iter()
+                    .filter_map(|reflect_component| {
+                        reflect_component.reflect_component(world, *entity)
+                    })
+                    .map(|component| component.clone_value())
+                    .collect();
+                (*entity, components)
+            })
+            .collect::<Vec<_>>()
+    };
+    Ok(serialize_ron(SnapshotSerializer {
+        entities: &snapshot,
+        registry,
+    })?)
+}
+
+/// Applies the components of a snapshot taken by [`serialize_components_ron`] to the same
+/// entities, inserting the ones they don't have anymore.
+///
+/// Nothing is applied if a component type of the snapshot can't be reflected. The entities which
+/// don't exist anymore are skipped, even if their id was reused by another entity since the
+/// snapshot was taken.
+pub fn apply_components_ron(
+    world: &mut World,
+    ron: &str,
+    registry: &TypeRegistryArc,
+) -> Result<(), ComponentSnapshotError> {
+    let type_registry = registry.read();
+    let mut deserializer = ron::de::Deserializer::from_str(ron)?;
+    let snapshot = SnapshotDeserializer {
+        type_registry: &type_registry,
+    }
+    .deserialize(&mut deserializer)?;
+
+    let mut entities = Vec::with_capacity(snapshot.len());
+    for (entity, components) in snapshot.iter() {
+        let components = components
+            .iter()
+            .map(|component| {
+                reflect_component_named(&type_registry, component.type_name())
+                    .map(|reflect_component| (reflect_component, component))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        entities.push((*entity, components));
+    }
+
+    for (entity, components) in entities {
+        if world.get_entity(entity).is_none() {
+            continue;
+        }
+        for (reflect_component, component) in components {
+            reflect_component
+                .apply_or_insert(world, entity, &**component, &type_registry)
+                .map_err(SceneSpawnError::from)?;
+        }
+    }
+    Ok(())
+}
+
+struct SnapshotSerializer<'a> {
+    entities: &'a [SnapshotEntity],
+    registry: &'a TypeRegistryArc,
+}
+
+impl<'a> Serialize for SnapshotSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_seq(Some(self.entities.len()))?;
+        for (entity, components) in self.entities.iter() {
+            state.serialize_element(&SnapshotEntitySerializer {
+                entity: *entity,
+                components,
+                registry: self.registry,
+            })?;
+        }
+        state.end()
+    }
+}
+
+struct SnapshotEntitySerializer<'a> {
+    entity: Entity,
+    components: &'a [Box<dyn Reflect>],
+    registry: &'a TypeRegistryArc,
+}
+
+impl<'a> Serialize for SnapshotEntitySerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct(SNAPSHOT_ENTITY_STRUCT, 3)?;
+        state.serialize_field(SNAPSHOT_ENTITY_FIELD_ENTITY, &self.entity.id())?;
+        state.serialize_field(SNAPSHOT_ENTITY_FIELD_GENERATION, &self.entity.generation())?;
+        state.serialize_field(
+            SNAPSHOT_ENTITY_FIELD_COMPONENTS,
+            &ComponentsSerializer {
+                components: self.components,
+                registry: self.registry,
+            },
+        )?;
+        state.end()
+    }
+}
+
+const SNAPSHOT_ENTITY_STRUCT: &str = "Entity";
+const SNAPSHOT_ENTITY_FIELD_ENTITY: &str = "entity";
+const SNAPSHOT_ENTITY_FIELD_GENERATION: &str = "generation";
+const SNAPSHOT_ENTITY_FIELD_COMPONENTS: &str = "components";
+
+struct SnapshotDeserializer<'a> {
+    type_registry: &'a TypeRegistry,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for SnapshotDeserializer<'a> {
+    type Value = Vec<SnapshotEntity>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'a, 'de> Visitor<'de> for SnapshotDeserializer<'a> {
+    type Value = Vec<SnapshotEntity>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("list of entities")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut entities = Vec::new();
+        while let Some(entity) = seq.next_element_seed(SnapshotEntityDeserializer {
+            type_registry: self.type_registry,
+        })? {
+            entities.push(entity);
+        }
+        Ok(entities)
+    }
+}
+
+struct SnapshotEntityDeserializer<'a> {
+    type_registry: &'a TypeRegistry,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for SnapshotEntityDeserializer<'a> {
+    type Value = SnapshotEntity;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(
+            SNAPSHOT_ENTITY_STRUCT,
+            &[
+                SNAPSHOT_ENTITY_FIELD_ENTITY,
+                SNAPSHOT_ENTITY_FIELD_GENERATION,
+                SNAPSHOT_ENTITY_FIELD_COMPONENTS,
+            ],
+            self,
+        )
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum SnapshotEntityField {
+    Entity,
+    Generation,
+    Components,
+}
+
+impl<'a, 'de> Visitor<'de> for SnapshotEntityDeserializer<'a> {
+    type Value = SnapshotEntity;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("entities")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut id = None;
+        let mut generation = None;
+        let mut components = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                SnapshotEntityField::Entity => {
+                    if id.is_some() {
+                        return Err(Error::duplicate_field(SNAPSHOT_ENTITY_FIELD_ENTITY));
+                    }
+                    id = Some(map.next_value::<u32>()?);
+                }
+                SnapshotEntityField::Generation => {
+                    if generation.is_some() {
+                        return Err(Error::duplicate_field(SNAPSHOT_ENTITY_FIELD_GENERATION));
+                    }
+                    generation = Some(map.next_value::<u32>()?);
+                }
+                SnapshotEntityField::Components => {
+                    if components.is_some() {
+                        return Err(Error::duplicate_field(SNAPSHOT_ENTITY_FIELD_COMPONENTS));
+                    }
+                    components = Some(map.next_value_seed(ComponentVecDeserializer {
+                        registry: self.type_registry,
+                    })?);
+                }
+            }
+        }
+
+        let id = id.ok_or_else(|| Error::missing_field(SNAPSHOT_ENTITY_FIELD_ENTITY))?;
+        let generation =
+            generation.ok_or_else(|| Error::missing_field(SNAPSHOT_ENTITY_FIELD_GENERATION))?;
+        let components =
+            components.ok_or_else(|| Error::missing_field(SNAPSHOT_ENTITY_FIELD_COMPONENTS))?;
+        let entity = Entity::from_bits(u64::from(generation) << 32 | u64::from(id));
+        Ok((entity, components))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_components_ron, serialize_components_ron, ComponentSnapshotError};
+    use crate::SceneSpawnError;
+    use bevy_ecs::{prelude::Component, reflect::ReflectComponent, world::World};
+    use bevy_reflect::{Reflect, TypeRegistryArc};
+    use std::any::type_name;
+
+    #[derive(Component, Reflect, Default, Debug, PartialEq)]
+    #[reflect(Component)]
+    struct Health(u32);
+
+    #[derive(Component, Reflect, Default, Debug, PartialEq)]
+    #[reflect(Component)]
+    struct Position {
+        x: f32,
+        y: f32,
+    }
+
+    #[derive(Component, Reflect, Default, Debug, PartialEq)]
+    #[reflect(Component)]
+    struct Speed(f32);
+
+    #[derive(Reflect, Default)]
+    struct NotComponent;
+
+    #[test]
+    fn components_round_trip() {
+        let registry = TypeRegistryArc::default();
+        registry.write().register::<Health>();
+        registry.write().register::<Position>();
+        registry.write().register::<Speed>();
+        registry.write().register::<NotComponent>();
+        let mut world = World::new();
+        let hero = world
+            .spawn()
+            .insert_bundle((Health(10), Position { x: 1.0, y: 2.0 }, Speed(3.0)))
+            .id();
+        let goblin = world.spawn().insert(Health(4)).id();
+
+        let component_types = [type_name::<Health>(), type_name::<Position>()];
+        let ron =
+            serialize_components_ron(&world, &[hero, goblin], &component_types, &registry).unwrap();
+        assert!(!ron.contains("Speed"));
+
+        world.get_mut::<Health>(hero).unwrap().0 = 0;
+        world.entity_mut(hero).remove::<Position>();
+        world.entity_mut(goblin).remove::<Health>();
+        world.get_mut::<Speed>(hero).unwrap().0 = 5.0;
+        apply_components_ron(&mut world, &ron, &registry).unwrap();
+
+        assert_eq!(world.get::<Health>(hero), Some(&Health(10)));
+        assert_eq!(
+            world.get::<Position>(hero),
+            Some(&Position { x: 1.0, y: 2.0 })
+        );
+        assert_eq!(world.get::<Health>(goblin), Some(&Health(4)));
+        assert_eq!(world.get::<Position>(goblin), None);
+        // components left out of the snapshot are untouched
+        assert_eq!(world.get::<Speed>(hero), Some(&Speed(5.0)));
+
+        let error =
+            serialize_components_ron(&world, &[hero], &[type_name::<NotComponent>()], &registry)
+                .unwrap_err();
+        assert!(matches!(
+            error,
+            ComponentSnapshotError::Unregistered(SceneSpawnError::UnregisteredComponent { .. })
+        ));
+        let error = serialize_components_ron(&world, &[hero], &["Unknown"], &registry).unwrap_err();
+        assert!(matches!(
+            error,
+            ComponentSnapshotError::Unregistered(SceneSpawnError::UnregisteredType { .. })
+        ));
+    }
+
+    #[test]
+    fn reused_entities_are_skipped() {
+        let registry = TypeRegistryArc::default();
+        registry.write().register::<Health>();
+        let mut world = World::new();
+        let hero = world.spawn().insert(Health(10)).id();
+        let ron =
+            serialize_components_ron(&world, &[hero], &[type_name::<Health>()], &registry).unwrap();
+
+        world.despawn(hero);
+        let newcomer = world.spawn().insert(Health(1)).id();
+        assert_eq!(newcomer.id(), hero.id());
+        apply_components_ron(&mut world, &ron, &registry).unwrap();
+        assert_eq!(world.get::<Health>(newcomer), Some(&Health(1)));
+    }
+}
@@ -2,7 +2,7 @@ use crate::{serde::SceneSerializer, Scene, SceneSpawnError};
 use anyhow::Result;
 use bevy_ecs::{
     entity::EntityMap,
-    reflect::{ReflectComponent, ReflectMapEntities},
+    reflect::{ReflectAfterInsert, ReflectComponent, ReflectMapEntities},
     world::World,
 };
 use bevy_reflect::{Reflect, TypeRegistryArc, TypeUuid};
@@ -15,6 +15,23 @@ pub struct DynamicScene {
     pub entities: Vec<DynamicEntity>,
 }
 
+/// The change tick at which the components written by [`DynamicScene::write_to_world_with`] are
+/// marked as added or changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SceneWriteTick {
+    /// The current change tick of the world, as for any component inserted or modified.
+    Current,
+    /// The given change tick, for example the one of the frame a save game was loaded on, for
+    /// change detection to see its components as added on that frame.
+    At(u32),
+}
+
+impl Default for SceneWriteTick {
+    fn default() -> Self {
+        SceneWriteTick::Current
+    }
+}
+
 /// A reflection-powered serializable representation of an entity and its components.
 pub struct DynamicEntity {
     /// The transiently unique identifier of a corresponding `Entity`.
@@ -78,6 +95,18 @@ impl DynamicScene {
         &self,
         world: &mut World,
         entity_map: &mut EntityMap,
+    ) -> Result<(), SceneSpawnError> {
+        self.write_to_world_with(world, entity_map, SceneWriteTick::Current)
+    }
+
+    /// Writes the entities to `world` like [`DynamicScene::write_to_world`], marking the
+    /// components added to the entities as added at `write_tick`, and the components applied to
+    /// existing ones as changed at `write_tick`.
+    pub fn write_to_world_with(
+        &self,
+        world: &mut World,
+        entity_map: &mut EntityMap,
+        write_tick: SceneWriteTick,
     ) -> Result<(), SceneSpawnError> {
         let registry = world.get_resource::<TypeRegistryArc>().unwrap().clone();
         let type_registry = registry.read();
@@ -112,8 +141,17 @@ impl DynamicScene {
                     .contains_type_id(registration.type_id())
                 {
                     reflect_component.apply_component(world, entity, &**component);
+                    if let SceneWriteTick::At(change_tick) = write_tick {
+                        reflect_component.set_changed_tick(world, entity, change_tick);
+                    }
                 } else {
-                    reflect_component.add_component(world, entity, &**component);
+                    reflect_component.add_component(world, entity, &**component, &type_registry)?;
+                    if let SceneWriteTick::At(change_tick) = write_tick {
+                        reflect_component.set_added_tick(world, entity, change_tick);
+                    }
+                }
+                if let Some(reflect_after_insert) = registration.data::<ReflectAfterInsert>() {
+                    reflect_after_insert.after_insert(world, entity);
                 }
             }
         }
@@ -150,3 +188,137 @@ where
     serialize.serialize(&mut ron_serializer)?;
     Ok(String::from_utf8(buf).unwrap())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{DynamicEntity, DynamicScene, SceneWriteTick};
+    use crate::SceneSpawnError;
+    use bevy_ecs::{
+        entity::{Entity, EntityMap},
+        prelude::{Added, Component},
+        reflect::{AfterInsert, ReflectAfterInsert, ReflectComponent, ReflectConstructError},
+        world::World,
+    };
+    use bevy_reflect::{DynamicStruct, Reflect, TypeRegistryArc};
+
+    #[derive(Component, Reflect, Default)]
+    #[reflect(Component, AfterInsert)]
+    struct Volume(f32);
+
+    impl AfterInsert for Volume {
+        fn after_insert(&mut self) {
+            self.0 = self.0.clamp(0.0, 1.0);
+        }
+    }
+
+    #[derive(Reflect, Default)]
+    struct NotComponent;
+
+    #[derive(Reflect, Default)]
+    struct Unregistered;
+
+    fn world_with_registry() -> World {
+        let mut world = World::new();
+        let registry = TypeRegistryArc::default();
+        registry.write().register::<Volume>();
+        registry.write().register::<NotComponent>();
+        world.insert_resource(registry);
+        world
+    }
+
+    fn scene_with(component: Box<dyn Reflect>) -> DynamicScene {
+        DynamicScene {
+            entities: vec![DynamicEntity {
+                entity: 0,
+                components: vec![component],
+            }],
+        }
+    }
+
+    #[test]
+    fn after_insert_runs_on_written_components() {
+        let mut world = world_with_registry();
+        let scene = scene_with(Box::new(Volume(3.0)));
+        let mut entity_map = EntityMap::default();
+        scene.write_to_world(&mut world, &mut entity_map).unwrap();
+
+        let entity = entity_map.values().next().unwrap();
+        assert_eq!(world.get::<Volume>(entity).unwrap().0, 1.0);
+    }
+
+    #[test]
+    fn unregistered_diagnostics() {
+        let mut world = world_with_registry();
+
+        let error = scene_with(Box::new(Unregistered))
+            .write_to_world(&mut world, &mut EntityMap::default())
+            .unwrap_err();
+        assert!(matches!(error, SceneSpawnError::UnregisteredType { .. }));
+        assert!(error.to_string().contains("app.register_type::<T>()"));
+
+        let error = scene_with(Box::new(NotComponent))
+            .write_to_world(&mut world, &mut EntityMap::default())
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            SceneSpawnError::UnregisteredComponent { .. }
+        ));
+        assert!(error.to_string().contains("#[reflect(Component)]"));
+    }
+
+    #[test]
+    fn invalid_component_value() {
+        let mut world = world_with_registry();
+        // a struct value for the tuple struct `Volume`
+        let mut volume = DynamicStruct::default();
+        volume.set_name(std::any::type_name::<Volume>().to_string());
+        volume.insert("level", 0.5f32);
+
+        let error = scene_with(Box::new(volume))
+            .write_to_world(&mut world, &mut EntityMap::default())
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            SceneSpawnError::InvalidComponent(ReflectConstructError::IncompatibleValue { .. })
+        ));
+        assert_eq!(world.query::<&Volume>().iter(&world).count(), 0);
+    }
+
+    #[test]
+    fn write_tick() {
+        let mut world = world_with_registry();
+        let mut added = world.query_filtered::<Entity, Added<Volume>>();
+        let write = |world: &mut World, write_tick: SceneWriteTick| {
+            let mut entity_map = EntityMap::default();
+            scene_with(Box::new(Volume(0.5)))
+                .write_to_world_with(world, &mut entity_map, write_tick)
+                .unwrap();
+            entity_map.values().next().unwrap()
+        };
+        // a previous frame
+        world.clear_trackers();
+        let loaded_tick = world.read_change_tick();
+        world.clear_trackers();
+
+        let current = write(&mut world, SceneWriteTick::Current);
+        let loaded = write(&mut world, SceneWriteTick::At(loaded_tick));
+        assert_eq!(added.iter(&world).collect::<Vec<_>>(), vec![current]);
+
+        // the tick of the loaded component is the one of the frame it was loaded on
+        let reflect_component = world
+            .get_resource::<TypeRegistryArc>()
+            .unwrap()
+            .read()
+            .get(std::any::TypeId::of::<Volume>())
+            .and_then(|registration| registration.data::<ReflectComponent>().cloned())
+            .unwrap();
+        let ticks = reflect_component
+            .get_change_ticks(world.entity(loaded))
+            .unwrap();
+        assert!(ticks.is_added(loaded_tick.wrapping_sub(1), loaded_tick));
+        let ticks = reflect_component
+            .get_change_ticks(world.entity(current))
+            .unwrap();
+        assert!(!ticks.is_added(loaded_tick.wrapping_sub(1), loaded_tick));
+    }
+}
@@ -1,14 +1,18 @@
 mod command;
+mod component_snapshot;
 mod dynamic_scene;
 mod scene;
 mod scene_loader;
+mod scene_patch;
 mod scene_spawner;
 pub mod serde;
 
 pub use command::*;
+pub use component_snapshot::*;
 pub use dynamic_scene::*;
 pub use scene::*;
 pub use scene_loader::*;
+pub use scene_patch::*;
 pub use scene_spawner::*;
 
 pub mod prelude {
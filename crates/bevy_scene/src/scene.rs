@@ -1,5 +1,14 @@
-use bevy_ecs::world::World;
-use bevy_reflect::TypeUuid;
+use bevy_asset::{Asset, Handle, HandleId};
+use bevy_ecs::{
+    archetype::{ArchetypeGeneration, ArchetypeId},
+    entity::{Entity, EntityMap},
+    reflect::{ReflectAfterInsert, ReflectComponent, ReflectMapEntities},
+    world::{World, WorldId},
+};
+use bevy_reflect::{Reflect, ReflectMut, TypeRegistration, TypeRegistry, TypeUuid};
+use bevy_utils::HashMap;
+
+use crate::SceneSpawnError;
 
 #[derive(Debug, TypeUuid)]
 #[uuid = "c156503c-edd9-4ec7-8d33-dab392df03cd"]
@@ -7,8 +16,750 @@ pub struct Scene {
     pub world: World,
 }
 
+/// The reflection data needed to copy the entities of a [`Scene`] into another world.
+///
+/// Preparing it once with [`Scene::prepare_copy_plan`] avoids looking every component up in the
+/// [`TypeRegistry`] each time the same scene is written with [`Scene::write_prepared`]. The plan
+/// keeps the reflection data registered at the time it was prepared, see
+/// [`SceneCopyPlan::is_outdated`].
+pub struct SceneCopyPlan {
+    world_id: WorldId,
+    archetype_generation: ArchetypeGeneration,
+    registry_generation: u64,
+    archetypes: Vec<ArchetypeCopyPlan>,
+}
+
+/// The entities of an archetype of the scene when the plan was prepared, and the reflection data
+/// of its components.
+struct ArchetypeCopyPlan {
+    id: ArchetypeId,
+    entities: Vec<Entity>,
+    components: Vec<(ReflectComponent, Option<ReflectAfterInsert>)>,
+}
+
+impl SceneCopyPlan {
+    /// Returns `true` if the world of `scene` was replaced, for example when the scene was
+    /// reloaded, or if entities were spawned, despawned or changed archetype in it, or if
+    /// `type_registry` changed since the plan was prepared, in which case it must be prepared
+    /// again.
+    pub fn is_outdated(&self, scene: &Scene, type_registry: &TypeRegistry) -> bool {
+        self.is_outdated_for(scene) || self.registry_generation != type_registry.generation()
+    }
+
+    /// Returns `true` if the world of `scene` was replaced or if its entities aren't the ones of
+    /// the archetypes the plan knows about, which makes writing the scene with the plan
+    /// impossible.
+    ///
+    /// This compares the entities of every archetype of the scene with the ones the plan was
+    /// prepared with.
+    fn is_outdated_for(&self, scene: &Scene) -> bool {
+        self.world_id != scene.world.id()
+            || self.archetype_generation != scene.world.archetypes().generation()
+            || self.archetypes.iter().any(|archetype| {
+                scene.world.archetypes()[archetype.id].entities() != archetype.entities.as_slice()
+            })
+    }
+}
+
+/// The contents of a [`Scene`], as returned by [`Scene::stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SceneStats {
+    pub entity_count: usize,
+    /// The number of components of all the entities together.
+    pub component_count: usize,
+    pub resource_count: usize,
+    /// The number of distinct combinations of components of the entities.
+    pub archetype_count: usize,
+}
+
+/// Substitutes the asset handles of a scene by other ones when it is written to a world, for
+/// example to swap a texture of a prefab.
+///
+/// The handles are looked up in the reflected fields of the components, including nested structs,
+/// tuples and lists, but not in maps. Handles missing from the table are kept.
+#[derive(Debug, Clone, Default)]
+pub struct HandleRemap {
+    ids: HashMap<HandleId, HandleId>,
+}
+
+impl HandleRemap {
+    /// Makes the handles to the `from` asset point to the `to` asset instead.
+    pub fn insert<T: Asset>(&mut self, from: &Handle<T>, to: &Handle<T>) {
+        self.ids.insert(from.id, to.id);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Substitutes the handles found in `value`.
+    pub fn remap(&self, value: &mut dyn Reflect) {
+        if let Some(id) = value.downcast_mut::<HandleId>() {
+            if let Some(new_id) = self.ids.get(id) {
+                *id = *new_id;
+            }
+            return;
+        }
+        match value.reflect_mut() {
+            ReflectMut::Struct(value) => {
+                for i in 0..value.field_len() {
+                    self.remap(value.field_at_mut(i).unwrap());
+                }
+            }
+            ReflectMut::TupleStruct(value) => {
+                for i in 0..value.field_len() {
+                    self.remap(value.field_mut(i).unwrap());
+                }
+            }
+            ReflectMut::Tuple(value) => {
+                for i in 0..value.field_len() {
+                    self.remap(value.field_mut(i).unwrap());
+                }
+            }
+            ReflectMut::List(value) => {
+                for i in 0..value.len() {
+                    self.remap(value.get_mut(i).unwrap());
+                }
+            }
+            ReflectMut::Map(_) | ReflectMut::Value(_) => {}
+        }
+    }
+}
+
 impl Scene {
     pub fn new(world: World) -> Self {
         Self { world }
     }
+
+    /// Counts what writing the scene to a world would spawn, without writing it.
+    pub fn stats(&self) -> SceneStats {
+        let mut stats = SceneStats::default();
+        for archetype in self.world.archetypes().iter() {
+            if archetype.is_empty() {
+                continue;
+            }
+            stats.entity_count += archetype.len();
+            stats.component_count += archetype.len() * archetype.components().count();
+            stats.archetype_count += 1;
+        }
+        stats.resource_count = self
+            .world
+            .archetypes()
+            .resource()
+            .unique_components()
+            .values()
+            .filter(|column| !column.is_empty())
+            .count();
+        stats
+    }
+
+    /// Creates a scene from a snapshot of the entities of `world`, keeping the components whose
+    /// registration in `type_registry` passes `filter`.
+    ///
+    /// Components without [`ReflectComponent`] data can't be copied and are always skipped. If
+    /// `keep_empty_entities` is `false`, the entities without any kept component are left out of
+    /// the scene.
+    pub fn from_world_filtered(
+        world: &World,
+        type_registry: &TypeRegistry,
+        filter: impl Fn(&TypeRegistration) -> bool,
+        keep_empty_entities: bool,
+    ) -> Self {
+        let mut scene_world = World::new();
+        let mut entity_map = EntityMap::default();
+        for archetype in world.archetypes().iter() {
+            let reflect_components = archetype
+                .components()
+                .filter_map(|component_id| {
+                    let type_id = world.components().get_info(component_id)?.type_id()?;
+                    let registration = type_registry.get(type_id)?;
+                    if !filter(registration) {
+                        return None;
+                    }
+                    registration.data::<ReflectComponent>()
+                })
+                .collect::<Vec<_>>();
+            if reflect_components.is_empty() && !keep_empty_entities {
+                continue;
+            }
+            for entity in archetype.entities() {
+                let scene_entity = *entity_map
+                    .entry(*entity)
+                    .or_insert_with(|| scene_world.spawn().id());
+                for reflect_component in reflect_components.iter() {
+                    reflect_component.copy_component(
+                        world,
+                        &mut scene_world,
+                        *entity,
+                        scene_entity,
+                    );
+                }
+            }
+        }
+        Scene::new(scene_world)
+    }
+
+    /// Creates a scene from a snapshot of the `entities` of `world`, with their components which
+    /// registered [`ReflectComponent`] in `type_registry`.
+    ///
+    /// The references between the selected entities are mapped to their copies in the scene, for
+    /// the components registering [`ReflectMapEntities`]. A component referring to an entity
+    /// which is not selected is left out of the scene, as its reference would dangle. Entities
+    /// which don't exist are skipped.
+    pub fn from_world_entities(
+        world: &World,
+        entities: &[Entity],
+        type_registry: &TypeRegistry,
+    ) -> Self {
+        let mut scene_world = World::new();
+        let mut entity_map = EntityMap::default();
+        for &entity in entities {
+            let location = match world.entities().get(entity) {
+                Some(location) if entity_map.get(entity).is_err() => location,
+                _ => continue,
+            };
+            let scene_entity = scene_world.spawn().id();
+            entity_map.insert(entity, scene_entity);
+            for component_id in world.archetypes()[location.archetype_id].components() {
+                let reflect_component = world
+                    .components()
+                    .get_info(component_id)
+                    .and_then(|component_info| component_info.type_id())
+                    .and_then(|type_id| type_registry.get(type_id))
+                    .and_then(|registration| registration.data::<ReflectComponent>());
+                if let Some(reflect_component) = reflect_component {
+                    reflect_component.copy_component(world, &mut scene_world, entity, scene_entity);
+                }
+            }
+        }
+
+        // Collected first, as removing components moves entities to other archetypes
+        let mut entity_references = Vec::new();
+        for archetype in scene_world.archetypes().iter() {
+            for component_id in archetype.components() {
+                let registration = scene_world
+                    .components()
+                    .get_info(component_id)
+                    .and_then(|component_info| component_info.type_id())
+                    .and_then(|type_id| type_registry.get(type_id));
+                if let Some((reflect_map_entities, reflect_component)) =
+                    registration.and_then(|registration| {
+                        Some((
+                            registration.data::<ReflectMapEntities>()?,
+                            registration.data::<ReflectComponent>()?,
+                        ))
+                    })
+                {
+                    entity_references.extend(archetype.entities().iter().map(|scene_entity| {
+                        (*scene_entity, reflect_map_entities, reflect_component)
+                    }));
+                }
+            }
+        }
+        for (scene_entity, reflect_map_entities, reflect_component) in entity_references {
+            if reflect_map_entities
+                .map_entity(&mut scene_world, scene_entity, &entity_map)
+                .is_err()
+            {
+                reflect_component.remove_component(&mut scene_world, scene_entity);
+            }
+        }
+        Scene::new(scene_world)
+    }
+
+    /// Resolves the [`ReflectComponent`] of every component of the scene in `type_registry`, and
+    /// their [`ReflectAfterInsert`] if they have one.
+    pub fn prepare_copy_plan(
+        &self,
+        type_registry: &TypeRegistry,
+    ) -> Result<SceneCopyPlan, SceneSpawnError> {
+        let mut archetypes = Vec::new();
+        for archetype in self.world.archetypes().iter() {
+            let reflect_components = archetype
+                .components()
+                .map(|component_id| {
+                    let component_info = self
+                        .world
+                        .components()
+                        .get_info(component_id)
+                        .expect("component_ids in archetypes should have ComponentInfo");
+
+                    type_registry
+                        .get(component_info.type_id().unwrap())
+                        .ok_or_else(|| SceneSpawnError::UnregisteredType {
+                            type_name: component_info.name().to_string(),
+                        })
+                        .and_then(|registration| {
+                            let reflect_component = registration
+                                .data::<ReflectComponent>()
+                                .cloned()
+                                .ok_or_else(|| SceneSpawnError::UnregisteredComponent {
+                                    type_name: component_info.name().to_string(),
+                                })?;
+                            let reflect_after_insert =
+                                registration.data::<ReflectAfterInsert>().cloned();
+                            Ok((reflect_component, reflect_after_insert))
+                        })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            archetypes.push(ArchetypeCopyPlan {
+                id: archetype.id(),
+                entities: archetype.entities().to_vec(),
+                components: reflect_components,
+            });
+        }
+        Ok(SceneCopyPlan {
+            world_id: self.world.id(),
+            archetype_generation: self.world.archetypes().generation(),
+            registry_generation: type_registry.generation(),
+            archetypes,
+        })
+    }
+
+    /// Copies the entities of the scene into `world` using a `plan` prepared for this scene,
+    /// spawning the entities missing from `entity_map`.
+    ///
+    /// The plan is used even if the type registry changed since it was prepared, see
+    /// [`SceneCopyPlan::is_outdated`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the scene was replaced or its entities changed since `plan` was prepared.
+    pub fn write_prepared(
+        &self,
+        plan: &SceneCopyPlan,
+        world: &mut World,
+        entity_map: &mut EntityMap,
+    ) {
+        self.write_prepared_remapped(plan, world, entity_map, &HandleRemap::default());
+    }
+
+    /// Copies the entities of the scene into `world` like [`Scene::write_prepared`], substituting
+    /// their asset handles found in `handle_remap`.
+    pub fn write_prepared_remapped(
+        &self,
+        plan: &SceneCopyPlan,
+        world: &mut World,
+        entity_map: &mut EntityMap,
+        handle_remap: &HandleRemap,
+    ) {
+        assert!(
+            !plan.is_outdated_for(self),
+            "the copy plan was prepared for another world or before the entities of the scene changed"
+        );
+        for archetype in plan.archetypes.iter() {
+            for scene_entity in archetype.entities.iter() {
+                let entity = *entity_map
+                    .entry(*scene_entity)
+                    .or_insert_with(|| world.spawn().id());
+                for (reflect_component, reflect_after_insert) in archetype.components.iter() {
+                    reflect_component.copy_component(&self.world, world, *scene_entity, entity);
+                    if !handle_remap.is_empty() {
+                        if let Some(mut component) =
+                            reflect_component.reflect_component_mut(world, entity)
+                        {
+                            handle_remap.remap(&mut *component);
+                        }
+                    }
+                    if let Some(reflect_after_insert) = reflect_after_insert {
+                        reflect_after_insert.after_insert(world, entity);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The progress of a [`SceneStream`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SceneStreamProgress {
+    /// The number of entities of the scene written so far.
+    pub spawned: usize,
+    pub total: usize,
+}
+
+impl SceneStreamProgress {
+    pub fn is_complete(&self) -> bool {
+        self.spawned == self.total
+    }
+}
+
+/// Writes the entities of a [`Scene`] into a world a bounded number at a time, for example over
+/// several frames to avoid blocking a frame on a huge scene.
+///
+/// The references between entities are only mapped with [`ReflectMapEntities`] once all the
+/// entities were written, as they may refer to entities written later. Until then, they refer to
+/// the entities of the scene.
+pub struct SceneStream {
+    plan: SceneCopyPlan,
+    map_entities: Vec<ReflectMapEntities>,
+    entity_map: EntityMap,
+    /// The index in the plan of the archetype being written, and of its next entity to write.
+    next: (usize, usize),
+    progress: SceneStreamProgress,
+}
+
+impl SceneStream {
+    /// Prepares the streaming of `scene`, resolving its reflection data in `type_registry` like
+    /// [`Scene::prepare_copy_plan`].
+    pub fn new(scene: &Scene, type_registry: &TypeRegistry) -> Result<Self, SceneSpawnError> {
+        Ok(SceneStream {
+            plan: scene.prepare_copy_plan(type_registry)?,
+            map_entities: type_registry
+                .iter()
+                .filter_map(|registration| registration.data::<ReflectMapEntities>().cloned())
+                .collect(),
+            entity_map: EntityMap::default(),
+            next: (0, 0),
+            progress: SceneStreamProgress {
+                spawned: 0,
+                total: scene.stats().entity_count,
+            },
+        })
+    }
+
+    /// Writes up to `max_entities` more entities of `scene` into `world`, mapping the references
+    /// between the entities once the last one is written, and returns the progress.
+    ///
+    /// Returns [`SceneSpawnError::SceneChangedWhileStreamed`] without writing anything if the
+    /// scene was replaced or its entities changed since the stream was created. The stream can't
+    /// go on, and the scene must be streamed again from the start.
+    pub fn write_next(
+        &mut self,
+        scene: &Scene,
+        world: &mut World,
+        max_entities: usize,
+    ) -> Result<SceneStreamProgress, SceneSpawnError> {
+        if self.plan.is_outdated_for(scene) {
+            return Err(SceneSpawnError::SceneChangedWhileStreamed);
+        }
+        if self.progress.is_complete() {
+            return Ok(self.progress);
+        }
+        let mut remaining = max_entities;
+        while remaining > 0 {
+            let (archetype_index, entity_index) = self.next;
+            let archetype = match self.plan.archetypes.get(archetype_index) {
+                Some(archetype) => archetype,
+                None => break,
+            };
+            let scene_entities = &archetype.entities;
+            let end = scene_entities.len().min(entity_index + remaining);
+            for scene_entity in &scene_entities[entity_index.min(end)..end] {
+                let entity = *self
+                    .entity_map
+                    .entry(*scene_entity)
+                    .or_insert_with(|| world.spawn().id());
+                for (reflect_component, reflect_after_insert) in archetype.components.iter() {
+                    reflect_component.copy_component(&scene.world, world, *scene_entity, entity);
+                    if let Some(reflect_after_insert) = reflect_after_insert {
+                        reflect_after_insert.after_insert(world, entity);
+                    }
+                }
+            }
+            let written = end.saturating_sub(entity_index);
+            remaining -= written;
+            self.progress.spawned += written;
+            self.next = if end == scene_entities.len() {
+                (archetype_index + 1, 0)
+            } else {
+                (archetype_index, end)
+            };
+        }
+        if self.progress.is_complete() {
+            for map_entities in self.map_entities.iter() {
+                map_entities.map_entities(world, &self.entity_map).unwrap();
+            }
+        }
+        Ok(self.progress)
+    }
+
+    pub fn progress(&self) -> SceneStreamProgress {
+        self.progress
+    }
+
+    /// The entities written so far, by entity of the scene.
+    pub fn entity_map(&self) -> &EntityMap {
+        &self.entity_map
+    }
+
+    /// Returns the entities written, by entity of the scene.
+    pub fn into_entity_map(self) -> EntityMap {
+        self.entity_map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HandleRemap, Scene, SceneStats, SceneStream, SceneStreamProgress};
+    use crate::SceneSpawnError;
+    use bevy_asset::{Handle, HandleId};
+    use bevy_ecs::{
+        entity::{Entity, EntityMap, MapEntities, MapEntitiesError},
+        prelude::Component,
+        reflect::{ReflectComponent, ReflectMapEntities},
+        world::{FromWorld, World},
+    };
+    use bevy_reflect::{Reflect, TypeRegistration, TypeRegistry};
+    use std::any::TypeId;
+
+    #[derive(Component, Reflect, Default, Debug, PartialEq)]
+    #[reflect(Component)]
+    struct Name(String);
+
+    #[derive(Component, Reflect, Default)]
+    #[reflect(Component)]
+    struct Transient;
+
+    #[test]
+    fn from_world_filtered_skips_denied_components() {
+        let mut type_registry = TypeRegistry::default();
+        type_registry.register::<Name>();
+        type_registry.register::<Transient>();
+        let mut world = World::new();
+        world
+            .spawn()
+            .insert_bundle((Name("kept".to_string()), Transient));
+        world.spawn().insert(Transient);
+
+        let not_transient =
+            |registration: &TypeRegistration| registration.type_id() != TypeId::of::<Transient>();
+
+        let mut scene = Scene::from_world_filtered(&world, &type_registry, not_transient, false);
+        let names: Vec<_> = scene.world.query::<&Name>().iter(&scene.world).collect();
+        assert_eq!(names, vec![&Name("kept".to_string())]);
+        assert_eq!(
+            scene.world.query::<&Transient>().iter(&scene.world).count(),
+            0
+        );
+        assert_eq!(scene.world.entities().len(), 1);
+
+        let scene = Scene::from_world_filtered(&world, &type_registry, not_transient, true);
+        assert_eq!(scene.world.entities().len(), 2);
+    }
+
+    #[derive(Component, Reflect, Default)]
+    #[reflect(Component)]
+    struct Skin {
+        textures: Vec<Handle<Scene>>,
+    }
+
+    #[test]
+    fn write_with_handle_remap() {
+        let mut type_registry = TypeRegistry::default();
+        type_registry.register::<Skin>();
+        let default_skin = Handle::<Scene>::weak(HandleId::random::<Scene>());
+        let eyes = Handle::<Scene>::weak(HandleId::random::<Scene>());
+        let new_skin = Handle::<Scene>::weak(HandleId::random::<Scene>());
+        let mut scene_world = World::new();
+        scene_world.spawn().insert(Skin {
+            textures: vec![default_skin.clone(), eyes.clone()],
+        });
+        let scene = Scene::new(scene_world);
+
+        let mut handle_remap = HandleRemap::default();
+        handle_remap.insert(&default_skin, &new_skin);
+        let mut world = World::new();
+        let mut entity_map = EntityMap::default();
+        let plan = scene.prepare_copy_plan(&type_registry).unwrap();
+        scene.write_prepared_remapped(&plan, &mut world, &mut entity_map, &handle_remap);
+
+        let entity = entity_map.values().next().unwrap();
+        let textures = &world.get::<Skin>(entity).unwrap().textures;
+        assert_eq!(textures, &vec![new_skin, eyes]);
+    }
+
+    #[test]
+    fn stats() {
+        let mut world = World::new();
+        world
+            .spawn()
+            .insert_bundle((Name("a".to_string()), Transient));
+        world
+            .spawn()
+            .insert_bundle((Name("b".to_string()), Transient));
+        world.spawn().insert(Name("c".to_string()));
+        world.spawn();
+        world.insert_resource(0u32);
+        world.insert_resource(Transient);
+        world.insert_resource(0u64);
+        world.remove_resource::<u64>();
+
+        assert_eq!(
+            Scene::new(world).stats(),
+            SceneStats {
+                entity_count: 4,
+                component_count: 5,
+                resource_count: 2,
+                archetype_count: 3,
+            }
+        );
+        assert_eq!(Scene::new(World::new()).stats(), SceneStats::default());
+    }
+
+    #[derive(Component, Reflect, Debug, PartialEq)]
+    #[reflect(Component, MapEntities)]
+    struct Target(Entity);
+
+    impl FromWorld for Target {
+        fn from_world(_world: &mut World) -> Self {
+            Target(Entity::from_raw(u32::MAX))
+        }
+    }
+
+    impl MapEntities for Target {
+        fn map_entities(&mut self, entity_map: &EntityMap) -> Result<(), MapEntitiesError> {
+            self.0 = entity_map.get(self.0)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn from_world_entities_drops_dangling_references() {
+        let mut type_registry = TypeRegistry::default();
+        type_registry.register::<Name>();
+        type_registry.register::<Target>();
+        let mut world = World::new();
+        let name = |name: &str| Name(name.to_string());
+        world.spawn().insert(name("a"));
+        let d = world.spawn().insert(name("d")).id();
+        let c = world
+            .spawn()
+            .insert_bundle((name("c"), Target(d), Transient))
+            .id();
+        let b = world.spawn().insert_bundle((name("b"), Target(c))).id();
+
+        let mut scene = Scene::from_world_entities(&world, &[b, c], &type_registry);
+        assert_eq!(scene.stats().entity_count, 2);
+        let scene_entity = |scene: &mut Scene, name: &str| {
+            scene
+                .world
+                .query::<(Entity, &Name)>()
+                .iter(&scene.world)
+                .find(|(_, entity_name)| entity_name.0 == name)
+                .map(|(entity, _)| entity)
+                .unwrap()
+        };
+        let scene_b = scene_entity(&mut scene, "b");
+        let scene_c = scene_entity(&mut scene, "c");
+        // b refers to c, which is in the scene
+        assert_eq!(scene.world.get::<Target>(scene_b), Some(&Target(scene_c)));
+        // c refers to d, which isn't: the reference would dangle
+        assert!(scene.world.get::<Target>(scene_c).is_none());
+        // Transient isn't registered
+        assert!(scene.world.get::<Transient>(scene_c).is_none());
+    }
+
+    #[test]
+    fn stream_maps_references_once_complete() {
+        let mut type_registry = TypeRegistry::default();
+        type_registry.register::<Target>();
+        let mut scene_world = World::new();
+        let first = scene_world.spawn().id();
+        let mut previous = first;
+        for _ in 1..5000 {
+            previous = scene_world.spawn().insert(Target(previous)).id();
+        }
+        scene_world.entity_mut(first).insert(Target(previous));
+        let scene = Scene::new(scene_world);
+
+        let mut world = World::new();
+        // entities already in the world don't collide with the scene ones
+        world.spawn_batch((0..100).map(|_| (Transient,)));
+        let mut stream = SceneStream::new(&scene, &type_registry).unwrap();
+        let mut calls = 0;
+        loop {
+            let progress = stream.write_next(&scene, &mut world, 1024).unwrap();
+            calls += 1;
+            if progress.is_complete() {
+                break;
+            }
+            assert_eq!(progress.spawned, calls * 1024);
+            // the references still refer to the entities of the scene
+            for scene_entity in stream.entity_map().keys() {
+                let entity = stream.entity_map().get(scene_entity).unwrap();
+                assert_eq!(
+                    world.get::<Target>(entity),
+                    scene.world.get::<Target>(scene_entity)
+                );
+            }
+        }
+        assert_eq!(calls, 5);
+        assert_eq!(
+            stream.progress(),
+            SceneStreamProgress {
+                spawned: 5000,
+                total: 5000
+            }
+        );
+        // further calls do nothing
+        assert_eq!(
+            stream.write_next(&scene, &mut world, 1024).unwrap().spawned,
+            5000
+        );
+
+        let entity_map = stream.into_entity_map();
+        for scene_entity in entity_map.keys() {
+            let entity = entity_map.get(scene_entity).unwrap();
+            let scene_target = scene.world.get::<Target>(scene_entity).unwrap().0;
+            let target = world.get::<Target>(entity).unwrap().0;
+            assert_eq!(target, entity_map.get(scene_target).unwrap());
+        }
+    }
+
+    #[test]
+    fn stream_fails_once_scene_changed() {
+        let mut type_registry = TypeRegistry::default();
+        type_registry.register::<Name>();
+        type_registry.register::<Transient>();
+        let mut scene_world = World::new();
+        for name in ["a", "b"] {
+            scene_world.spawn().insert(Name(name.to_string()));
+        }
+        let mut scene = Scene::new(scene_world);
+
+        let mut world = World::new();
+        let mut stream = SceneStream::new(&scene, &type_registry).unwrap();
+        stream.write_next(&scene, &mut world, 1).unwrap();
+        scene.world.spawn().insert(Transient);
+        assert!(matches!(
+            stream.write_next(&scene, &mut world, 1),
+            Err(SceneSpawnError::SceneChangedWhileStreamed)
+        ));
+
+        // spawning or despawning entities in the existing archetypes changes the scene too
+        let mut stream = SceneStream::new(&scene, &type_registry).unwrap();
+        stream.write_next(&scene, &mut world, 1).unwrap();
+        scene.world.spawn().insert(Name("c".to_string()));
+        assert!(matches!(
+            stream.write_next(&scene, &mut world, 1),
+            Err(SceneSpawnError::SceneChangedWhileStreamed)
+        ));
+        let mut stream = SceneStream::new(&scene, &type_registry).unwrap();
+        let c = scene
+            .world
+            .query::<(Entity, &Name)>()
+            .iter(&scene.world)
+            .find(|(_, name)| name.0 == "c")
+            .map(|(entity, _)| entity)
+            .unwrap();
+        scene.world.despawn(c);
+        assert!(matches!(
+            stream.write_next(&scene, &mut world, 1),
+            Err(SceneSpawnError::SceneChangedWhileStreamed)
+        ));
+        assert_eq!(world.entities().len(), 2);
+
+        // a reloaded scene has another world, even with the same archetypes
+        let mut stream = SceneStream::new(&scene, &type_registry).unwrap();
+        let mut reloaded_world = World::new();
+        reloaded_world.spawn().insert(Name("a".to_string()));
+        reloaded_world.spawn().insert(Transient);
+        let reloaded = Scene::new(reloaded_world);
+        assert!(matches!(
+            stream.write_next(&reloaded, &mut world, 1),
+            Err(SceneSpawnError::SceneChangedWhileStreamed)
+        ));
+        assert_eq!(world.entities().len(), 2);
+    }
 }
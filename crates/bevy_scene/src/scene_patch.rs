@@ -0,0 +1,370 @@
+use crate::{
+    dynamic_scene::serialize_ron, serde::ScenePatchSerializer, DynamicEntity, Scene,
+    SceneSpawnError,
+};
+use bevy_ecs::{
+    entity::Entity,
+    reflect::{
+        reflect_entity_components, reflect_world_entities_diff, ComponentDifference,
+        ReflectComponent,
+    },
+    world::World,
+};
+use bevy_reflect::{Reflect, TypeRegistry, TypeRegistryArc};
+use bevy_utils::HashMap;
+
+/// The changes turning a [`Scene`] into another one, computed by [`Scene::diff`] and applied with
+/// [`Scene::apply_patch`].
+///
+/// Entities are matched by their id in the world of each scene. Only the components which
+/// registered [`ReflectComponent`] are compared.
+#[derive(Default)]
+pub struct ScenePatch {
+    /// The entities missing from the first scene, with their components.
+    pub added_entities: Vec<DynamicEntity>,
+    /// The ids of the entities missing from the second scene.
+    pub removed_entities: Vec<u32>,
+    /// The components added to the entities of both scenes, or whose value changed, with their
+    /// value in the second scene.
+    pub changed_components: Vec<DynamicEntity>,
+    /// The ids of the entities of both scenes and the type names of the components they lost.
+    pub removed_components: Vec<(u32, String)>,
+}
+
+impl ScenePatch {
+    /// Returns `true` if the patch changes nothing, meaning both scenes were equal.
+    pub fn is_empty(&self) -> bool {
+        self.added_entities.is_empty()
+            && self.removed_entities.is_empty()
+            && self.changed_components.is_empty()
+            && self.removed_components.is_empty()
+    }
+
+    /// Serialize this patch into rust object notation (ron).
+    pub fn serialize_ron(&self, registry: &TypeRegistryArc) -> Result<String, ron::Error> {
+        serialize_ron(ScenePatchSerializer::new(self, registry))
+    }
+}
+
+/// The entities of `world` by id, ordered by id.
+fn entities_by_id(world: &World) -> Vec<(u32, Entity)> {
+    let mut entities = world
+        .archetypes()
+        .iter()
+        .flat_map(|archetype| archetype.entities())
+        .map(|entity| (entity.id(), *entity))
+        .collect::<Vec<_>>();
+    entities.sort_unstable_by_key(|(id, _)| *id);
+    entities
+}
+
+/// Looks the [`ReflectComponent`] of the component with the given type name up.
+pub(crate) fn reflect_component_named<'a>(
+    type_registry: &'a TypeRegistry,
+    type_name: &str,
+) -> Result<&'a ReflectComponent, SceneSpawnError> {
+    let registration = type_registry.get_with_name(type_name).ok_or_else(|| {
+        SceneSpawnError::UnregisteredType {
+            type_name: type_name.to_string(),
+        }
+    })?;
+    registration
+        .data::<ReflectComponent>()
+        .ok_or_else(|| SceneSpawnError::UnregisteredComponent {
+            type_name: type_name.to_string(),
+        })
+}
+
+/// Clones the reflected value of the component with the given type name.
+fn clone_component(
+    world: &World,
+    entity: Entity,
+    type_registry: &TypeRegistry,
+    type_name: &str,
+) -> Option<Box<dyn Reflect>> {
+    let reflect_component = reflect_component_named(type_registry, type_name).ok()?;
+    Some(
+        reflect_component
+            .reflect_component(world, entity)?
+            .clone_value(),
+    )
+}
+
+impl Scene {
+    /// Computes the changes turning this scene into `other`, comparing the components with
+    /// [`Reflect::reflect_partial_eq`].
+    ///
+    /// Entities are matched by id. As with [`reflect_entities_diff`](bevy_ecs::reflect::reflect_entities_diff),
+    /// components whose values can't be compared are considered changed.
+    pub fn diff(&self, other: &Scene, type_registry: &TypeRegistry) -> ScenePatch {
+        let mut patch = ScenePatch::default();
+        let entities = entities_by_id(&self.world)
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        let other_entities = entities_by_id(&other.world);
+        for (id, other_entity) in other_entities.iter() {
+            let entity = match entities.get(id) {
+                Some(entity) => *entity,
+                None => {
+                    let mut components =
+                        reflect_entity_components(&other.world, *other_entity, type_registry)
+                            .map(|(_, component)| component.clone_value())
+                            .collect::<Vec<_>>();
+                    components.sort_by_key(|component| component.type_name().to_string());
+                    patch.added_entities.push(DynamicEntity {
+                        entity: *id,
+                        components,
+                    });
+                    continue;
+                }
+            };
+            let mut changed = Vec::new();
+            for difference in reflect_world_entities_diff(
+                &self.world,
+                entity,
+                &other.world,
+                *other_entity,
+                type_registry,
+            ) {
+                match difference {
+                    ComponentDifference::Mismatch(name)
+                    | ComponentDifference::MissingOnFirst(name) => {
+                        changed.extend(clone_component(
+                            &other.world,
+                            *other_entity,
+                            type_registry,
+                            name,
+                        ));
+                    }
+                    ComponentDifference::MissingOnSecond(name) => {
+                        patch.removed_components.push((*id, name.to_string()));
+                    }
+                }
+            }
+            if !changed.is_empty() {
+                patch.changed_components.push(DynamicEntity {
+                    entity: *id,
+                    components: changed,
+                });
+            }
+        }
+        let other_ids = other_entities.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+        patch.removed_entities = entities_by_id(&self.world)
+            .into_iter()
+            .map(|(id, _)| id)
+            .filter(|id| other_ids.binary_search(id).is_err())
+            .collect();
+        patch
+    }
+
+    /// Applies the changes of `patch`, usually computed by [`Scene::diff`] from this scene.
+    ///
+    /// The added entities keep their id if it is free in the scene. Changes to entities which
+    /// are not in the scene are ignored. Nothing is applied if a component type of the patch
+    /// can't be reflected.
+    pub fn apply_patch(
+        &mut self,
+        patch: &ScenePatch,
+        type_registry: &TypeRegistry,
+    ) -> Result<(), SceneSpawnError> {
+        let removed_components = patch
+            .removed_components
+            .iter()
+            .map(|(id, type_name)| {
+                reflect_component_named(type_registry, type_name)
+                    .map(|reflect_component| (*id, reflect_component))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut changed_components = Vec::new();
+        for changed in patch
+            .added_entities
+            .iter()
+            .chain(patch.changed_components.iter())
+        {
+            let components = changed
+                .components
+                .iter()
+                .map(|component| {
+                    reflect_component_named(type_registry, component.type_name())
+                        .map(|reflect_component| (reflect_component, component))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            changed_components.push((changed.entity, components));
+        }
+
+        let mut entities = entities_by_id(&self.world)
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        for id in patch.removed_entities.iter() {
+            if let Some(entity) = entities.remove(id) {
+                self.world.despawn(entity);
+            }
+        }
+        for (id, reflect_component) in removed_components {
+            if let Some(entity) = entities.get(&id) {
+                reflect_component.remove_component(&mut self.world, *entity);
+            }
+        }
+        for added in patch.added_entities.iter() {
+            let entity = match entities.get(&added.entity) {
+                Some(entity) => *entity,
+                None => match self.world.get_or_spawn(Entity::from_raw(added.entity)) {
+                    Some(entity) => entity.id(),
+                    None => self.world.spawn().id(),
+                },
+            };
+            entities.insert(added.entity, entity);
+        }
+        for (id, components) in changed_components {
+            let entity = match entities.get(&id) {
+                Some(entity) => *entity,
+                None => continue,
+            };
+            for (reflect_component, component) in components {
+                reflect_component.apply_or_insert(
+                    &mut self.world,
+                    entity,
+                    &**component,
+                    type_registry,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{serde::ScenePatchDeserializer, Scene};
+    use bevy_ecs::{entity::Entity, prelude::Component, reflect::ReflectComponent, world::World};
+    use bevy_reflect::{Reflect, TypeRegistry, TypeRegistryArc};
+    use serde::de::DeserializeSeed;
+
+    #[derive(Component, Reflect, Default, Debug, PartialEq)]
+    #[reflect(Component)]
+    struct Name(String);
+
+    #[derive(Component, Reflect, Default, Debug, PartialEq)]
+    #[reflect(Component)]
+    struct Health(u32);
+
+    fn prefab() -> World {
+        let mut world = World::new();
+        world
+            .spawn()
+            .insert_bundle((Name("hero".to_string()), Health(10)));
+        world.spawn().insert(Name("sword".to_string()));
+        world
+    }
+
+    fn named(world: &mut World, name: &str) -> Entity {
+        world
+            .query::<(Entity, &Name)>()
+            .iter(world)
+            .find(|(_, entity_name)| entity_name.0 == name)
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn diff_and_apply_patch() {
+        let mut type_registry = TypeRegistry::default();
+        type_registry.register::<Name>();
+        type_registry.register::<Health>();
+        let mut scene = Scene::new(prefab());
+        let mut modified_world = prefab();
+        let hero = named(&mut modified_world, "hero");
+        modified_world.get_mut::<Health>(hero).unwrap().0 = 5;
+        let shield = modified_world
+            .spawn()
+            .insert(Name("shield".to_string()))
+            .id();
+        let modified = Scene::new(modified_world);
+
+        assert!(scene.diff(&scene, &type_registry).is_empty());
+        let patch = scene.diff(&modified, &type_registry);
+        assert!(patch.removed_entities.is_empty());
+        assert!(patch.removed_components.is_empty());
+        assert_eq!(patch.changed_components.len(), 1);
+        let changed = &patch.changed_components[0];
+        assert_eq!(changed.entity, hero.id());
+        assert_eq!(changed.components.len(), 1);
+        assert!(changed.components[0]
+            .reflect_partial_eq(&Health(5))
+            .unwrap());
+        assert_eq!(patch.added_entities.len(), 1);
+        assert_eq!(patch.added_entities[0].entity, shield.id());
+
+        let registry = TypeRegistryArc::default();
+        registry.write().register::<Name>();
+        registry.write().register::<Health>();
+        let ron = patch.serialize_ron(&registry).unwrap();
+        assert!(ron.contains("shield"));
+
+        scene.apply_patch(&patch, &type_registry).unwrap();
+        assert!(scene.diff(&modified, &type_registry).is_empty());
+        assert!(modified.diff(&scene, &type_registry).is_empty());
+
+        let revert = modified.diff(&Scene::new(prefab()), &type_registry);
+        assert_eq!(revert.removed_entities, vec![shield.id()]);
+        assert_eq!(revert.changed_components.len(), 1);
+        assert!(revert.added_entities.is_empty());
+    }
+
+    #[test]
+    fn patch_round_trips_through_ron() {
+        let registry = TypeRegistryArc::default();
+        registry.write().register::<Name>();
+        registry.write().register::<Health>();
+        let mut scene = Scene::new(prefab());
+        let mut modified_world = prefab();
+        modified_world
+            .spawn()
+            .insert_bundle((Name("shield".to_string()), Health(3)));
+        let hero = named(&mut modified_world, "hero");
+        modified_world.entity_mut(hero).remove::<Health>();
+        modified_world.get_mut::<Name>(hero).unwrap().0 = "knight".to_string();
+        let sword = named(&mut modified_world, "sword");
+        modified_world.despawn(sword);
+        let modified = Scene::new(modified_world);
+
+        let patch = scene.diff(&modified, &registry.read());
+        assert_eq!(patch.added_entities.len(), 1);
+        assert_eq!(patch.removed_entities, vec![sword.id()]);
+        assert_eq!(patch.changed_components.len(), 1);
+        assert_eq!(patch.removed_components.len(), 1);
+
+        let ron = patch.serialize_ron(&registry).unwrap();
+        let mut deserializer = ron::de::Deserializer::from_str(&ron).unwrap();
+        let loaded = ScenePatchDeserializer {
+            type_registry: &registry.read(),
+        }
+        .deserialize(&mut deserializer)
+        .unwrap();
+        assert_eq!(loaded.removed_entities, patch.removed_entities);
+        assert_eq!(loaded.removed_components, patch.removed_components);
+
+        scene.apply_patch(&loaded, &registry.read()).unwrap();
+        assert!(scene.diff(&modified, &registry.read()).is_empty());
+        assert!(modified.diff(&scene, &registry.read()).is_empty());
+    }
+
+    #[test]
+    fn failed_patch_changes_nothing() {
+        let mut type_registry = TypeRegistry::default();
+        type_registry.register::<Name>();
+        type_registry.register::<Health>();
+        let mut scene = Scene::new(prefab());
+        let mut modified_world = prefab();
+        modified_world.spawn().insert(Name("shield".to_string()));
+        let hero = named(&mut modified_world, "hero");
+        modified_world.get_mut::<Health>(hero).unwrap().0 = 5;
+        let patch = scene.diff(&Scene::new(modified_world), &type_registry);
+
+        // the shield could be added, but the health of the hero can't be changed
+        let mut names_only = TypeRegistry::default();
+        names_only.register::<Name>();
+        assert!(scene.apply_patch(&patch, &names_only).is_err());
+        assert!(scene.diff(&Scene::new(prefab()), &type_registry).is_empty());
+    }
+}
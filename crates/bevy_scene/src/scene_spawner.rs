@@ -1,13 +1,13 @@
-use crate::{DynamicScene, Scene};
+use crate::{DynamicScene, Scene, SceneCopyPlan, SceneStream, SceneStreamProgress};
 use bevy_app::{Events, ManualEventReader};
 use bevy_asset::{AssetEvent, Assets, Handle};
 use bevy_ecs::{
     entity::{Entity, EntityMap},
-    reflect::{ReflectComponent, ReflectMapEntities},
+    reflect::{ReflectConstructError, ReflectMapEntities},
     system::Command,
     world::{Mut, World},
 };
-use bevy_reflect::TypeRegistryArc;
+use bevy_reflect::{TypeRegistry, TypeRegistryArc};
 use bevy_transform::{hierarchy::AddChild, prelude::Parent};
 use bevy_utils::{tracing::error, HashMap};
 use thiserror::Error;
@@ -18,6 +18,15 @@ struct InstanceInfo {
     entity_map: EntityMap,
 }
 
+/// A scene spawned a bounded number of entities per frame by [`SceneSpawner::stream`].
+struct StreamedScene {
+    handle: Handle<Scene>,
+    instance_id: InstanceId,
+    entities_per_frame: usize,
+    /// Created once the scene is loaded.
+    stream: Option<SceneStream>,
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct InstanceId(Uuid);
 
@@ -33,22 +42,35 @@ pub struct SceneSpawner {
     spawned_dynamic_scenes: HashMap<Handle<DynamicScene>, Vec<InstanceId>>,
     spawned_instances: HashMap<InstanceId, InstanceInfo>,
     scene_asset_event_reader: ManualEventReader<AssetEvent<DynamicScene>>,
+    real_scene_asset_event_reader: ManualEventReader<AssetEvent<Scene>>,
+    /// The copy plans of the spawned scenes, prepared again when they are outdated and removed
+    /// when their scene is modified.
+    copy_plans: HashMap<Handle<Scene>, SceneCopyPlan>,
     dynamic_scenes_to_spawn: Vec<Handle<DynamicScene>>,
     scenes_to_spawn: Vec<(Handle<Scene>, InstanceId)>,
     scenes_to_despawn: Vec<Handle<DynamicScene>>,
     scenes_with_parent: Vec<(InstanceId, Entity)>,
+    streamed_scenes: Vec<StreamedScene>,
 }
 
 #[derive(Error, Debug)]
 pub enum SceneSpawnError {
-    #[error("scene contains the unregistered component `{type_name}`. consider adding `#[reflect(Component)]` to your type")]
+    /// The type is in the registry, but without [`ReflectComponent`](bevy_ecs::reflect::ReflectComponent) data.
+    #[error("scene contains the type `{type_name}`, which is registered but doesn't reflect `Component`. consider adding `#[reflect(Component)]` to your type")]
     UnregisteredComponent { type_name: String },
-    #[error("scene contains the unregistered type `{type_name}`. consider registering the type using `app.register_type::<T>()`")]
+    /// The type is not in the registry at all.
+    #[error("scene contains the unregistered type `{type_name}`. consider deriving `Reflect` for it and registering it using `app.register_type::<T>()`")]
     UnregisteredType { type_name: String },
     #[error("scene does not exist")]
     NonExistentScene { handle: Handle<DynamicScene> },
     #[error("scene does not exist")]
     NonExistentRealScene { handle: Handle<Scene> },
+    /// A component of the scene couldn't be built from its reflected value.
+    #[error(transparent)]
+    InvalidComponent(#[from] ReflectConstructError),
+    /// The scene was replaced or its entities changed while a [`SceneStream`] was writing it.
+    #[error("scene changed while it was streamed")]
+    SceneChangedWhileStreamed,
 }
 
 impl SceneSpawner {
@@ -62,6 +84,32 @@ impl SceneSpawner {
         instance_id
     }
 
+    /// Spawns the scene over several frames, `entities_per_frame` entities at a time, to avoid
+    /// blocking a frame on a huge scene.
+    ///
+    /// The instance is only ready once all its entities are spawned, and the references between
+    /// them mapped. See [`SceneSpawner::stream_progress`] in the meantime.
+    pub fn stream(&mut self, scene_handle: Handle<Scene>, entities_per_frame: usize) -> InstanceId {
+        let instance_id = InstanceId::new();
+        self.streamed_scenes.push(StreamedScene {
+            handle: scene_handle,
+            instance_id,
+            entities_per_frame,
+            stream: None,
+        });
+        instance_id
+    }
+
+    /// Returns the progress of a scene instance spawned with [`SceneSpawner::stream`], or
+    /// [`None`] if it isn't streamed or its scene isn't loaded yet.
+    pub fn stream_progress(&self, instance_id: InstanceId) -> Option<SceneStreamProgress> {
+        self.streamed_scenes
+            .iter()
+            .find(|streamed| streamed.instance_id == instance_id)
+            .and_then(|streamed| streamed.stream.as_ref())
+            .map(SceneStream::progress)
+    }
+
     pub fn spawn_as_child(&mut self, scene_handle: Handle<Scene>, parent: Entity) -> InstanceId {
         let instance_id = InstanceId::new();
         self.scenes_to_spawn.push((scene_handle, instance_id));
@@ -154,40 +202,8 @@ impl SceneSpawner {
                         handle: scene_handle.clone(),
                     })?;
 
-            for archetype in scene.world.archetypes().iter() {
-                for scene_entity in archetype.entities() {
-                    let entity = *instance_info
-                        .entity_map
-                        .entry(*scene_entity)
-                        .or_insert_with(|| world.spawn().id());
-                    for component_id in archetype.components() {
-                        let component_info = scene
-                            .world
-                            .components()
-                            .get_info(component_id)
-                            .expect("component_ids in archetypes should have ComponentInfo");
-
-                        let reflect_component = type_registry
-                            .get(component_info.type_id().unwrap())
-                            .ok_or_else(|| SceneSpawnError::UnregisteredType {
-                                type_name: component_info.name().to_string(),
-                            })
-                            .and_then(|registration| {
-                                registration.data::<ReflectComponent>().ok_or_else(|| {
-                                    SceneSpawnError::UnregisteredComponent {
-                                        type_name: component_info.name().to_string(),
-                                    }
-                                })
-                            })?;
-                        reflect_component.copy_component(
-                            &scene.world,
-                            world,
-                            *scene_entity,
-                            entity,
-                        );
-                    }
-                }
-            }
+            let plan = Self::copy_plan(&mut self.copy_plans, &scene_handle, scene, &type_registry)?;
+            scene.write_prepared(plan, world, &mut instance_info.entity_map);
             for registration in type_registry.iter() {
                 if let Some(map_entities_reflect) = registration.data::<ReflectMapEntities>() {
                     map_entities_reflect
@@ -205,6 +221,23 @@ impl SceneSpawner {
         })
     }
 
+    /// Returns the cached copy plan of `scene`, preparing it if it is missing or outdated.
+    fn copy_plan<'a>(
+        copy_plans: &'a mut HashMap<Handle<Scene>, SceneCopyPlan>,
+        scene_handle: &Handle<Scene>,
+        scene: &Scene,
+        type_registry: &TypeRegistry,
+    ) -> Result<&'a SceneCopyPlan, SceneSpawnError> {
+        let outdated = copy_plans
+            .get(scene_handle)
+            .map_or(true, |plan| plan.is_outdated(scene, type_registry));
+        if outdated {
+            let plan = scene.prepare_copy_plan(type_registry)?;
+            copy_plans.insert(scene_handle.clone_weak(), plan);
+        }
+        Ok(&copy_plans[scene_handle])
+    }
+
     pub fn update_spawned_scenes(
         &mut self,
         world: &mut World,
@@ -263,6 +296,58 @@ impl SceneSpawner {
         Ok(())
     }
 
+    /// Spawns the next entities of the streamed scenes, completing their instance once all their
+    /// entities are spawned.
+    pub fn stream_queued_scenes(&mut self, world: &mut World) -> Result<(), SceneSpawnError> {
+        let streamed_scenes = std::mem::take(&mut self.streamed_scenes);
+        let type_registry = world.get_resource::<TypeRegistryArc>().unwrap().clone();
+        let type_registry = type_registry.read();
+        for mut streamed in streamed_scenes {
+            let progress = world.resource_scope(
+                |world, scenes: Mut<Assets<Scene>>| -> Result<_, SceneSpawnError> {
+                    let scene = match scenes.get(&streamed.handle) {
+                        Some(scene) => scene,
+                        None => return Ok(None),
+                    };
+                    if streamed.stream.is_none() {
+                        streamed.stream = Some(SceneStream::new(scene, &type_registry)?);
+                    }
+                    let stream = streamed.stream.as_mut().unwrap();
+                    match stream.write_next(scene, world, streamed.entities_per_frame) {
+                        Err(SceneSpawnError::SceneChangedWhileStreamed) => {
+                            // The scene was reloaded or modified: the entities written so far are
+                            // despawned, and it is streamed again from the start
+                            let entity_map = streamed.stream.take().unwrap().into_entity_map();
+                            for entity in entity_map.values() {
+                                world.despawn(entity);
+                            }
+                            let mut stream = SceneStream::new(scene, &type_registry)?;
+                            let progress =
+                                stream.write_next(scene, world, streamed.entities_per_frame)?;
+                            streamed.stream = Some(stream);
+                            Ok(Some(progress))
+                        }
+                        progress => progress.map(Some),
+                    }
+                },
+            )?;
+            if progress.map_or(false, |progress| progress.is_complete()) {
+                let instance_info = InstanceInfo {
+                    entity_map: streamed.stream.take().unwrap().into_entity_map(),
+                };
+                self.spawned_instances
+                    .insert(streamed.instance_id, instance_info);
+                self.spawned_scenes
+                    .entry(streamed.handle)
+                    .or_insert_with(Vec::new)
+                    .push(streamed.instance_id);
+            } else {
+                self.streamed_scenes.push(streamed);
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn set_scene_instance_parent_sync(&mut self, world: &mut World) {
         let scenes_with_parent = std::mem::take(&mut self.scenes_with_parent);
 
@@ -317,6 +402,16 @@ pub fn scene_spawner_system(world: &mut World) {
 
         let mut updated_spawned_scenes = Vec::new();
         let scene_spawner = &mut *scene_spawner;
+        let real_scene_asset_events = world.get_resource::<Events<AssetEvent<Scene>>>().unwrap();
+        for event in scene_spawner
+            .real_scene_asset_event_reader
+            .iter(real_scene_asset_events)
+        {
+            if let AssetEvent::Modified { handle } | AssetEvent::Removed { handle } = event {
+                scene_spawner.copy_plans.remove(handle);
+            }
+        }
+
         for event in scene_spawner
             .scene_asset_event_reader
             .iter(scene_asset_events)
@@ -332,9 +427,64 @@ pub fn scene_spawner_system(world: &mut World) {
         scene_spawner
             .spawn_queued_scenes(world)
             .unwrap_or_else(|err| panic!("{}", err));
+        scene_spawner
+            .stream_queued_scenes(world)
+            .unwrap_or_else(|err| panic!("{}", err));
         scene_spawner
             .update_spawned_scenes(world, &updated_spawned_scenes)
             .unwrap();
         scene_spawner.set_scene_instance_parent_sync(world);
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::SceneSpawner;
+    use crate::Scene;
+    use bevy_asset::{Handle, HandleId};
+    use bevy_ecs::{prelude::Component, reflect::ReflectComponent, world::World};
+    use bevy_reflect::{Reflect, TypeRegistry};
+    use bevy_utils::HashMap;
+
+    #[derive(Component, Reflect, Default)]
+    #[reflect(Component)]
+    struct Name(String);
+
+    #[derive(Component, Reflect, Default)]
+    #[reflect(Component)]
+    struct Visible;
+
+    #[test]
+    fn copy_plans_are_cached() {
+        let mut type_registry = TypeRegistry::default();
+        type_registry.register::<Name>();
+        let mut world = World::new();
+        world.spawn().insert(Name("a".to_string()));
+        let mut scene = Scene::new(world);
+        let handle = Handle::<Scene>::weak(HandleId::random::<Scene>());
+        let mut copy_plans = HashMap::default();
+
+        SceneSpawner::copy_plan(&mut copy_plans, &handle, &scene, &type_registry).unwrap();
+        assert!(!copy_plans[&handle].is_outdated(&scene, &type_registry));
+        // a plan isn't reused with another registry, even one with as many registrations
+        let mut other_registry = TypeRegistry::default();
+        other_registry.register::<Visible>();
+        assert!(copy_plans[&handle].is_outdated(&scene, &other_registry));
+        assert!(
+            SceneSpawner::copy_plan(&mut copy_plans, &handle, &scene, &other_registry).is_err()
+        );
+
+        // the plan is prepared again once the registry changes
+        type_registry.register::<Visible>();
+        let plan =
+            SceneSpawner::copy_plan(&mut copy_plans, &handle, &scene, &type_registry).unwrap();
+        assert!(!plan.is_outdated(&scene, &type_registry));
+
+        // or once the scene gains archetypes
+        scene.world.spawn().insert(Visible);
+        let plan =
+            SceneSpawner::copy_plan(&mut copy_plans, &handle, &scene, &type_registry).unwrap();
+        assert!(!plan.is_outdated(&scene, &type_registry));
+        assert_eq!(copy_plans.len(), 1);
+    }
+}
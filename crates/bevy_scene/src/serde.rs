@@ -1,4 +1,4 @@
-use crate::{DynamicEntity, DynamicScene};
+use crate::{DynamicEntity, DynamicScene, ScenePatch};
 use anyhow::Result;
 use bevy_reflect::{
     serde::{ReflectDeserializer, ReflectSerializer},
@@ -37,6 +37,76 @@ impl<'a> Serialize for SceneSerializer<'a> {
     }
 }
 
+pub struct ScenePatchSerializer<'a> {
+    pub patch: &'a ScenePatch,
+    pub registry: &'a TypeRegistryArc,
+}
+
+impl<'a> ScenePatchSerializer<'a> {
+    pub fn new(patch: &'a ScenePatch, registry: &'a TypeRegistryArc) -> Self {
+        ScenePatchSerializer { patch, registry }
+    }
+}
+
+impl<'a> Serialize for ScenePatchSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct(SCENE_PATCH_STRUCT, 4)?;
+        state.serialize_field(
+            SCENE_PATCH_FIELD_ADDED_ENTITIES,
+            &EntitiesSerializer {
+                entities: &self.patch.added_entities,
+                registry: self.registry,
+            },
+        )?;
+        state.serialize_field(
+            SCENE_PATCH_FIELD_REMOVED_ENTITIES,
+            &self.patch.removed_entities,
+        )?;
+        state.serialize_field(
+            SCENE_PATCH_FIELD_CHANGED_COMPONENTS,
+            &EntitiesSerializer {
+                entities: &self.patch.changed_components,
+                registry: self.registry,
+            },
+        )?;
+        state.serialize_field(
+            SCENE_PATCH_FIELD_REMOVED_COMPONENTS,
+            &self.patch.removed_components,
+        )?;
+        state.end()
+    }
+}
+
+pub const SCENE_PATCH_STRUCT: &str = "ScenePatch";
+pub const SCENE_PATCH_FIELD_ADDED_ENTITIES: &str = "added_entities";
+pub const SCENE_PATCH_FIELD_REMOVED_ENTITIES: &str = "removed_entities";
+pub const SCENE_PATCH_FIELD_CHANGED_COMPONENTS: &str = "changed_components";
+pub const SCENE_PATCH_FIELD_REMOVED_COMPONENTS: &str = "removed_components";
+
+struct EntitiesSerializer<'a> {
+    entities: &'a [DynamicEntity],
+    registry: &'a TypeRegistryArc,
+}
+
+impl<'a> Serialize for EntitiesSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_seq(Some(self.entities.len()))?;
+        for entity in self.entities.iter() {
+            state.serialize_element(&EntitySerializer {
+                entity,
+                registry: self.registry,
+            })?;
+        }
+        state.end()
+    }
+}
+
 pub struct EntitySerializer<'a> {
     pub entity: &'a DynamicEntity,
     pub registry: &'a TypeRegistryArc,
@@ -209,6 +279,123 @@ impl<'a, 'de> Visitor<'de> for SceneEntityVisitor<'a> {
     }
 }
 
+pub struct ScenePatchDeserializer<'a> {
+    pub type_registry: &'a TypeRegistry,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for ScenePatchDeserializer<'a> {
+    type Value = ScenePatch;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(
+            SCENE_PATCH_STRUCT,
+            &[
+                SCENE_PATCH_FIELD_ADDED_ENTITIES,
+                SCENE_PATCH_FIELD_REMOVED_ENTITIES,
+                SCENE_PATCH_FIELD_CHANGED_COMPONENTS,
+                SCENE_PATCH_FIELD_REMOVED_COMPONENTS,
+            ],
+            ScenePatchVisitor {
+                registry: self.type_registry,
+            },
+        )
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "snake_case")]
+enum ScenePatchField {
+    AddedEntities,
+    RemovedEntities,
+    ChangedComponents,
+    RemovedComponents,
+}
+
+struct ScenePatchVisitor<'a> {
+    pub registry: &'a TypeRegistry,
+}
+
+impl<'a, 'de> Visitor<'de> for ScenePatchVisitor<'a> {
+    type Value = ScenePatch;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("scene patch")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut added_entities = None;
+        let mut removed_entities = None;
+        let mut changed_components = None;
+        let mut removed_components = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                ScenePatchField::AddedEntities => {
+                    if added_entities.is_some() {
+                        return Err(Error::duplicate_field(SCENE_PATCH_FIELD_ADDED_ENTITIES));
+                    }
+                    added_entities = Some(map.next_value_seed(EntitiesDeserializer {
+                        registry: self.registry,
+                    })?);
+                }
+                ScenePatchField::RemovedEntities => {
+                    if removed_entities.is_some() {
+                        return Err(Error::duplicate_field(SCENE_PATCH_FIELD_REMOVED_ENTITIES));
+                    }
+                    removed_entities = Some(map.next_value::<Vec<u32>>()?);
+                }
+                ScenePatchField::ChangedComponents => {
+                    if changed_components.is_some() {
+                        return Err(Error::duplicate_field(SCENE_PATCH_FIELD_CHANGED_COMPONENTS));
+                    }
+                    changed_components = Some(map.next_value_seed(EntitiesDeserializer {
+                        registry: self.registry,
+                    })?);
+                }
+                ScenePatchField::RemovedComponents => {
+                    if removed_components.is_some() {
+                        return Err(Error::duplicate_field(SCENE_PATCH_FIELD_REMOVED_COMPONENTS));
+                    }
+                    removed_components = Some(map.next_value::<Vec<(u32, String)>>()?);
+                }
+            }
+        }
+
+        Ok(ScenePatch {
+            added_entities: added_entities
+                .ok_or_else(|| Error::missing_field(SCENE_PATCH_FIELD_ADDED_ENTITIES))?,
+            removed_entities: removed_entities
+                .ok_or_else(|| Error::missing_field(SCENE_PATCH_FIELD_REMOVED_ENTITIES))?,
+            changed_components: changed_components
+                .ok_or_else(|| Error::missing_field(SCENE_PATCH_FIELD_CHANGED_COMPONENTS))?,
+            removed_components: removed_components
+                .ok_or_else(|| Error::missing_field(SCENE_PATCH_FIELD_REMOVED_COMPONENTS))?,
+        })
+    }
+}
+
+struct EntitiesDeserializer<'a> {
+    pub registry: &'a TypeRegistry,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for EntitiesDeserializer<'a> {
+    type Value = Vec<DynamicEntity>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(SceneEntitySeqVisitor {
+            type_registry: self.registry,
+        })
+    }
+}
+
 pub struct ComponentVecDeserializer<'a> {
     pub registry: &'a TypeRegistry,
 }
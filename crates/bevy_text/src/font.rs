@@ -1,10 +1,25 @@
-use ab_glyph::{FontArc, FontVec, InvalidFont, OutlinedGlyph};
+use ab_glyph::{FontArc, FontVec, GlyphImage, GlyphImageFormat, InvalidFont, OutlinedGlyph};
 use bevy_reflect::TypeUuid;
 use bevy_render::{
     render_resource::{Extent3d, TextureDimension, TextureFormat},
-    texture::Image,
+    texture::{Image, ImageType},
 };
 
+/// How glyph outlines are turned into coverage when they are rasterized into a font atlas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FontSmoothing {
+    /// Glyph edges are anti-aliased, using the partial coverage of each pixel.
+    AntiAliased,
+    /// Pixels are either fully covered or not at all, giving crisp edges for pixel-art fonts.
+    None,
+}
+
+impl Default for FontSmoothing {
+    fn default() -> Self {
+        FontSmoothing::AntiAliased
+    }
+}
+
 #[derive(Debug, TypeUuid)]
 #[uuid = "97059ac6-c9ba-4da9-95b6-bed82c3ce198"]
 pub struct Font {
@@ -18,12 +33,20 @@ impl Font {
         Ok(Font { font })
     }
 
-    pub fn get_outlined_glyph_texture(outlined_glyph: OutlinedGlyph) -> Image {
+    pub fn get_outlined_glyph_texture(
+        outlined_glyph: OutlinedGlyph,
+        font_smoothing: FontSmoothing,
+    ) -> Image {
         let bounds = outlined_glyph.px_bounds();
         let width = bounds.width() as usize;
         let height = bounds.height() as usize;
         let mut alpha = vec![0.0; width * height];
         outlined_glyph.draw(|x, y, v| {
+            let v = match font_smoothing {
+                FontSmoothing::AntiAliased => v,
+                FontSmoothing::None if v >= 0.5 => 1.0,
+                FontSmoothing::None => 0.0,
+            };
             alpha[y as usize * width + x as usize] = v;
         });
 
@@ -43,4 +66,77 @@ impl Font {
             TextureFormat::Rgba8UnormSrgb,
         )
     }
+
+    /// Decodes the colored bitmap of a glyph, usually an emoji, scaled to `font_size` from the
+    /// size of its strike.
+    ///
+    /// Returns [`None`] if the image can't be decoded, which is always the case for PNG images
+    /// when the `png` feature of `bevy_render` isn't enabled.
+    pub fn get_color_glyph_texture(glyph_image: &GlyphImage, font_size: f32) -> Option<Image> {
+        let image = match glyph_image.format {
+            GlyphImageFormat::Png => {
+                Image::from_buffer(glyph_image.data, ImageType::MimeType("image/png")).ok()?
+            }
+            _ => return None,
+        };
+        if image.texture_descriptor.format != TextureFormat::Rgba8UnormSrgb {
+            return None;
+        }
+        let source_size = image.texture_descriptor.size;
+        let scale = font_size / glyph_image.scale;
+        let width = ((source_size.width as f32 * scale).round() as u32).max(1);
+        let height = ((source_size.height as f32 * scale).round() as u32).max(1);
+        // nearest sampling, emoji strikes are usually close to the font size
+        let mut data = Vec::with_capacity((width * height * 4) as usize);
+        for y in 0..height {
+            let source_y = (y * source_size.height / height) as usize;
+            for x in 0..width {
+                let source_x = (x * source_size.width / width) as usize;
+                let index = (source_y * source_size.width as usize + source_x) * 4;
+                data.extend_from_slice(&image.data[index..index + 4]);
+            }
+        }
+        Some(Image::new(
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Font, FontSmoothing};
+    use ab_glyph::{Font as _, PxScale};
+
+    #[test]
+    fn font_smoothing_none_thresholds_coverage() {
+        let font = Font::try_from_bytes(
+            include_bytes!("../../../assets/fonts/FiraMono-Medium.ttf").to_vec(),
+        )
+        .unwrap();
+        let outline = || {
+            let glyph = font.font.glyph_id('a').with_scale(PxScale::from(12.0));
+            font.font.outline_glyph(glyph).unwrap()
+        };
+
+        let anti_aliased = Font::get_outlined_glyph_texture(outline(), FontSmoothing::AntiAliased);
+        let none = Font::get_outlined_glyph_texture(outline(), FontSmoothing::None);
+
+        let alpha = |image: &bevy_render::texture::Image| {
+            image
+                .data
+                .chunks(4)
+                .map(|pixel| pixel[3])
+                .collect::<Vec<_>>()
+        };
+        assert_ne!(alpha(&anti_aliased), alpha(&none));
+        assert!(alpha(&anti_aliased).iter().any(|a| *a != 0 && *a != 255));
+        assert!(alpha(&none).iter().all(|a| *a == 0 || *a == 255));
+    }
 }
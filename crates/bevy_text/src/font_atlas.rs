@@ -6,7 +6,7 @@ use bevy_render::{
     texture::Image,
 };
 use bevy_sprite::{DynamicTextureAtlasBuilder, TextureAtlas};
-use bevy_utils::HashMap;
+use bevy_utils::{HashMap, HashSet};
 
 #[cfg(feature = "subpixel_glyph_atlas")]
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
@@ -42,6 +42,9 @@ impl From<Point> for SubpixelOffset {
 pub struct FontAtlas {
     pub dynamic_texture_atlas_builder: DynamicTextureAtlasBuilder,
     pub glyph_to_atlas_index: HashMap<(GlyphId, SubpixelOffset), usize>,
+    /// The atlas indices of the glyphs whose texture holds their colors rather than their
+    /// coverage.
+    pub color_glyphs: HashSet<usize>,
     pub texture_atlas: Handle<TextureAtlas>,
 }
 
@@ -65,6 +68,7 @@ impl FontAtlas {
         Self {
             texture_atlas: texture_atlases.add(texture_atlas),
             glyph_to_atlas_index: HashMap::default(),
+            color_glyphs: HashSet::default(),
             dynamic_texture_atlas_builder: DynamicTextureAtlasBuilder::new(size, 1),
         }
     }
@@ -84,6 +88,15 @@ impl FontAtlas {
             .contains_key(&(glyph_id, subpixel_offset))
     }
 
+    /// Returns `true` if the glyph at `index` in the atlas is a color glyph.
+    pub fn is_color_glyph(&self, index: usize) -> bool {
+        self.color_glyphs.contains(&index)
+    }
+
+    /// Adds the texture of a glyph to the atlas, returning `false` if there is no room left.
+    ///
+    /// `is_color` tells if `texture` holds the colors of the glyph, rather than its coverage in
+    /// the alpha channel.
     pub fn add_glyph(
         &mut self,
         textures: &mut Assets<Image>,
@@ -91,6 +104,7 @@ impl FontAtlas {
         glyph_id: GlyphId,
         subpixel_offset: SubpixelOffset,
         texture: &Image,
+        is_color: bool,
     ) -> bool {
         let texture_atlas = texture_atlases.get_mut(&self.texture_atlas).unwrap();
         if let Some(index) =
@@ -99,6 +113,9 @@ impl FontAtlas {
         {
             self.glyph_to_atlas_index
                 .insert((glyph_id, subpixel_offset), index);
+            if is_color {
+                self.color_glyphs.insert(index);
+            }
             true
         } else {
             false
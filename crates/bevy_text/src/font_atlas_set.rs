@@ -1,5 +1,5 @@
-use crate::{error::TextError, Font, FontAtlas};
-use ab_glyph::{GlyphId, OutlinedGlyph, Point};
+use crate::{error::TextError, Font, FontAtlas, FontSmoothing};
+use ab_glyph::{Glyph, GlyphId, OutlinedGlyph, Point};
 use bevy_asset::{Assets, Handle};
 use bevy_core::FloatOrd;
 use bevy_math::Vec2;
@@ -8,18 +8,21 @@ use bevy_render::texture::Image;
 use bevy_sprite::TextureAtlas;
 use bevy_utils::HashMap;
 
-type FontSizeKey = FloatOrd;
+type FontAtlasKey = (FloatOrd, FontSmoothing);
 
 #[derive(TypeUuid)]
 #[uuid = "73ba778b-b6b5-4f45-982d-d21b6b86ace2"]
 pub struct FontAtlasSet {
-    font_atlases: HashMap<FontSizeKey, Vec<FontAtlas>>,
+    font_atlases: HashMap<FontAtlasKey, Vec<FontAtlas>>,
 }
 
 #[derive(Debug, Clone)]
 pub struct GlyphAtlasInfo {
     pub texture_atlas: Handle<TextureAtlas>,
     pub glyph_index: usize,
+    /// Whether the glyph texture holds the colors of the glyph, like an emoji, which should be
+    /// drawn as is, rather than its coverage, which is tinted with the text color.
+    pub is_color: bool,
 }
 
 impl Default for FontAtlasSet {
@@ -31,13 +34,19 @@ impl Default for FontAtlasSet {
 }
 
 impl FontAtlasSet {
-    pub fn iter(&self) -> impl Iterator<Item = (&FontSizeKey, &Vec<FontAtlas>)> {
+    pub fn iter(&self) -> impl Iterator<Item = (&FontAtlasKey, &Vec<FontAtlas>)> {
         self.font_atlases.iter()
     }
 
-    pub fn has_glyph(&self, glyph_id: GlyphId, glyph_position: Point, font_size: f32) -> bool {
+    pub fn has_glyph(
+        &self,
+        glyph_id: GlyphId,
+        glyph_position: Point,
+        font_size: f32,
+        font_smoothing: FontSmoothing,
+    ) -> bool {
         self.font_atlases
-            .get(&FloatOrd(font_size))
+            .get(&(FloatOrd(font_size), font_smoothing))
             .map_or(false, |font_atlas| {
                 font_atlas
                     .iter()
@@ -50,14 +59,55 @@ impl FontAtlasSet {
         texture_atlases: &mut Assets<TextureAtlas>,
         textures: &mut Assets<Image>,
         outlined_glyph: OutlinedGlyph,
+        font_smoothing: FontSmoothing,
+    ) -> Result<GlyphAtlasInfo, TextError> {
+        let glyph = outlined_glyph.glyph().clone();
+        let glyph_texture = Font::get_outlined_glyph_texture(outlined_glyph, font_smoothing);
+        self.add_texture_to_atlas(
+            texture_atlases,
+            textures,
+            &glyph,
+            font_smoothing,
+            &glyph_texture,
+            false,
+        )
+    }
+
+    /// Adds the colored texture of `glyph`, given by [`Font::get_color_glyph_texture`], to an
+    /// atlas.
+    pub fn add_color_glyph_to_atlas(
+        &mut self,
+        texture_atlases: &mut Assets<TextureAtlas>,
+        textures: &mut Assets<Image>,
+        glyph: &Glyph,
+        font_smoothing: FontSmoothing,
+        glyph_texture: &Image,
+    ) -> Result<GlyphAtlasInfo, TextError> {
+        self.add_texture_to_atlas(
+            texture_atlases,
+            textures,
+            glyph,
+            font_smoothing,
+            glyph_texture,
+            true,
+        )
+    }
+
+    fn add_texture_to_atlas(
+        &mut self,
+        texture_atlases: &mut Assets<TextureAtlas>,
+        textures: &mut Assets<Image>,
+        glyph: &Glyph,
+        font_smoothing: FontSmoothing,
+        glyph_texture: &Image,
+        is_color: bool,
     ) -> Result<GlyphAtlasInfo, TextError> {
-        let glyph = outlined_glyph.glyph();
         let glyph_id = glyph.id;
         let glyph_position = glyph.position;
         let font_size = glyph.scale.y;
         let font_atlases = self
             .font_atlases
-            .entry(FloatOrd(font_size))
+            .entry((FloatOrd(font_size), font_smoothing))
             .or_insert_with(|| {
                 vec![FontAtlas::new(
                     textures,
@@ -65,14 +115,14 @@ impl FontAtlasSet {
                     Vec2::new(512.0, 512.0),
                 )]
             });
-        let glyph_texture = Font::get_outlined_glyph_texture(outlined_glyph);
         let add_char_to_font_atlas = |atlas: &mut FontAtlas| -> bool {
             atlas.add_glyph(
                 textures,
                 texture_atlases,
                 glyph_id,
                 glyph_position.into(),
-                &glyph_texture,
+                glyph_texture,
+                is_color,
             )
         };
         if !font_atlases.iter_mut().any(add_char_to_font_atlas) {
@@ -86,37 +136,37 @@ impl FontAtlasSet {
                 texture_atlases,
                 glyph_id,
                 glyph_position.into(),
-                &glyph_texture,
+                glyph_texture,
+                is_color,
             ) {
                 return Err(TextError::FailedToAddGlyph(glyph_id));
             }
         }
 
         Ok(self
-            .get_glyph_atlas_info(font_size, glyph_id, glyph_position)
+            .get_glyph_atlas_info(font_size, font_smoothing, glyph_id, glyph_position)
             .unwrap())
     }
 
     pub fn get_glyph_atlas_info(
         &self,
         font_size: f32,
+        font_smoothing: FontSmoothing,
         glyph_id: GlyphId,
         position: Point,
     ) -> Option<GlyphAtlasInfo> {
         self.font_atlases
-            .get(&FloatOrd(font_size))
+            .get(&(FloatOrd(font_size), font_smoothing))
             .and_then(|font_atlases| {
-                font_atlases
-                    .iter()
-                    .find_map(|atlas| {
-                        atlas
-                            .get_glyph_index(glyph_id, position.into())
-                            .map(|glyph_index| (glyph_index, atlas.texture_atlas.clone_weak()))
-                    })
-                    .map(|(glyph_index, texture_atlas)| GlyphAtlasInfo {
-                        texture_atlas,
-                        glyph_index,
-                    })
+                font_atlases.iter().find_map(|atlas| {
+                    atlas
+                        .get_glyph_index(glyph_id, position.into())
+                        .map(|glyph_index| GlyphAtlasInfo {
+                            texture_atlas: atlas.texture_atlas.clone_weak(),
+                            glyph_index,
+                            is_color: atlas.is_color_glyph(glyph_index),
+                        })
+                })
             })
     }
 }
@@ -1,16 +1,25 @@
+use std::{
+    cell::Cell,
+    hash::{Hash, Hasher},
+};
+
 use ab_glyph::{Font as _, FontArc, Glyph, ScaleFont as _};
 use bevy_asset::{Assets, Handle};
 use bevy_math::{Size, Vec2};
 use bevy_render::texture::Image;
 use bevy_sprite::TextureAtlas;
 use glyph_brush_layout::{
-    FontId, GlyphPositioner, Layout, SectionGeometry, SectionGlyph, SectionText, ToSectionText,
+    BuiltInLineBreaker, FontId, GlyphPositioner, Layout, LineBreak, LineBreaker, SectionGeometry,
+    SectionGlyph, SectionText, ToSectionText,
 };
 
-use crate::{error::TextError, Font, FontAtlasSet, GlyphAtlasInfo, TextAlignment};
+use crate::{
+    error::TextError, BreakLineOn, Font, FontAtlasSet, FontSmoothing, GlyphAtlasInfo,
+    HorizontalAlign, TextAlignment,
+};
 
 pub struct GlyphBrush {
-    fonts: Vec<FontArc>,
+    pub(crate) fonts: Vec<FontArc>,
     handles: Vec<Handle<Font>>,
     latest_font_id: FontId,
 }
@@ -26,9 +35,13 @@ impl Default for GlyphBrush {
 }
 
 impl GlyphBrush {
+    /// Lays out `sections`, breaking the lines of each one according to the matching entry of
+    /// `linebreak_behaviors`, and kerning its glyphs if the matching entry of `kerning` is set.
     pub fn compute_glyphs<S: ToSectionText>(
         &self,
         sections: &[S],
+        linebreak_behaviors: &[BreakLineOn],
+        kerning: &[bool],
         bounds: Size,
         text_alignment: TextAlignment,
     ) -> Result<Vec<SectionGlyph>, TextError> {
@@ -36,17 +49,52 @@ impl GlyphBrush {
             bounds: (bounds.width, bounds.height),
             ..Default::default()
         };
-        let section_glyphs = Layout::default()
-            .h_align(text_alignment.horizontal.into())
-            .v_align(text_alignment.vertical.into())
-            .calculate_glyphs(&self.fonts, &geom, sections);
+        // The layout skips the sections without a scale, so they are never broken
+        let section_breaks = sections
+            .iter()
+            .zip(linebreak_behaviors)
+            .filter(|(section, _)| {
+                let scale = section.to_section_text().scale;
+                scale.x > 0.0 && scale.y > 0.0
+            })
+            .map(|(section, linebreak_behavior)| {
+                (
+                    section.to_section_text().text.as_ptr() as usize,
+                    *linebreak_behavior,
+                )
+            })
+            .collect::<Vec<_>>();
+        let current_section = Cell::new(None);
+        let mut section_glyphs = Layout::Wrap {
+            line_breaker: SectionLineBreaker {
+                section_breaks: &section_breaks,
+                current_section: &current_section,
+            },
+            h_align: text_alignment.horizontal.into(),
+            v_align: text_alignment.vertical.into(),
+        }
+        .calculate_glyphs(&self.fonts, &geom, sections);
+        if kerning.iter().any(|kerning| !kerning) {
+            remove_kerning(
+                &mut section_glyphs,
+                kerning,
+                text_alignment.horizontal,
+                |previous, sg| {
+                    self.fonts[sg.font_id.0]
+                        .as_scaled(sg.glyph.scale)
+                        .kern(previous.glyph.id, sg.glyph.id)
+                },
+            );
+        }
         Ok(section_glyphs)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn process_glyphs(
         &self,
         glyphs: Vec<SectionGlyph>,
         sections: &[SectionText],
+        font_smoothing: FontSmoothing,
         font_atlas_set_storage: &mut Assets<FontAtlasSet>,
         fonts: &Assets<Font>,
         texture_atlases: &mut Assets<TextureAtlas>,
@@ -94,6 +142,57 @@ impl GlyphBrush {
             let glyph_position = glyph.position;
             let adjust = GlyphPlacementAdjuster::new(&mut glyph);
             let section_data = sections_data[sg.section_index];
+            let font_size = section_data.2;
+            // color glyphs, like emojis, are bitmaps drawn as is rather than outlines
+            if let Some(glyph_image) = section_data
+                .1
+                .font
+                .glyph_raster_image(glyph_id, font_size as u16)
+            {
+                let handle_font_atlas: Handle<FontAtlasSet> = section_data.0.as_weak();
+                let font_atlas_set = font_atlas_set_storage
+                    .get_or_insert_with(handle_font_atlas, FontAtlasSet::default);
+                let atlas_info = match font_atlas_set
+                    .get_glyph_atlas_info(font_size, font_smoothing, glyph_id, glyph_position)
+                    .filter(|atlas_info| atlas_info.is_color)
+                {
+                    Some(atlas_info) => Some(atlas_info),
+                    None => Font::get_color_glyph_texture(&glyph_image, font_size)
+                        .map(|glyph_texture| {
+                            font_atlas_set.add_color_glyph_to_atlas(
+                                texture_atlases,
+                                textures,
+                                &glyph,
+                                font_smoothing,
+                                &glyph_texture,
+                            )
+                        })
+                        .transpose()?,
+                };
+                if let Some(atlas_info) = atlas_info {
+                    let texture_atlas = texture_atlases.get(&atlas_info.texture_atlas).unwrap();
+                    let glyph_rect = texture_atlas.textures[atlas_info.glyph_index as usize];
+                    let size = Vec2::new(glyph_rect.width(), glyph_rect.height());
+
+                    // the image origin is its bottom left corner, in pixels of its strike
+                    let scale = font_size / glyph_image.scale;
+                    let left = glyph.position.x + glyph_image.origin.x * scale;
+                    let bottom = glyph.position.y - glyph_image.origin.y * scale;
+                    let x = left + size.x / 2.0 - min_x;
+                    let y = max_y - bottom + size.y / 2.0;
+                    let position = adjust.position(Vec2::new(x, y));
+
+                    positioned_glyphs.push(PositionedGlyph {
+                        position,
+                        size,
+                        atlas_info,
+                        section_index: sg.section_index,
+                        byte_index,
+                        clipped: false,
+                    });
+                    continue;
+                }
+            }
             if let Some(outlined_glyph) = section_data.1.font.outline_glyph(glyph) {
                 let bounds = outlined_glyph.px_bounds();
                 let handle_font_atlas: Handle<FontAtlasSet> = section_data.0.as_weak();
@@ -101,10 +200,15 @@ impl GlyphBrush {
                     .get_or_insert_with(handle_font_atlas, FontAtlasSet::default);
 
                 let atlas_info = font_atlas_set
-                    .get_glyph_atlas_info(section_data.2, glyph_id, glyph_position)
+                    .get_glyph_atlas_info(font_size, font_smoothing, glyph_id, glyph_position)
                     .map(Ok)
                     .unwrap_or_else(|| {
-                        font_atlas_set.add_glyph_to_atlas(texture_atlases, textures, outlined_glyph)
+                        font_atlas_set.add_glyph_to_atlas(
+                            texture_atlases,
+                            textures,
+                            outlined_glyph,
+                            font_smoothing,
+                        )
                     })?;
 
                 let texture_atlas = texture_atlases.get(&atlas_info.texture_atlas).unwrap();
@@ -121,12 +225,19 @@ impl GlyphBrush {
                     atlas_info,
                     section_index: sg.section_index,
                     byte_index,
+                    clipped: false,
                 });
             }
         }
         Ok(positioned_glyphs)
     }
 
+    /// Replaces the font at `font_id`, for example after its asset was reloaded.
+    pub fn replace_font(&mut self, font_id: FontId, handle: Handle<Font>, font: FontArc) {
+        self.fonts[font_id.0] = font;
+        self.handles[font_id.0] = handle;
+    }
+
     pub fn add_font(&mut self, handle: Handle<Font>, font: FontArc) -> FontId {
         self.fonts.push(font);
         self.handles.push(handle);
@@ -136,13 +247,112 @@ impl GlyphBrush {
     }
 }
 
+/// Undoes the kerning applied by the layout between adjacent glyphs of a line in the sections
+/// where `kerning` isn't set, shifting the rest of the line and keeping it aligned.
+///
+/// `kern` gives the kerning the layout applied before a glyph, after the previous one. Lines
+/// aren't wrapped again, so a line may slightly exceed the bounds.
+fn remove_kerning(
+    glyphs: &mut [SectionGlyph],
+    kerning: &[bool],
+    horizontal: HorizontalAlign,
+    kern: impl Fn(&SectionGlyph, &SectionGlyph) -> f32,
+) {
+    let align_factor = match horizontal {
+        HorizontalAlign::Left => 0.,
+        HorizontalAlign::Center => 0.5,
+        HorizontalAlign::Right => 1.,
+    };
+    let mut line_start = 0;
+    while line_start < glyphs.len() {
+        let line_y = glyphs[line_start].glyph.position.y;
+        let line_end = glyphs[line_start..]
+            .iter()
+            .position(|sg| sg.glyph.position.y != line_y)
+            .map_or(glyphs.len(), |len| line_start + len);
+
+        let mut offsets = Vec::with_capacity(line_end - line_start);
+        let mut offset = 0.;
+        offsets.push(offset);
+        for pair in glyphs[line_start..line_end].windows(2) {
+            let (previous, sg) = (&pair[0], &pair[1]);
+            if previous.section_index == sg.section_index
+                && previous.font_id == sg.font_id
+                && !kerning.get(sg.section_index).copied().unwrap_or(true)
+            {
+                offset -= kern(previous, sg);
+            }
+            offsets.push(offset);
+        }
+        let line_shift = offset * align_factor;
+        for (sg, offset) in glyphs[line_start..line_end].iter_mut().zip(offsets) {
+            sg.glyph.position.x += offset - line_shift;
+        }
+        line_start = line_end;
+    }
+}
+
+/// A [`LineBreaker`] applying the [`BreakLineOn`] of each section.
+///
+/// Line breakers are only given a text, but the layout breaks the text of the sections it lays out
+/// once each and in order, so the breaker counts them to find the index of the section. Sections
+/// may share their text, so the address of a text only tells whether it is the one of the next
+/// section.
+///
+/// At the end of each section, the layout also breaks copies of its last character followed by
+/// another one, to know whether the end of the section is a line break. Those use the
+/// [`BreakLineOn`] of the current section.
+#[derive(Debug, Clone, Copy)]
+struct SectionLineBreaker<'a> {
+    /// The address of the text of each section the layout breaks, and its [`BreakLineOn`].
+    section_breaks: &'a [(usize, BreakLineOn)],
+    current_section: &'a Cell<Option<usize>>,
+}
+
+impl Hash for SectionLineBreaker<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.section_breaks.hash(state);
+    }
+}
+
+impl LineBreaker for SectionLineBreaker<'_> {
+    fn line_breaks<'a>(&self, text: &'a str) -> Box<dyn Iterator<Item = LineBreak> + 'a> {
+        let current_section = self.current_section.get();
+        let next_section = current_section.map_or(0, |index| index + 1);
+        let section_index = match self.section_breaks.get(next_section) {
+            Some((address, _)) if *address == text.as_ptr() as usize => {
+                self.current_section.set(Some(next_section));
+                Some(next_section)
+            }
+            _ => current_section,
+        };
+        let linebreak_behavior = section_index
+            .and_then(|index| self.section_breaks.get(index))
+            .map_or(BreakLineOn::WordBoundary, |(_, behavior)| *behavior);
+        match linebreak_behavior {
+            BreakLineOn::WordBoundary => BuiltInLineBreaker::UnicodeLineBreaker.line_breaks(text),
+            BreakLineOn::AnyCharacter => BuiltInLineBreaker::AnyCharLineBreaker.line_breaks(text),
+            BreakLineOn::NoWrap => Box::new(
+                BuiltInLineBreaker::UnicodeLineBreaker
+                    .line_breaks(text)
+                    .filter(|line_break| matches!(line_break, LineBreak::Hard(_))),
+            ),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PositionedGlyph {
     pub position: Vec2,
     pub size: Vec2,
     pub atlas_info: GlyphAtlasInfo,
     pub section_index: usize,
+    /// The index of the character of the glyph in the value of its section, even when the
+    /// whitespace of that section is collapsed.
     pub byte_index: usize,
+    /// Whether the glyph only partially lies within the clip rect of its [`TextLayoutInfo`](crate::TextLayoutInfo),
+    /// meaning the renderer should scissor it.
+    pub clipped: bool,
 }
 
 #[cfg(feature = "subpixel_glyph_atlas")]
@@ -179,3 +389,395 @@ impl GlyphPlacementAdjuster {
         Vec2::new(self.0, 0.) + v
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ab_glyph::GlyphId;
+    use ab_glyph::{FontArc, PxScale, ScaleFont};
+    use bevy_app::App;
+    use bevy_asset::{AddAsset, AssetPlugin, Assets, Handle};
+    use bevy_core::CorePlugin;
+    use bevy_math::{Size, Vec2};
+    use bevy_render::texture::Image;
+    use bevy_sprite::TextureAtlas;
+    use glyph_brush_layout::{FontId, SectionGlyph, SectionText};
+
+    use super::{remove_kerning, GlyphBrush};
+    use crate::{
+        BreakLineOn, Font, FontAtlasSet, FontSmoothing, HorizontalAlign, TextAlignment,
+        VerticalAlign,
+    };
+
+    fn section_lines(texts: &[&str], linebreak_behaviors: &[BreakLineOn]) -> Vec<Vec<f32>> {
+        let mut brush = GlyphBrush::default();
+        let font =
+            FontArc::try_from_slice(include_bytes!("../../../assets/fonts/FiraMono-Medium.ttf"))
+                .unwrap();
+        let font_id = brush.add_font(Handle::default(), font);
+        let sections = texts
+            .iter()
+            .map(|text| SectionText {
+                text,
+                scale: PxScale::from(20.0),
+                font_id,
+            })
+            .collect::<Vec<_>>();
+        let alignment = TextAlignment {
+            vertical: VerticalAlign::Top,
+            horizontal: HorizontalAlign::Left,
+        };
+
+        let glyphs = brush
+            .compute_glyphs(
+                &sections,
+                linebreak_behaviors,
+                &vec![true; texts.len()],
+                Size::new(100.0, f32::MAX),
+                alignment,
+            )
+            .unwrap();
+
+        let mut lines = vec![Vec::new(); texts.len()];
+        for glyph in glyphs {
+            let line_y = glyph.glyph.position.y;
+            if !lines[glyph.section_index].contains(&line_y) {
+                lines[glyph.section_index].push(line_y);
+            }
+        }
+        lines
+    }
+
+    #[test]
+    fn no_wrap_section_stays_on_one_line() {
+        let texts = ["aaa bbb ", "ccc ddd eee", " fff ggg"];
+        let wrapping = section_lines(&texts, &[BreakLineOn::WordBoundary; 3]);
+        assert!(wrapping[1].len() > 1);
+
+        let no_wrap = section_lines(
+            &texts,
+            &[
+                BreakLineOn::WordBoundary,
+                BreakLineOn::NoWrap,
+                BreakLineOn::WordBoundary,
+            ],
+        );
+        assert_eq!(no_wrap[1].len(), 1);
+        let paragraph_lines = no_wrap[0].len() + no_wrap[2].len();
+        assert!(paragraph_lines > 2);
+    }
+
+    #[test]
+    fn end_of_section_probes_keep_sections_apart() {
+        // the end of each section is probed for a line break, which must not be mistaken for the
+        // next section
+        let texts = ["aaa bbb.", "ccc ddd eee", "fff ggg hhh iii"];
+        let lines = section_lines(
+            &texts,
+            &[
+                BreakLineOn::WordBoundary,
+                BreakLineOn::NoWrap,
+                BreakLineOn::WordBoundary,
+            ],
+        );
+        assert_eq!(lines[1].len(), 1);
+        assert!(lines[2].len() > 1);
+    }
+
+    #[test]
+    fn sections_sharing_text_break_separately() {
+        let text = "ccc ddd eee ";
+        let lines = section_lines(
+            &[text, text],
+            &[BreakLineOn::WordBoundary, BreakLineOn::NoWrap],
+        );
+        assert!(lines[0].len() > 1);
+        assert_eq!(lines[1].len(), 1);
+    }
+
+    #[test]
+    fn mixed_sizes_share_baseline() {
+        let mut brush = GlyphBrush::default();
+        let font =
+            FontArc::try_from_slice(include_bytes!("../../../assets/fonts/FiraMono-Medium.ttf"))
+                .unwrap();
+        let font_id = brush.add_font(Handle::default(), font);
+        let sections = [16.0, 32.0, 16.0]
+            .iter()
+            .map(|font_size| SectionText {
+                text: "ab ",
+                scale: PxScale::from(*font_size),
+                font_id,
+            })
+            .collect::<Vec<_>>();
+        let alignment = TextAlignment {
+            vertical: VerticalAlign::Top,
+            horizontal: HorizontalAlign::Left,
+        };
+
+        let glyphs = brush
+            .compute_glyphs(
+                &sections,
+                &[BreakLineOn::WordBoundary; 3],
+                &[true; 3],
+                Size::new(f32::MAX, f32::MAX),
+                alignment,
+            )
+            .unwrap();
+
+        // Glyph positions are on the baseline, shared by all the sections of the line at the
+        // max ascent of its sections, rather than at the top of each section
+        let baseline = glyphs[0].glyph.position.y;
+        assert!(glyphs
+            .iter()
+            .all(|glyph| glyph.glyph.position.y == baseline));
+        let ascent = ab_glyph::Font::as_scaled(&brush.fonts[font_id.0], 32.0).ascent();
+        assert!((baseline - ascent).abs() < 1.0);
+    }
+
+    fn kerned_lines(kerning: &[bool], horizontal: HorizontalAlign) -> Vec<f32> {
+        // two lines of 10 wide glyphs, the first one split in two sections, with a kerning of
+        // -2 between all glyphs
+        let glyph = |section_index: usize, x: f32, y: f32| SectionGlyph {
+            section_index,
+            byte_index: 0,
+            glyph: GlyphId(0).with_scale_and_position(10.0, ab_glyph::point(x, y)),
+            font_id: FontId(0),
+        };
+        let mut glyphs = vec![
+            glyph(0, 0.0, 0.0),
+            glyph(0, 8.0, 0.0),
+            glyph(1, 16.0, 0.0),
+            glyph(1, 24.0, 0.0),
+            glyph(1, 0.0, 10.0),
+            glyph(1, 8.0, 10.0),
+        ];
+        remove_kerning(&mut glyphs, kerning, horizontal, |_, _| -2.0);
+        glyphs.iter().map(|sg| sg.glyph.position.x).collect()
+    }
+
+    #[test]
+    fn kerning_can_be_disabled() {
+        let kerned = kerned_lines(&[true, true], HorizontalAlign::Left);
+        assert_eq!(kerned, vec![0.0, 8.0, 16.0, 24.0, 0.0, 8.0]);
+
+        // kerning is only removed between the glyphs of a section, not across sections or lines
+        let unkerned = kerned_lines(&[true, false], HorizontalAlign::Left);
+        assert_eq!(unkerned, vec![0.0, 8.0, 16.0, 26.0, 0.0, 10.0]);
+
+        // aligned lines stay aligned on their end or center
+        let right = kerned_lines(&[false, false], HorizontalAlign::Right);
+        assert_eq!(right, vec![-4.0, 6.0, 14.0, 24.0, -2.0, 8.0]);
+        let center = kerned_lines(&[false, false], HorizontalAlign::Center);
+        assert_eq!(center, vec![-2.0, 8.0, 16.0, 26.0, -1.0, 9.0]);
+    }
+
+    fn be16(table: &mut Vec<u8>, values: &[u16]) {
+        for value in values {
+            table.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+
+    fn be32(table: &mut Vec<u8>, values: &[u32]) {
+        for value in values {
+            table.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+
+    /// Builds a font of 1000 units per em with a glyph for each of `advances`, adding its `head`,
+    /// `hhea`, `hmtx` and `maxp` tables to `tables`.
+    fn build_font(advances: &[u16], tables: Vec<(&[u8; 4], Vec<u8>)>) -> Vec<u8> {
+        let mut head = Vec::new();
+        be32(&mut head, &[0x00010000, 0x00010000, 0, 0x5F0F3CF5]);
+        be16(&mut head, &[0, 1000]);
+        be32(&mut head, &[0; 4]);
+        be16(&mut head, &[0, 0, 1000, 1000, 0, 8, 2, 0, 0]);
+
+        let mut hhea = Vec::new();
+        be32(&mut hhea, &[0x00010000]);
+        be16(&mut hhea, &[800, (-200i16) as u16, 0, 1000]);
+        be16(&mut hhea, &[0; 11]);
+        be16(&mut hhea, &[advances.len() as u16]);
+
+        let mut hmtx = Vec::new();
+        for advance in advances {
+            be16(&mut hmtx, &[*advance, 0]);
+        }
+
+        let mut maxp = Vec::new();
+        be32(&mut maxp, &[0x00005000]);
+        be16(&mut maxp, &[advances.len() as u16]);
+
+        let mut tables = tables;
+        tables.extend([
+            (b"head", head),
+            (b"hhea", hhea),
+            (b"hmtx", hmtx),
+            (b"maxp", maxp),
+        ]);
+        tables.sort_by_key(|(tag, _)| **tag);
+        let mut font = Vec::new();
+        be32(&mut font, &[0x00010000]);
+        be16(&mut font, &[tables.len() as u16, 0, 0, 0]);
+        let mut offset = 12 + 16 * tables.len();
+        for (tag, table) in tables.iter() {
+            font.extend_from_slice(*tag);
+            be32(&mut font, &[0, offset as u32, table.len() as u32]);
+            offset += table.len();
+        }
+        for (_, table) in tables.iter() {
+            font.extend_from_slice(table);
+        }
+        font
+    }
+
+    /// Builds a font whose only glyphs are 'A' and 'V', 600 units wide, with a `kern` table
+    /// moving 'V' 100 units closer after 'A'.
+    fn kerning_font() -> Vec<u8> {
+        let mut cmap = Vec::new();
+        be16(&mut cmap, &[0, 1, 3, 10]);
+        be32(&mut cmap, &[12]);
+        // a format 12 subtable mapping 'A' to glyph 1 and 'V' to glyph 2
+        be16(&mut cmap, &[12, 0]);
+        be32(&mut cmap, &[40, 0, 2, 0x41, 0x41, 1, 0x56, 0x56, 2]);
+
+        let mut kern = Vec::new();
+        be16(&mut kern, &[0, 1]);
+        // a horizontal format 0 subtable with a single pair
+        be16(&mut kern, &[0, 20, 1]);
+        be16(&mut kern, &[1, 6, 0, 0]);
+        be16(&mut kern, &[1, 2, (-100i16) as u16]);
+
+        build_font(&[500, 600, 600], vec![(b"cmap", cmap), (b"kern", kern)])
+    }
+
+    #[test]
+    fn kerning_font_can_be_unkerned() {
+        let mut brush = GlyphBrush::default();
+        let font = FontArc::try_from_vec(kerning_font()).unwrap();
+        let font_id = brush.add_font(Handle::default(), font);
+        let sections = [SectionText {
+            text: "AV",
+            scale: PxScale::from(1000.0),
+            font_id,
+        }];
+        let alignment = TextAlignment {
+            vertical: VerticalAlign::Top,
+            horizontal: HorizontalAlign::Left,
+        };
+        let advance = |kerning: bool| {
+            let glyphs = brush
+                .compute_glyphs(
+                    &sections,
+                    &[BreakLineOn::WordBoundary],
+                    &[kerning],
+                    Size::new(f32::MAX, f32::MAX),
+                    alignment,
+                )
+                .unwrap();
+            assert_eq!(glyphs.len(), 2);
+            glyphs[1].glyph.position.x - glyphs[0].glyph.position.x
+        };
+        assert_eq!(advance(true), 500.0);
+        assert_eq!(advance(false), 600.0);
+    }
+
+    /// A 4x4 orange PNG image.
+    const ORANGE_PNG: [u8; 75] = [
+        137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 4, 0, 0, 0, 4, 8, 6,
+        0, 0, 0, 169, 241, 158, 126, 0, 0, 0, 18, 73, 68, 65, 84, 120, 218, 99, 248, 223, 192, 240,
+        31, 25, 51, 144, 46, 0, 0, 80, 79, 39, 225, 17, 114, 42, 89, 0, 0, 0, 0, 73, 69, 78, 68,
+        174, 66, 96, 130,
+    ];
+
+    /// Builds a font like color emoji fonts, whose only glyph, for U+1F600, is [`ORANGE_PNG`] in a
+    /// 16 pixels per em `sbix` strike.
+    fn color_emoji_font() -> Vec<u8> {
+        let mut cmap = Vec::new();
+        be16(&mut cmap, &[0, 1, 3, 10]);
+        be32(&mut cmap, &[12]);
+        // a format 12 subtable mapping U+1F600 to glyph 1
+        be16(&mut cmap, &[12, 0]);
+        be32(&mut cmap, &[28, 0, 1, 0x1F600, 0x1F600, 1]);
+
+        let mut sbix = Vec::new();
+        be16(&mut sbix, &[1, 1]);
+        be32(&mut sbix, &[1, 12]);
+        // the strike, with the offsets of the data of each glyph, glyph 0 having none
+        be16(&mut sbix, &[16, 72]);
+        be32(&mut sbix, &[16, 16, 16 + 8 + ORANGE_PNG.len() as u32]);
+        be16(&mut sbix, &[0, 0]);
+        sbix.extend_from_slice(b"png ");
+        sbix.extend_from_slice(&ORANGE_PNG);
+
+        build_font(&[1000, 1000], vec![(b"cmap", cmap), (b"sbix", sbix)])
+    }
+
+    #[test]
+    fn color_glyphs_are_rgba() {
+        let mut app = App::new();
+        app.add_plugin(CorePlugin)
+            .add_plugin(AssetPlugin)
+            .add_asset::<Image>()
+            .add_asset::<TextureAtlas>()
+            .add_asset::<Font>()
+            .add_asset::<FontAtlasSet>();
+        let mut fonts = app.world.remove_resource::<Assets<Font>>().unwrap();
+        let mut font_atlas_sets = app.world.remove_resource::<Assets<FontAtlasSet>>().unwrap();
+        let mut texture_atlases = app.world.remove_resource::<Assets<TextureAtlas>>().unwrap();
+        let mut textures = app.world.remove_resource::<Assets<Image>>().unwrap();
+
+        let font = Font::try_from_bytes(color_emoji_font()).unwrap();
+        let font_arc = font.font.clone();
+        let mut brush = GlyphBrush::default();
+        let font_id = brush.add_font(fonts.add(font), font_arc);
+        let sections = [SectionText {
+            text: "x\u{1F600}",
+            scale: PxScale::from(16.0),
+            font_id,
+        }];
+        let alignment = TextAlignment {
+            vertical: VerticalAlign::Top,
+            horizontal: HorizontalAlign::Left,
+        };
+        let glyphs = brush
+            .compute_glyphs(
+                &sections,
+                &[BreakLineOn::WordBoundary],
+                &[true],
+                Size::new(f32::MAX, f32::MAX),
+                alignment,
+            )
+            .unwrap();
+        let glyphs = brush
+            .process_glyphs(
+                glyphs,
+                &sections,
+                FontSmoothing::AntiAliased,
+                &mut font_atlas_sets,
+                &fonts,
+                &mut texture_atlases,
+                &mut textures,
+            )
+            .unwrap();
+
+        // the missing glyph has neither an outline nor an image
+        assert_eq!(glyphs.len(), 1);
+        let emoji = &glyphs[0];
+        assert_eq!(emoji.byte_index, 1);
+        assert!(emoji.atlas_info.is_color);
+        assert_eq!(emoji.size, Vec2::new(4.0, 4.0));
+
+        let texture_atlas = texture_atlases
+            .get(&emoji.atlas_info.texture_atlas)
+            .unwrap();
+        let rect = texture_atlas.textures[emoji.atlas_info.glyph_index];
+        let atlas_texture = textures.get(&texture_atlas.texture).unwrap();
+        let atlas_width = atlas_texture.texture_descriptor.size.width as usize;
+        for y in rect.min.y as usize..rect.max.y as usize {
+            for x in rect.min.x as usize..rect.max.x as usize {
+                let index = (y * atlas_width + x) * 4;
+                assert_eq!(atlas_texture.data[index..index + 4], [255, 128, 0, 255]);
+            }
+        }
+    }
+}
@@ -4,6 +4,7 @@ mod font_atlas;
 mod font_atlas_set;
 mod font_loader;
 mod glyph_brush;
+mod paragraphs;
 mod pipeline;
 mod text;
 mod text2d;
@@ -14,6 +15,7 @@ pub use font_atlas::*;
 pub use font_atlas_set::*;
 pub use font_loader::*;
 pub use glyph_brush::*;
+pub use paragraphs::*;
 pub use pipeline::*;
 pub use text::*;
 pub use text2d::*;
@@ -46,6 +48,8 @@ impl Plugin for TextPlugin {
             .register_type::<HorizontalAlign>()
             .init_asset_loader::<FontLoader>()
             .insert_resource(DefaultTextPipeline::default())
+            .init_resource::<TextSettings>()
+            .add_system_to_stage(CoreStage::PreUpdate, invalidate_modified_fonts)
             .add_system_to_stage(CoreStage::PostUpdate, text2d_system);
 
         if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
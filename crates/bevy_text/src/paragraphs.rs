@@ -0,0 +1,359 @@
+use std::ops::Range;
+
+use bevy_math::{Size, Vec2};
+
+use crate::{PositionedGlyph, TextLayoutInfo};
+
+/// An edit of a text: the bytes in `range` of its previous value were replaced by
+/// `inserted_len` bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub inserted_len: usize,
+}
+
+/// The layout of a paragraph of a text, the part of a line up to a newline, which may wrap over
+/// several lines.
+#[derive(Debug, Clone)]
+pub struct ParagraphLayout {
+    /// The glyphs of the paragraph, positioned relative to its bottom-left corner, with byte
+    /// indices relative to its start.
+    pub glyphs: Vec<PositionedGlyph>,
+    pub size: Size,
+}
+
+/// A text laid out paragraph by paragraph, so that an edit only lays out again the paragraphs
+/// it touches.
+///
+/// Paragraphs are stacked top to bottom and left aligned, `line_gap` apart. Only the extent of
+/// each paragraph is kept, its glyphs being in the [`TextLayoutInfo`] laid out along with it,
+/// which edits update in place: the glyphs before the edited paragraphs only move when the
+/// height of the text changes, and the ones after them only have their byte index shifted.
+/// Laying out a whole text or editing it gives the same [`TextLayoutInfo`].
+#[derive(Debug, Clone, Default)]
+pub struct ParagraphsLayout {
+    paragraphs: Vec<ParagraphExtent>,
+    text_len: usize,
+    line_gap: f32,
+}
+
+/// The extent of a laid out paragraph within a [`ParagraphsLayout`].
+#[derive(Debug, Clone)]
+struct ParagraphExtent {
+    /// The length of the paragraph in bytes, including its newline.
+    len: usize,
+    size: Size,
+    glyph_count: usize,
+}
+
+impl ParagraphsLayout {
+    /// Lays out each paragraph of `text` with `layout_paragraph`, which is given the paragraph
+    /// without its newline, and returns the layout with the glyphs of all the paragraphs,
+    /// positioned relative to the bottom-left corner of the text like the layout of
+    /// [`TextPipeline::queue_text`](crate::TextPipeline::queue_text).
+    pub fn new<E>(
+        text: &str,
+        line_gap: f32,
+        mut layout_paragraph: impl FnMut(&str) -> Result<ParagraphLayout, E>,
+    ) -> Result<(Self, TextLayoutInfo), E> {
+        let mut laid_out = Vec::new();
+        let mut values = text.split('\n').peekable();
+        while let Some(value) = values.next() {
+            let newline_len = if values.peek().is_some() { 1 } else { 0 };
+            laid_out.push((value.len() + newline_len, layout_paragraph(value)?));
+        }
+        let mut layout = ParagraphsLayout {
+            paragraphs: Vec::new(),
+            text_len: 0,
+            line_gap,
+        };
+        let mut info = TextLayoutInfo {
+            glyphs: Vec::new(),
+            size: Size::new(0., 0.),
+            clip: None,
+            effects: Vec::new(),
+        };
+        layout.replace(&mut info, 0..0, 0, laid_out);
+        Ok((layout, info))
+    }
+
+    /// Updates the layout and its glyphs `info` for `edit`, `text` being the value after the
+    /// edit, and returns how many paragraphs were laid out again.
+    ///
+    /// Only the paragraphs overlapping the edit are laid out again. If `edit` doesn't match the
+    /// previous and new length of the text, or doesn't fall on character boundaries, the whole
+    /// text is laid out again. Nothing is updated if `layout_paragraph` fails.
+    pub fn edit<E>(
+        &mut self,
+        info: &mut TextLayoutInfo,
+        text: &str,
+        edit: &TextEdit,
+        mut layout_paragraph: impl FnMut(&str) -> Result<ParagraphLayout, E>,
+    ) -> Result<usize, E> {
+        let consistent = edit.range.start <= edit.range.end
+            && edit.range.end <= self.text_len
+            && self.text_len - edit.range.len() + edit.inserted_len == text.len();
+        let span = if consistent {
+            self.edited_span(text, edit)
+        } else {
+            None
+        };
+        let (paragraphs, start, value) = match span {
+            Some(span) => span,
+            None => {
+                let (layout, laid_out_info) =
+                    ParagraphsLayout::new(text, self.line_gap, layout_paragraph)?;
+                *self = layout;
+                *info = laid_out_info;
+                return Ok(self.paragraphs.len());
+            }
+        };
+
+        let ends_text = paragraphs.end == self.paragraphs.len();
+        let mut values = value.split('\n').peekable();
+        let mut laid_out = Vec::new();
+        while let Some(value) = values.next() {
+            let is_last = values.peek().is_none();
+            if is_last && !ends_text {
+                // the empty value after the newline of the last edited paragraph belongs to the
+                // next paragraph
+                debug_assert!(value.is_empty());
+                break;
+            }
+            let newline_len = if is_last { 0 } else { 1 };
+            laid_out.push((value.len() + newline_len, layout_paragraph(value)?));
+        }
+        let count = laid_out.len();
+        self.replace(info, paragraphs, start, laid_out);
+        debug_assert_eq!(self.text_len, text.len());
+        Ok(count)
+    }
+
+    /// Replaces the `paragraphs` starting at byte `start` with the `laid_out` ones, along with
+    /// their glyphs in `info`.
+    fn replace(
+        &mut self,
+        info: &mut TextLayoutInfo,
+        paragraphs: Range<usize>,
+        start: usize,
+        laid_out: Vec<(usize, ParagraphLayout)>,
+    ) {
+        let glyph_count = |extents: &[ParagraphExtent]| {
+            extents
+                .iter()
+                .map(|extent| extent.glyph_count)
+                .sum::<usize>()
+        };
+        let glyphs_start = glyph_count(&self.paragraphs[..paragraphs.start]);
+        let glyphs_end = glyphs_start + glyph_count(&self.paragraphs[paragraphs.clone()]);
+        let removed_len = self.paragraphs[paragraphs.clone()]
+            .iter()
+            .map(|extent| extent.len)
+            .sum::<usize>();
+        let mut top = self.paragraphs[..paragraphs.start]
+            .iter()
+            .map(|extent| extent.size.height + self.line_gap)
+            .sum::<f32>();
+        self.paragraphs.splice(
+            paragraphs,
+            laid_out.iter().map(|(len, paragraph)| ParagraphExtent {
+                len: *len,
+                size: paragraph.size,
+                glyph_count: paragraph.glyphs.len(),
+            }),
+        );
+
+        let gaps = self.paragraphs.len().saturating_sub(1) as f32 * self.line_gap;
+        let height = self
+            .paragraphs
+            .iter()
+            .map(|extent| extent.size.height)
+            .sum::<f32>()
+            + gaps;
+        let width = self
+            .paragraphs
+            .iter()
+            .map(|extent| extent.size.width)
+            .fold(0., f32::max);
+
+        // The glyphs before the edit keep their distance to the top of the text, and the ones
+        // after it to its bottom
+        let height_change = height - info.size.height;
+        if height_change != 0. {
+            for glyph in info.glyphs[..glyphs_start].iter_mut() {
+                glyph.position.y += height_change;
+            }
+        }
+        let mut inserted_len = 0;
+        let mut glyphs = Vec::with_capacity(
+            laid_out
+                .iter()
+                .map(|(_, paragraph)| paragraph.glyphs.len())
+                .sum(),
+        );
+        for (len, paragraph) in laid_out {
+            let bottom = height - top - paragraph.size.height;
+            let paragraph_start = start + inserted_len;
+            glyphs.extend(paragraph.glyphs.into_iter().map(|glyph| PositionedGlyph {
+                position: glyph.position + Vec2::new(0., bottom),
+                byte_index: glyph.byte_index + paragraph_start,
+                ..glyph
+            }));
+            top += paragraph.size.height + self.line_gap;
+            inserted_len += len;
+        }
+        if inserted_len != removed_len {
+            for glyph in info.glyphs[glyphs_end..].iter_mut() {
+                glyph.byte_index = glyph.byte_index - removed_len + inserted_len;
+            }
+        }
+        info.glyphs.splice(glyphs_start..glyphs_end, glyphs);
+        info.size = Size::new(width, height);
+        self.text_len = self.text_len - removed_len + inserted_len;
+    }
+
+    /// Returns the range of the paragraphs touched by `edit`, the byte at which they start, and
+    /// their new value in `text`.
+    fn edited_span<'a>(
+        &self,
+        text: &'a str,
+        edit: &TextEdit,
+    ) -> Option<(Range<usize>, usize, &'a str)> {
+        let last = self.paragraphs.len().checked_sub(1)?;
+        // The index and start of the paragraph containing `byte`, the end of the text being in
+        // the last paragraph
+        let paragraph_at = |byte: usize| {
+            let mut start = 0;
+            for (index, extent) in self.paragraphs.iter().enumerate() {
+                if byte < start + extent.len {
+                    return (index, start);
+                }
+                start += extent.len;
+            }
+            (last, self.text_len - self.paragraphs[last].len)
+        };
+        let (first, first_start) = paragraph_at(edit.range.start);
+        let (last_edited, last_start) = paragraph_at(edit.range.end);
+        let last_end = last_start + self.paragraphs[last_edited].len;
+        let new_end = last_end - edit.range.len() + edit.inserted_len;
+        let value = text.get(first_start..new_end)?;
+        // The newline ending the last edited paragraph is after the edit, so it must still be
+        // there
+        if last_edited != last && !value.ends_with('\n') {
+            return None;
+        }
+        Some((first..last_edited + 1, first_start, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParagraphLayout, ParagraphsLayout, TextEdit};
+    use crate::{GlyphAtlasInfo, PositionedGlyph, TextLayoutInfo};
+    use bevy_math::{Size, Vec2};
+    use std::convert::Infallible;
+
+    /// Lays out a paragraph on a single line, with a 10x10 glyph for each character but spaces.
+    fn monospace(paragraph: &str) -> Result<ParagraphLayout, Infallible> {
+        let glyphs = paragraph
+            .char_indices()
+            .enumerate()
+            .filter(|(_, (_, character))| *character != ' ')
+            .map(|(i, (byte_index, _))| PositionedGlyph {
+                position: Vec2::new(i as f32 * 10.0 + 5.0, 5.0),
+                size: Vec2::new(10.0, 10.0),
+                atlas_info: GlyphAtlasInfo {
+                    texture_atlas: Default::default(),
+                    glyph_index: 0,
+                    is_color: false,
+                },
+                section_index: 0,
+                byte_index,
+                clipped: false,
+            })
+            .collect();
+        Ok(ParagraphLayout {
+            glyphs,
+            size: Size::new(paragraph.chars().count() as f32 * 10.0, 10.0),
+        })
+    }
+
+    fn glyphs(layout: &TextLayoutInfo) -> Vec<(Vec2, usize)> {
+        layout
+            .glyphs
+            .iter()
+            .map(|glyph| (glyph.position, glyph.byte_index))
+            .collect()
+    }
+
+    /// Applies `edit` to `text` incrementally, checking that the layout is the same as the one
+    /// of the edited text, and returns how many paragraphs were laid out again.
+    fn check_edit(text: &str, edit: TextEdit, inserted: &str) -> usize {
+        let (mut layout, mut actual) = ParagraphsLayout::new(text, 2.0, monospace).unwrap();
+        let mut edited = text.to_string();
+        edited.replace_range(edit.range.clone(), inserted);
+        let count = layout.edit(&mut actual, &edited, &edit, monospace).unwrap();
+
+        let (_, expected) = ParagraphsLayout::new(&edited, 2.0, monospace).unwrap();
+        assert_eq!(glyphs(&actual), glyphs(&expected));
+        assert_eq!(actual.size, expected.size);
+        count
+    }
+
+    #[test]
+    fn edit_relays_out_touched_paragraphs() {
+        let text = (0..1000)
+            .map(|line| format!("let line_{} = {};", line, line))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let line_500 = text.find("let line_500 ").unwrap();
+
+        let insert = |at: usize, inserted: &str| {
+            let edit = TextEdit {
+                range: at..at,
+                inserted_len: inserted.len(),
+            };
+            check_edit(&text, edit, inserted)
+        };
+        assert_eq!(insert(line_500 + 4, "mut "), 1);
+        assert_eq!(insert(0, "// "), 1);
+        assert_eq!(insert(text.len(), ";"), 1);
+        // splitting a line lays out both halves
+        assert_eq!(insert(line_500 + 4, "\n"), 2);
+        assert_eq!(insert(text.len(), "\n"), 2);
+
+        let remove = |range: std::ops::Range<usize>| {
+            let edit = TextEdit {
+                range,
+                inserted_len: 0,
+            };
+            check_edit(&text, edit, "")
+        };
+        // joining two lines
+        assert_eq!(remove(line_500 - 1..line_500), 1);
+        assert_eq!(remove(line_500..line_500 + 4), 1);
+        assert_eq!(remove(0..text.len()), 1);
+    }
+
+    #[test]
+    fn edit_stacks_paragraphs() {
+        let (mut layout, mut info) = ParagraphsLayout::new("ab\n\nc", 2.0, monospace).unwrap();
+        assert_eq!(info.size, Size::new(20.0, 34.0));
+        assert_eq!(
+            glyphs(&info),
+            vec![
+                (Vec2::new(5.0, 29.0), 0),
+                (Vec2::new(15.0, 29.0), 1),
+                (Vec2::new(5.0, 5.0), 4),
+            ]
+        );
+
+        // an edit not matching the text lays everything out again
+        let edit = TextEdit {
+            range: 0..1,
+            inserted_len: 5,
+        };
+        assert_eq!(layout.edit(&mut info, "x\ny", &edit, monospace).unwrap(), 2);
+        assert_eq!(info.size, Size::new(10.0, 22.0));
+    }
+}
@@ -1,23 +1,36 @@
-use std::hash::Hash;
+use std::{
+    borrow::Cow,
+    f32::consts::FRAC_1_SQRT_2,
+    hash::{Hash, Hasher},
+    ops::Range,
+};
 
-use ab_glyph::{PxScale, ScaleFont};
-use bevy_asset::{Assets, Handle, HandleId};
-use bevy_math::Size;
-use bevy_render::texture::Image;
-use bevy_sprite::TextureAtlas;
-use bevy_utils::HashMap;
+use ab_glyph::{FontArc, PxScale, PxScaleFont, ScaleFont};
+use bevy_asset::{AssetEvent, Assets, Handle, HandleId};
+use bevy_ecs::{
+    change_detection::DetectChanges,
+    event::EventReader,
+    system::{Query, ResMut},
+};
+use bevy_math::{Size, Vec2};
+use bevy_render::{color::Color, texture::Image};
+use bevy_sprite::{Rect, TextureAtlas};
+use bevy_utils::{AHasher, HashMap};
 
-use glyph_brush_layout::{FontId, SectionText};
+use glyph_brush_layout::{FontId, SectionGlyph, SectionText};
 
 use crate::{
-    error::TextError, glyph_brush::GlyphBrush, scale_value, Font, FontAtlasSet, PositionedGlyph,
-    TextAlignment, TextSection,
+    error::TextError, glyph_brush::GlyphBrush, scale_value, DefaultTextPipeline, Font,
+    FontAtlasSet, FontSmoothing, ParagraphLayout, ParagraphsLayout, PositionedGlyph, Text,
+    TextAlignment, TextEdit, TextEffects, TextSection, WhitespaceHandling,
 };
 
 pub struct TextPipeline<ID> {
     brush: GlyphBrush,
-    glyph_map: HashMap<ID, TextLayoutInfo>,
+    glyph_map: HashMap<ID, QueuedLayout>,
     map_font_id: HashMap<HandleId, FontId>,
+    /// The ids of invalidated fonts, reused when they are added back to the brush.
+    invalidated_font_ids: HashMap<HandleId, FontId>,
 }
 
 impl<ID> Default for TextPipeline<ID> {
@@ -26,28 +39,324 @@ impl<ID> Default for TextPipeline<ID> {
             brush: GlyphBrush::default(),
             glyph_map: Default::default(),
             map_font_id: Default::default(),
+            invalidated_font_ids: Default::default(),
         }
     }
 }
 
+struct QueuedLayout {
+    info: TextLayoutInfo,
+    /// The hash of the inputs `info` was laid out from, if it is still valid for them.
+    inputs: Option<u64>,
+    /// The layout of each paragraph of a text queued with [`TextPipeline::queue_text_edit`], whose
+    /// glyphs are `info`, and the hash of the inputs it was laid out from, besides the text
+    /// itself.
+    paragraphs: Option<(u64, ParagraphsLayout)>,
+}
+
+/// The glyphs of a laid out text.
+///
+/// Glyph positions are the centers of the glyphs, relative to the bottom-left corner of the
+/// text, with the y axis pointing up.
+#[derive(Clone)]
 pub struct TextLayoutInfo {
     pub glyphs: Vec<PositionedGlyph>,
     pub size: Size,
+    /// The rect glyphs were clipped against, in the same space as the glyph positions.
+    pub clip: Option<Rect>,
+    /// The copies of the glyphs for the [`TextEffects`] of the text, in the order they are drawn
+    /// in, before [`TextLayoutInfo::glyphs`].
+    pub effects: Vec<GlyphGroup>,
+}
+
+/// Copies of the glyphs of a text, drawn behind it in a single color.
+#[derive(Clone, Debug)]
+pub struct GlyphGroup {
+    pub glyphs: Vec<PositionedGlyph>,
+    pub color: Color,
+}
+
+impl TextLayoutInfo {
+    /// Returns this layout with its y axis flipped within a box of the given `height`, without
+    /// laying the text out again.
+    ///
+    /// With a `height` of `self.size.height`, this turns positions relative to the bottom-left
+    /// corner with y pointing up into positions relative to the top-left corner with y pointing
+    /// down, and back.
+    pub fn flipped_y(&self, height: f32) -> TextLayoutInfo {
+        let flip = |y: f32| height - y;
+        let flip_glyphs = |glyphs: &[PositionedGlyph]| {
+            glyphs
+                .iter()
+                .map(|glyph| PositionedGlyph {
+                    // positions are glyph centers, so the glyph height doesn't need to be
+                    // accounted for
+                    position: Vec2::new(glyph.position.x, flip(glyph.position.y)),
+                    ..glyph.clone()
+                })
+                .collect()
+        };
+        TextLayoutInfo {
+            glyphs: flip_glyphs(&self.glyphs),
+            size: self.size,
+            clip: self.clip.map(|clip| Rect {
+                min: Vec2::new(clip.min.x, flip(clip.max.y)),
+                max: Vec2::new(clip.max.x, flip(clip.min.y)),
+            }),
+            effects: self
+                .effects
+                .iter()
+                .map(|group| GlyphGroup {
+                    glyphs: flip_glyphs(&group.glyphs),
+                    color: group.color,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TextLayoutInfo {
+    /// Returns the glyphs as a list of triangles, two per glyph, to render the text in a custom
+    /// pass.
+    ///
+    /// The vertices are in the same space as the glyph positions, with a `z` of `0`. Their UVs
+    /// point to the glyph in its [`TextureAtlas`], and their color is the color of the section of
+    /// `sections` it belongs to. The glyphs of [`TextLayoutInfo::effects`] come first, in the
+    /// color of their group. Glyphs whose atlas is missing are skipped.
+    pub fn to_mesh_vertices(
+        &self,
+        sections: &[TextSection],
+        texture_atlases: &Assets<TextureAtlas>,
+    ) -> Vec<TextVertex> {
+        let mut vertices = Vec::with_capacity(self.glyph_count() * 6);
+        for (glyph, color) in self.glyphs_with_color(sections) {
+            if let Some(atlas) = texture_atlases.get(&glyph.atlas_info.texture_atlas) {
+                vertices.extend(glyph_vertices(
+                    glyph,
+                    atlas.textures[glyph.atlas_info.glyph_index],
+                    atlas.size,
+                    color.as_linear_rgba_f32(),
+                ));
+            }
+        }
+        vertices
+    }
+
+    /// Returns all the glyphs to draw in order, the ones of [`TextLayoutInfo::effects`] first,
+    /// with their color: the one of their group, or of the section of `sections` they belong to.
+    ///
+    /// Color glyphs, like emojis, aren't tinted with the color of their section, only with its
+    /// alpha, while effects keep tinting them into a silhouette.
+    pub fn glyphs_with_color<'a>(
+        &'a self,
+        sections: &'a [TextSection],
+    ) -> impl Iterator<Item = (&'a PositionedGlyph, Color)> + 'a {
+        self.effects
+            .iter()
+            .flat_map(|group| group.glyphs.iter().map(move |glyph| (glyph, group.color)))
+            .chain(self.glyphs.iter().map(move |glyph| {
+                let color = sections[glyph.section_index].style.color;
+                if glyph.atlas_info.is_color {
+                    (glyph, Color::rgba(1.0, 1.0, 1.0, color.a()))
+                } else {
+                    (glyph, color)
+                }
+            }))
+    }
+
+    fn glyph_count(&self) -> usize {
+        self.effects
+            .iter()
+            .map(|group| group.glyphs.len())
+            .sum::<usize>()
+            + self.glyphs.len()
+    }
+}
+
+impl TextLayoutInfo {
+    /// Returns the position of a caret placed before the character at `byte_index` in the value
+    /// of the section `section_index`, at the height of the center of the glyphs next to it.
+    ///
+    /// Positions are the ones of [`PositionedGlyph::section_index`] and
+    /// [`PositionedGlyph::byte_index`], so they point into the values of the sections as given,
+    /// even when their whitespace is collapsed. Before a character with a glyph, the caret is on
+    /// the leading edge of that glyph. The characters without a glyph, like spaces, are spread
+    /// evenly over the gap between the glyphs around them, and at the end of a line, of a section
+    /// or of the text, the caret is on the trailing edge of the last glyph. Without any glyph, it
+    /// is at the origin.
+    pub fn caret_position(&self, section_index: usize, byte_index: usize) -> Vec2 {
+        self.caret_rect((section_index, byte_index))
+            .map_or(Vec2::ZERO, |rect| (rect.min + rect.max) / 2.0)
+    }
+
+    /// Returns the rects covering the characters in the `range` of `(section_index, byte_index)`
+    /// positions, one for each line of the selection.
+    ///
+    /// Positions are the same as for [`TextLayoutInfo::caret_position`], and the characters
+    /// without a glyph are placed the same way. An empty selection has no rect.
+    pub fn selection_rects(&self, range: Range<(usize, usize)>) -> Vec<Rect> {
+        if range.start >= range.end {
+            return Vec::new();
+        }
+        let glyph_rects = self
+            .glyphs
+            .iter()
+            .filter(|glyph| range.contains(&(glyph.section_index, glyph.byte_index)))
+            .map(|glyph| Rect {
+                min: glyph.position - glyph.size / 2.0,
+                max: glyph.position + glyph.size / 2.0,
+            });
+        let mut rects: Vec<Rect> = Vec::new();
+        let mut previous_x = f32::NEG_INFINITY;
+        for rect in self
+            .caret_rect(range.start)
+            .into_iter()
+            .chain(glyph_rects)
+            .chain(self.caret_rect(range.end))
+        {
+            let x = (rect.min.x + rect.max.x) / 2.0;
+            match rects.last_mut() {
+                // glyphs go left to right within a line
+                Some(line) if x > previous_x => {
+                    line.min = line.min.min(rect.min);
+                    line.max = line.max.max(rect.max);
+                }
+                _ => rects.push(rect),
+            }
+            previous_x = x;
+        }
+        // a selection of characters without a glyph at the end of a line has no width there
+        rects.retain(|rect| rect.max.x > rect.min.x);
+        rects
+    }
+
+    /// Returns the zero-width rect of a caret placed before the character at the
+    /// `(section_index, byte_index)` position, as high as the glyph it is placed against.
+    fn caret_rect(&self, position: (usize, usize)) -> Option<Rect> {
+        let edge = |glyph: &PositionedGlyph, x: f32| Rect {
+            min: Vec2::new(x, glyph.position.y - glyph.size.y / 2.0),
+            max: Vec2::new(x, glyph.position.y + glyph.size.y / 2.0),
+        };
+        let leading = |glyph: &PositionedGlyph| edge(glyph, glyph.position.x - glyph.size.x / 2.0);
+        let trailing = |glyph: &PositionedGlyph| edge(glyph, glyph.position.x + glyph.size.x / 2.0);
+
+        let (section_index, byte_index) = position;
+        let next = self
+            .glyphs
+            .iter()
+            .position(|glyph| (glyph.section_index, glyph.byte_index) >= position);
+        match next {
+            None => self.glyphs.last().map(trailing),
+            Some(0) => Some(leading(&self.glyphs[0])),
+            Some(index) => {
+                let (previous, next) = (&self.glyphs[index - 1], &self.glyphs[index]);
+                // the characters without a glyph between `previous` and `next`, as far as they
+                // are known: the end of the section of `previous` isn't
+                let gap = if previous.section_index == next.section_index {
+                    Some((previous.byte_index + 1, next.byte_index))
+                } else if section_index == next.section_index {
+                    Some((0, next.byte_index))
+                } else {
+                    None
+                };
+                match gap {
+                    _ if (next.section_index, next.byte_index) == position => Some(leading(next)),
+                    Some((first, end)) if next.position.x > previous.position.x => {
+                        let start = trailing(previous).min.x;
+                        let offset = (byte_index - first) as f32 / (end - first) as f32;
+                        Some(edge(
+                            previous,
+                            start + (leading(next).min.x - start) * offset,
+                        ))
+                    }
+                    _ => Some(trailing(previous)),
+                }
+            }
+        }
+    }
+}
+
+/// A vertex of the triangles returned by [`TextLayoutInfo::to_mesh_vertices`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextVertex {
+    pub position: [f32; 3],
+    pub uv: [f32; 2],
+    pub color: [f32; 4],
+}
+
+/// The two triangles of a glyph, with counter-clockwise winding.
+fn glyph_vertices(
+    glyph: &PositionedGlyph,
+    atlas_rect: Rect,
+    atlas_size: Vec2,
+    color: [f32; 4],
+) -> [TextVertex; 6] {
+    let min = glyph.position - glyph.size / 2.0;
+    let max = glyph.position + glyph.size / 2.0;
+    let uv_min = atlas_rect.min / atlas_size;
+    let uv_max = atlas_rect.max / atlas_size;
+    // The y axis of the glyphs points up, while the one of the atlas points down
+    let vertex = |x: f32, y: f32, u: f32, v: f32| TextVertex {
+        position: [x, y, 0.0],
+        uv: [u, v],
+        color,
+    };
+    let bottom_left = vertex(min.x, min.y, uv_min.x, uv_max.y);
+    let bottom_right = vertex(max.x, min.y, uv_max.x, uv_max.y);
+    let top_right = vertex(max.x, max.y, uv_max.x, uv_min.y);
+    let top_left = vertex(min.x, max.y, uv_min.x, uv_min.y);
+    [
+        bottom_left,
+        bottom_right,
+        top_right,
+        bottom_left,
+        top_right,
+        top_left,
+    ]
 }
 
 impl<ID: Hash + Eq> TextPipeline<ID> {
     pub fn get_or_insert_font_id(&mut self, handle: &Handle<Font>, font: &Font) -> FontId {
         let brush = &mut self.brush;
-        *self
-            .map_font_id
-            .entry(handle.id)
-            .or_insert_with(|| brush.add_font(handle.clone(), font.font.clone()))
+        let invalidated_font_ids = &mut self.invalidated_font_ids;
+        *self.map_font_id.entry(handle.id).or_insert_with(|| {
+            match invalidated_font_ids.remove(&handle.id) {
+                Some(font_id) => {
+                    brush.replace_font(font_id, handle.clone(), font.font.clone());
+                    font_id
+                }
+                None => brush.add_font(handle.clone(), font.font.clone()),
+            }
+        })
+    }
+
+    /// Forgets the font data cached for `handle`, so that the next [`TextPipeline::queue_text`]
+    /// using it picks up the current content of the font asset. Other fonts stay cached.
+    ///
+    /// The glyphs of this font already rasterized in its [`FontAtlasSet`] are not affected,
+    /// but all text will be laid out again when next queued.
+    pub fn invalidate_font(&mut self, handle: &Handle<Font>) {
+        if let Some(font_id) = self.map_font_id.remove(&handle.id) {
+            self.invalidated_font_ids.insert(handle.id, font_id);
+        }
+        for layout in self.glyph_map.values_mut() {
+            layout.inputs = None;
+            layout.paragraphs = None;
+        }
     }
 
     pub fn get_glyphs(&self, id: &ID) -> Option<&TextLayoutInfo> {
-        self.glyph_map.get(id)
+        self.glyph_map.get(id).map(|layout| &layout.info)
     }
 
+    /// Lays out `sections` and rasterizes their glyphs, storing the result for
+    /// [`TextPipeline::get_glyphs`].
+    ///
+    /// If the text of `id` was already laid out from the same sections, the existing layout is
+    /// kept. Only the fields affecting the layout are compared, so changing the color of a
+    /// section doesn't require a new layout.
+    ///
+    /// The glyphs are copied for each of the `effects`, which are laid out again when they change.
     #[allow(clippy::too_many_arguments)]
     pub fn queue_text(
         &mut self,
@@ -57,14 +366,58 @@ impl<ID: Hash + Eq> TextPipeline<ID> {
         scale_factor: f64,
         text_alignment: TextAlignment,
         bounds: Size,
+        clip: Option<Rect>,
+        effects: &TextEffects,
+        font_smoothing: FontSmoothing,
         font_atlas_set_storage: &mut Assets<FontAtlasSet>,
         texture_atlases: &mut Assets<TextureAtlas>,
         textures: &mut Assets<Image>,
     ) -> Result<(), TextError> {
+        let inputs = layout_inputs_hash(
+            sections,
+            scale_factor,
+            text_alignment,
+            bounds,
+            clip,
+            effects,
+            font_smoothing,
+        );
+        if let Some(QueuedLayout {
+            inputs: Some(previous),
+            ..
+        }) = self.glyph_map.get(&id)
+        {
+            if *previous == inputs {
+                return Ok(());
+            }
+        }
+
+        let linebreak_behaviors = sections
+            .iter()
+            .map(|section| section.linebreak_behavior)
+            .collect::<Vec<_>>();
+        let kerning = sections
+            .iter()
+            .map(|section| section.kerning)
+            .collect::<Vec<_>>();
+        let values = collapse_whitespace(sections);
+        // The glyphs point into the values laid out, which are mapped back to the values of the
+        // sections where their whitespace was collapsed
+        let byte_indices = sections
+            .iter()
+            .zip(values.iter())
+            .map(|(section, value)| match value {
+                Cow::Owned(value) if *value != section.value => {
+                    Some(uncollapsed_byte_indices(&section.value, value))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
         let mut scaled_fonts = Vec::new();
         let sections = sections
             .iter()
-            .map(|section| {
+            .zip(values.iter())
+            .map(|(section, value)| {
                 let font = fonts
                     .get(section.style.font.id)
                     .ok_or(TextError::NoSuchFont)?;
@@ -76,55 +429,912 @@ impl<ID: Hash + Eq> TextPipeline<ID> {
                 let section = SectionText {
                     font_id,
                     scale: PxScale::from(font_size),
-                    text: &section.value,
+                    text: value,
                 };
 
                 Ok(section)
             })
             .collect::<Result<Vec<_>, _>>()?;
 
-        let section_glyphs = self
-            .brush
-            .compute_glyphs(&sections, bounds, text_alignment)?;
+        let section_glyphs = self.brush.compute_glyphs(
+            &sections,
+            &linebreak_behaviors,
+            &kerning,
+            bounds,
+            text_alignment,
+        )?;
 
         if section_glyphs.is_empty() {
             self.glyph_map.insert(
                 id,
-                TextLayoutInfo {
-                    glyphs: Vec::new(),
-                    size: Size::new(0., 0.),
+                QueuedLayout {
+                    info: TextLayoutInfo {
+                        glyphs: Vec::new(),
+                        size: Size::new(0., 0.),
+                        clip,
+                        effects: Vec::new(),
+                    },
+                    inputs: Some(inputs),
+                    paragraphs: None,
                 },
             );
             return Ok(());
         }
 
-        let mut min_x: f32 = std::f32::MAX;
-        let mut min_y: f32 = std::f32::MAX;
-        let mut max_x: f32 = std::f32::MIN;
-        let mut max_y: f32 = std::f32::MIN;
-
-        for sg in section_glyphs.iter() {
-            let scaled_font = scaled_fonts[sg.section_index];
-            let glyph = &sg.glyph;
-            min_x = min_x.min(glyph.position.x);
-            min_y = min_y.min(glyph.position.y - scaled_font.ascent());
-            max_x = max_x.max(glyph.position.x + scaled_font.h_advance(glyph.id));
-            max_y = max_y.max(glyph.position.y - scaled_font.descent());
-        }
-
-        let size = Size::new(max_x - min_x, max_y - min_y);
+        let size = text_size(&section_glyphs, &scaled_fonts);
 
-        let glyphs = self.brush.process_glyphs(
+        let mut glyphs = self.brush.process_glyphs(
             section_glyphs,
             &sections,
+            font_smoothing,
             font_atlas_set_storage,
             fonts,
             texture_atlases,
             textures,
         )?;
+        for glyph in glyphs.iter_mut() {
+            if let Some(byte_indices) = &byte_indices[glyph.section_index] {
+                glyph.byte_index = byte_indices[glyph.byte_index];
+            }
+        }
+
+        let mut effects = effect_glyph_groups(&glyphs, effects, scale_factor);
+        let glyphs = match clip {
+            Some(clip) => {
+                for group in effects.iter_mut() {
+                    group.glyphs = clip_glyphs(std::mem::take(&mut group.glyphs), clip);
+                }
+                clip_glyphs(glyphs, clip)
+            }
+            None => glyphs,
+        };
+
+        self.glyph_map.insert(
+            id,
+            QueuedLayout {
+                info: TextLayoutInfo {
+                    glyphs,
+                    size,
+                    clip,
+                    effects,
+                },
+                inputs: Some(inputs),
+                paragraphs: None,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Lays out `section` like [`TextPipeline::queue_text`], keeping the layout of each of its
+    /// paragraphs so that after an `edit` of its value, only the edited paragraphs are laid out
+    /// again.
+    ///
+    /// This is meant for long texts edited a little at a time, like the content of a code
+    /// editor. The text is left and top aligned, and its whitespace is preserved. The whole text
+    /// is laid out again when `edit` is `None`, or when the other arguments or the fields of
+    /// `section` other than its value changed since the previous call for `id`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn queue_text_edit(
+        &mut self,
+        id: ID,
+        fonts: &Assets<Font>,
+        section: &TextSection,
+        edit: Option<&TextEdit>,
+        scale_factor: f64,
+        bounds: Size,
+        font_smoothing: FontSmoothing,
+        font_atlas_set_storage: &mut Assets<FontAtlasSet>,
+        texture_atlases: &mut Assets<TextureAtlas>,
+        textures: &mut Assets<Image>,
+    ) -> Result<(), TextError> {
+        let font = fonts
+            .get(section.style.font.id)
+            .ok_or(TextError::NoSuchFont)?;
+        let font_id = self.get_or_insert_font_id(&section.style.font, font);
+        let font_size = scale_value(section.style.font_size, scale_factor);
+        let scaled_font = ab_glyph::Font::as_scaled(&font.font, font_size);
 
-        self.glyph_map.insert(id, TextLayoutInfo { glyphs, size });
+        let mut hasher = AHasher::default();
+        section.style.font.id.hash(&mut hasher);
+        font_size.to_bits().hash(&mut hasher);
+        section.linebreak_behavior.hash(&mut hasher);
+        section.kerning.hash(&mut hasher);
+        bounds.width.to_bits().hash(&mut hasher);
+        bounds.height.to_bits().hash(&mut hasher);
+        font_smoothing.hash(&mut hasher);
+        let inputs = hasher.finish();
 
+        let brush = &self.brush;
+        let layout_paragraph = |paragraph: &str| -> Result<ParagraphLayout, TextError> {
+            let sections = [SectionText {
+                font_id,
+                scale: PxScale::from(font_size),
+                text: paragraph,
+            }];
+            let section_glyphs = brush.compute_glyphs(
+                &sections,
+                &[section.linebreak_behavior],
+                &[section.kerning],
+                bounds,
+                TextAlignment::default(),
+            )?;
+            let size = if section_glyphs.is_empty() {
+                // An empty paragraph still takes a line
+                Size::new(0., scaled_font.ascent() - scaled_font.descent())
+            } else {
+                text_size(&section_glyphs, &[scaled_font])
+            };
+            let glyphs = brush.process_glyphs(
+                section_glyphs,
+                &sections,
+                font_smoothing,
+                font_atlas_set_storage,
+                fonts,
+                texture_atlases,
+                textures,
+            )?;
+            Ok(ParagraphLayout { glyphs, size })
+        };
+
+        let queued = self.glyph_map.entry(id).or_insert_with(|| QueuedLayout {
+            info: TextLayoutInfo {
+                glyphs: Vec::new(),
+                size: Size::new(0., 0.),
+                clip: None,
+                effects: Vec::new(),
+            },
+            inputs: None,
+            paragraphs: None,
+        });
+        // Taken so that a failed layout doesn't leave paragraphs out of sync with the text, the
+        // previous glyphs being kept until the next layout
+        let paragraphs = match (queued.paragraphs.take(), edit) {
+            (Some((previous, mut paragraphs)), Some(edit)) if previous == inputs => {
+                paragraphs.edit(&mut queued.info, &section.value, edit, layout_paragraph)?;
+                paragraphs
+            }
+            _ => {
+                let (paragraphs, info) = ParagraphsLayout::new(
+                    &section.value,
+                    scaled_font.line_gap(),
+                    layout_paragraph,
+                )?;
+                queued.info = info;
+                paragraphs
+            }
+        };
+        queued.inputs = None;
+        queued.paragraphs = Some((inputs, paragraphs));
         Ok(())
     }
 }
+
+/// Returns the size of the text laid out as `section_glyphs`, from the extents of their lines.
+fn text_size(section_glyphs: &[SectionGlyph], scaled_fonts: &[PxScaleFont<&FontArc>]) -> Size {
+    let mut min_x: f32 = std::f32::MAX;
+    let mut min_y: f32 = std::f32::MAX;
+    let mut max_x: f32 = std::f32::MIN;
+    let mut max_y: f32 = std::f32::MIN;
+
+    for sg in section_glyphs.iter() {
+        let scaled_font = scaled_fonts[sg.section_index];
+        let glyph = &sg.glyph;
+        min_x = min_x.min(glyph.position.x);
+        min_y = min_y.min(glyph.position.y - scaled_font.ascent());
+        max_x = max_x.max(glyph.position.x + scaled_font.h_advance(glyph.id));
+        max_y = max_y.max(glyph.position.y - scaled_font.descent());
+    }
+
+    Size::new(max_x - min_x, max_y - min_y)
+}
+
+/// Invalidates the data cached for reloaded fonts, and lays out the text using them again.
+pub fn invalidate_modified_fonts(
+    mut font_events: EventReader<AssetEvent<Font>>,
+    mut text_pipeline: ResMut<DefaultTextPipeline>,
+    mut font_atlas_set_storage: ResMut<Assets<FontAtlasSet>>,
+    mut texts: Query<&mut Text>,
+) {
+    let mut modified_fonts = Vec::new();
+    for event in font_events.iter() {
+        if let AssetEvent::Modified { handle } = event {
+            text_pipeline.invalidate_font(handle);
+            // The glyphs rasterized from the previous version of the font are stale
+            font_atlas_set_storage.remove(handle.as_weak::<FontAtlasSet>());
+            modified_fonts.push(handle.id);
+        }
+    }
+    if modified_fonts.is_empty() {
+        return;
+    }
+
+    for mut text in texts.iter_mut() {
+        if text
+            .sections
+            .iter()
+            .any(|section| modified_fonts.contains(&section.style.font.id))
+        {
+            text.set_changed();
+        }
+    }
+}
+
+/// The estimated advance of a glyph, relative to the font size.
+const ESTIMATED_GLYPH_ADVANCE: f32 = 0.5;
+/// The estimated height of a line, relative to the font size.
+const ESTIMATED_LINE_HEIGHT: f32 = 1.2;
+
+/// Estimates the size `sections` would take once laid out within `bounds`, without needing
+/// their fonts to be loaded.
+///
+/// This uses typical font metrics instead of the actual glyphs, so the result is only an
+/// approximation: it should be replaced by the size computed by [`TextPipeline::queue_text`]
+/// once the fonts are available.
+pub fn estimate_text_size(sections: &[TextSection], scale_factor: f64, bounds: Size) -> Size {
+    let mut size = Size::new(0f32, 0f32);
+    let mut line_width: f32 = 0.;
+    let mut line_height: f32 = 0.;
+    for (section, value) in sections.iter().zip(collapse_whitespace(sections)) {
+        let font_size = scale_value(section.style.font_size, scale_factor);
+        let advance = font_size * ESTIMATED_GLYPH_ADVANCE;
+        for character in value.chars() {
+            let wraps = line_width > 0. && line_width + advance > bounds.width;
+            if character == '\n' || wraps {
+                size.width = size.width.max(line_width);
+                size.height += line_height.max(font_size * ESTIMATED_LINE_HEIGHT);
+                line_width = 0.;
+                line_height = 0.;
+            }
+            if character != '\n' {
+                line_width += advance;
+                line_height = line_height.max(font_size * ESTIMATED_LINE_HEIGHT);
+            }
+        }
+    }
+    size.width = size.width.max(line_width);
+    size.height += line_height;
+    size
+}
+
+/// Hashes the inputs of [`TextPipeline::queue_text`] which affect the layout of the text.
+fn layout_inputs_hash(
+    sections: &[TextSection],
+    scale_factor: f64,
+    text_alignment: TextAlignment,
+    bounds: Size,
+    clip: Option<Rect>,
+    effects: &TextEffects,
+    font_smoothing: FontSmoothing,
+) -> u64 {
+    let mut hasher = AHasher::default();
+    for section in sections {
+        section.value.hash(&mut hasher);
+        section.style.font.id.hash(&mut hasher);
+        section.style.font_size.to_bits().hash(&mut hasher);
+        section.linebreak_behavior.hash(&mut hasher);
+        section.whitespace.hash(&mut hasher);
+        section.kerning.hash(&mut hasher);
+    }
+    scale_factor.to_bits().hash(&mut hasher);
+    text_alignment.vertical.hash(&mut hasher);
+    text_alignment.horizontal.hash(&mut hasher);
+    bounds.width.to_bits().hash(&mut hasher);
+    bounds.height.to_bits().hash(&mut hasher);
+    clip.is_some().hash(&mut hasher);
+    if let Some(clip) = clip {
+        for value in [clip.min.x, clip.min.y, clip.max.x, clip.max.y] {
+            value.to_bits().hash(&mut hasher);
+        }
+    }
+    effects.shadow.is_some().hash(&mut hasher);
+    if let Some(shadow) = effects.shadow {
+        let [r, g, b, a] = shadow.color.as_rgba_f32();
+        for value in [shadow.offset.x, shadow.offset.y, r, g, b, a] {
+            value.to_bits().hash(&mut hasher);
+        }
+    }
+    effects.outline.is_some().hash(&mut hasher);
+    if let Some(outline) = effects.outline {
+        let [r, g, b, a] = outline.color.as_rgba_f32();
+        for value in [outline.width, r, g, b, a] {
+            value.to_bits().hash(&mut hasher);
+        }
+    }
+    font_smoothing.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The directions of the copies of a [`TextOutline`](crate::TextOutline) around the glyphs.
+const OUTLINE_DIRECTIONS: [(f32, f32); 8] = [
+    (1.0, 0.0),
+    (FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+    (0.0, 1.0),
+    (-FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+    (-1.0, 0.0),
+    (-FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+    (0.0, -1.0),
+    (FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+];
+
+/// Returns the copies of `glyphs` for `effects`, the shadow first as it is the furthest behind.
+///
+/// The offsets of the effects are in logical pixels, and are scaled by `scale_factor` like the
+/// font sizes.
+fn effect_glyph_groups(
+    glyphs: &[PositionedGlyph],
+    effects: &TextEffects,
+    scale_factor: f64,
+) -> Vec<GlyphGroup> {
+    let offset_glyphs = |offset: Vec2| {
+        glyphs.iter().map(move |glyph| PositionedGlyph {
+            position: glyph.position + offset,
+            ..glyph.clone()
+        })
+    };
+    let mut groups = Vec::new();
+    if let Some(shadow) = effects.shadow {
+        let offset = Vec2::new(
+            scale_value(shadow.offset.x, scale_factor),
+            scale_value(shadow.offset.y, scale_factor),
+        );
+        groups.push(GlyphGroup {
+            glyphs: offset_glyphs(offset).collect(),
+            color: shadow.color,
+        });
+    }
+    if let Some(outline) = effects.outline {
+        let width = scale_value(outline.width, scale_factor);
+        groups.push(GlyphGroup {
+            glyphs: OUTLINE_DIRECTIONS
+                .iter()
+                .flat_map(|&(x, y)| offset_glyphs(Vec2::new(x, y) * width))
+                .collect(),
+            color: outline.color,
+        });
+    }
+    groups
+}
+
+/// Returns the values of `sections` to lay out, applying their [`WhitespaceHandling`].
+///
+/// This runs before the layout, so only the start and the end of the whole text are trimmed,
+/// not the lines broken by wrapping.
+fn collapse_whitespace(sections: &[TextSection]) -> Vec<Cow<str>> {
+    // Whether the previous character was whitespace, the start of the text counts as such so
+    // that leading whitespace is removed
+    let mut after_whitespace = true;
+    let mut values = sections
+        .iter()
+        .map(|section| match section.whitespace {
+            WhitespaceHandling::Preserve => {
+                if let Some(last) = section.value.chars().last() {
+                    after_whitespace = last.is_whitespace();
+                }
+                Cow::Borrowed(section.value.as_str())
+            }
+            WhitespaceHandling::Collapse => {
+                let mut value = String::with_capacity(section.value.len());
+                for character in section.value.chars() {
+                    if !character.is_whitespace() {
+                        value.push(character);
+                        after_whitespace = false;
+                    } else if !after_whitespace {
+                        value.push(' ');
+                        after_whitespace = true;
+                    }
+                }
+                Cow::Owned(value)
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // Removes the whitespace at the end of the text, which may span several sections
+    for (section, value) in sections.iter().zip(values.iter_mut()).rev() {
+        if section.whitespace == WhitespaceHandling::Preserve {
+            break;
+        }
+        let trimmed = value.trim_end();
+        if !trimmed.is_empty() {
+            *value = Cow::Owned(trimmed.to_string());
+            break;
+        }
+        *value = Cow::Borrowed("");
+    }
+    values
+}
+
+/// Returns the index in `value` of each byte of `collapsed`, the value of a section with its
+/// whitespace collapsed by [`collapse_whitespace`].
+///
+/// Collapsing only removes characters, other than turning the first whitespace character of a
+/// run into a space, so the characters of `collapsed` are found in order in `value`.
+fn uncollapsed_byte_indices(value: &str, collapsed: &str) -> Vec<usize> {
+    let mut characters = value.char_indices();
+    let mut indices = Vec::with_capacity(collapsed.len());
+    for character in collapsed.chars() {
+        let index = characters
+            .find(|(_, original)| {
+                if character.is_whitespace() {
+                    original.is_whitespace()
+                } else {
+                    *original == character
+                }
+            })
+            .map_or(value.len(), |(index, _)| index);
+        indices.extend(std::iter::repeat(index).take(character.len_utf8()));
+    }
+    indices
+}
+
+/// Drops the glyphs lying fully outside of `clip`, and flags the ones crossing its edges
+/// as clipped.
+fn clip_glyphs(glyphs: Vec<PositionedGlyph>, clip: Rect) -> Vec<PositionedGlyph> {
+    glyphs
+        .into_iter()
+        .filter_map(|mut glyph| {
+            let min = glyph.position - glyph.size / 2.0;
+            let max = glyph.position + glyph.size / 2.0;
+            if max.x <= clip.min.x
+                || min.x >= clip.max.x
+                || max.y <= clip.min.y
+                || min.y >= clip.max.y
+            {
+                return None;
+            }
+            glyph.clipped = min.x < clip.min.x
+                || max.x > clip.max.x
+                || min.y < clip.min.y
+                || max.y > clip.max.y;
+            Some(glyph)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_math::Vec2;
+    use bevy_sprite::Rect;
+
+    use super::{
+        clip_glyphs, collapse_whitespace, effect_glyph_groups, estimate_text_size, glyph_vertices,
+        layout_inputs_hash, uncollapsed_byte_indices, TextLayoutInfo, TextPipeline,
+    };
+    use crate::{
+        Font, FontSmoothing, GlyphAtlasInfo, PositionedGlyph, TextAlignment, TextEffects,
+        TextOutline, TextSection, TextShadow, TextStyle, WhitespaceHandling,
+    };
+    use ab_glyph::Font as _;
+    use bevy_asset::{Handle, HandleId};
+    use bevy_math::Size;
+    use bevy_render::color::Color;
+    use glyph_brush_layout::FontId;
+
+    fn glyph_row(count: usize, width: f32) -> Vec<PositionedGlyph> {
+        (0..count)
+            .map(|i| PositionedGlyph {
+                position: Vec2::new((i as f32 + 0.5) * width, 5.0),
+                size: Vec2::new(width, 10.0),
+                atlas_info: GlyphAtlasInfo {
+                    texture_atlas: Default::default(),
+                    glyph_index: i,
+                    is_color: false,
+                },
+                section_index: 0,
+                byte_index: i,
+                clipped: false,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn clip_drops_glyphs_beyond_right_edge() {
+        let clip = Rect {
+            min: Vec2::new(0.0, 0.0),
+            max: Vec2::new(25.0, 10.0),
+        };
+        let glyphs = clip_glyphs(glyph_row(10, 10.0), clip);
+
+        let indices: Vec<_> = glyphs.iter().map(|glyph| glyph.byte_index).collect();
+        assert_eq!(indices, vec![0, 1, 2]);
+        let clipped: Vec<_> = glyphs.iter().map(|glyph| glyph.clipped).collect();
+        assert_eq!(clipped, vec![false, false, true]);
+    }
+
+    #[test]
+    fn clip_keeps_glyphs_fully_inside() {
+        let clip = Rect {
+            min: Vec2::new(-10.0, -10.0),
+            max: Vec2::new(100.0, 20.0),
+        };
+        let glyphs = clip_glyphs(glyph_row(10, 10.0), clip);
+
+        assert_eq!(glyphs.len(), 10);
+        assert!(glyphs.iter().all(|glyph| !glyph.clipped));
+    }
+
+    #[test]
+    fn shadow_and_outline_glyphs() {
+        let glyphs = glyph_row(3, 10.0);
+        assert!(effect_glyph_groups(&glyphs, &TextEffects::default(), 2.0).is_empty());
+
+        let effects = TextEffects {
+            shadow: Some(TextShadow {
+                offset: Vec2::new(2.0, -1.0),
+                color: Color::BLACK,
+            }),
+            outline: None,
+        };
+        let groups = effect_glyph_groups(&glyphs, &effects, 2.0);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].color, Color::BLACK);
+        assert_eq!(groups[0].glyphs.len(), glyphs.len());
+        for (glyph, shadow_glyph) in glyphs.iter().zip(&groups[0].glyphs) {
+            // the offset is in logical pixels
+            assert_eq!(shadow_glyph.position, glyph.position + Vec2::new(4.0, -2.0));
+            assert_eq!(shadow_glyph.byte_index, glyph.byte_index);
+        }
+
+        let effects = TextEffects {
+            outline: Some(TextOutline {
+                width: 1.0,
+                color: Color::RED,
+            }),
+            ..effects
+        };
+        let groups = effect_glyph_groups(&glyphs, &effects, 1.0);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[1].color, Color::RED);
+        assert_eq!(groups[1].glyphs.len(), 8 * glyphs.len());
+        for outline_glyph in &groups[1].glyphs {
+            let glyph = &glyphs[outline_glyph.byte_index];
+            let distance = outline_glyph.position.distance(glyph.position);
+            assert!((distance - 1.0).abs() < 1e-5);
+        }
+
+        let layout = TextLayoutInfo {
+            glyphs: glyphs.clone(),
+            size: Size::new(30.0, 10.0),
+            clip: None,
+            effects: groups,
+        };
+        let sections = [section("abc", 20.0)];
+        let colors: Vec<_> = layout
+            .glyphs_with_color(&sections)
+            .map(|(_, color)| color)
+            .collect();
+        assert_eq!(colors.len(), 10 * glyphs.len());
+        assert_eq!(colors[0], Color::BLACK);
+        assert_eq!(colors[3], Color::RED);
+        assert_eq!(colors[27], Color::WHITE);
+    }
+
+    #[test]
+    fn flipped_y_mirrors_glyphs() {
+        let mut glyphs = glyph_row(2, 10.0);
+        // a descending second glyph, taller than the first one
+        glyphs[1].position.y = 6.0;
+        glyphs[1].size.y = 12.0;
+        let layout = TextLayoutInfo {
+            glyphs,
+            size: Size::new(20.0, 12.0),
+            clip: Some(Rect {
+                min: Vec2::new(0.0, 0.0),
+                max: Vec2::new(20.0, 8.0),
+            }),
+            effects: Vec::new(),
+        };
+
+        let flipped = layout.flipped_y(layout.size.height);
+        let positions: Vec<_> = flipped.glyphs.iter().map(|glyph| glyph.position).collect();
+        assert_eq!(positions, vec![Vec2::new(5.0, 7.0), Vec2::new(15.0, 6.0)]);
+        // glyph tops in the y-up layout become glyph tops in the y-down layout
+        for (glyph, flipped_glyph) in layout.glyphs.iter().zip(&flipped.glyphs) {
+            let top = layout.size.height - (glyph.position.y + glyph.size.y / 2.0);
+            assert_eq!(flipped_glyph.position.y - flipped_glyph.size.y / 2.0, top);
+        }
+        let clip = flipped.clip.unwrap();
+        assert_eq!(clip.min, Vec2::new(0.0, 4.0));
+        assert_eq!(clip.max, Vec2::new(20.0, 12.0));
+
+        let round_trip = flipped.flipped_y(layout.size.height);
+        for (glyph, round_trip_glyph) in layout.glyphs.iter().zip(&round_trip.glyphs) {
+            assert_eq!(glyph.position, round_trip_glyph.position);
+        }
+    }
+
+    fn section(value: &str, font_size: f32) -> TextSection {
+        TextSection {
+            value: value.to_string(),
+            style: TextStyle {
+                font: Handle::default(),
+                font_size,
+                color: Color::WHITE,
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn estimate_size_without_fonts() {
+        let unbounded = Size::new(f32::MAX, f32::MAX);
+        let size = estimate_text_size(&[section("hello", 20.0)], 1.0, unbounded);
+        assert_eq!(size, Size::new(50.0, 24.0));
+
+        let size = estimate_text_size(
+            &[section("hello\n", 20.0), section("hi", 40.0)],
+            2.0,
+            unbounded,
+        );
+        assert_eq!(size, Size::new(100.0, 48.0 + 96.0));
+
+        let size = estimate_text_size(&[section("hello", 20.0)], 1.0, Size::new(25.0, 100.0));
+        assert_eq!(size, Size::new(20.0, 3.0 * 24.0));
+
+        let size = estimate_text_size(&[section("", 20.0)], 1.0, unbounded);
+        assert_eq!(size, Size::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn invalidate_font_keeps_other_fonts() {
+        let mono = || {
+            Font::try_from_bytes(
+                include_bytes!("../../../assets/fonts/FiraMono-Medium.ttf").to_vec(),
+            )
+            .unwrap()
+        };
+        let sans = Font::try_from_bytes(
+            include_bytes!("../../../assets/fonts/FiraSans-Bold.ttf").to_vec(),
+        )
+        .unwrap();
+        let reloaded = Handle::<Font>::weak(HandleId::random::<Font>());
+        let other = Handle::<Font>::weak(HandleId::random::<Font>());
+        let mut pipeline = TextPipeline::<u32>::default();
+
+        let reloaded_id = pipeline.get_or_insert_font_id(&reloaded, &mono());
+        let other_id = pipeline.get_or_insert_font_id(&other, &mono());
+
+        pipeline.invalidate_font(&reloaded);
+        // Without invalidation, the cached font would be used
+        assert_eq!(pipeline.get_or_insert_font_id(&other, &sans), other_id);
+        assert_eq!(
+            pipeline.get_or_insert_font_id(&reloaded, &sans),
+            reloaded_id
+        );
+
+        let glyph_count = |font_id: FontId| pipeline.brush.fonts[font_id.0].glyph_count();
+        assert_eq!(glyph_count(reloaded_id), sans.font.glyph_count());
+        assert_eq!(glyph_count(other_id), mono().font.glyph_count());
+    }
+
+    #[test]
+    fn layout_inputs_ignore_color() {
+        let hash = |sections: &[TextSection]| {
+            layout_inputs_hash(
+                sections,
+                1.0,
+                TextAlignment::default(),
+                Size::new(100.0, 100.0),
+                None,
+                &TextEffects::default(),
+                FontSmoothing::default(),
+            )
+        };
+        let sections = [section("hello", 20.0), section("world", 20.0)];
+        let mut recolored = sections.clone();
+        recolored[1].style.color = Color::RED;
+        assert_eq!(hash(&sections), hash(&recolored));
+
+        let mut edited = sections.clone();
+        edited[1].value = "there".to_string();
+        assert_ne!(hash(&sections), hash(&edited));
+        let mut resized = sections.clone();
+        resized[0].style.font_size = 30.0;
+        assert_ne!(hash(&sections), hash(&resized));
+
+        let shadowed = layout_inputs_hash(
+            &sections,
+            1.0,
+            TextAlignment::default(),
+            Size::new(100.0, 100.0),
+            None,
+            &TextEffects {
+                shadow: Some(TextShadow {
+                    offset: Vec2::new(1.0, -1.0),
+                    color: Color::BLACK,
+                }),
+                outline: None,
+            },
+            FontSmoothing::default(),
+        );
+        assert_ne!(hash(&sections), shadowed);
+    }
+
+    #[test]
+    fn collapse_whitespace_runs() {
+        let collapsing = |value: &str| TextSection {
+            whitespace: WhitespaceHandling::Collapse,
+            ..section(value, 20.0)
+        };
+        let sections = [collapsing("a   b\n\n c")];
+        assert_eq!(collapse_whitespace(&sections), vec!["a b c"]);
+
+        let sections = [
+            collapsing("  a \t"),
+            collapsing(" b  "),
+            section(" c  ", 20.0),
+            collapsing("  d "),
+            collapsing("\n"),
+        ];
+        assert_eq!(
+            collapse_whitespace(&sections),
+            vec!["a ", "b ", " c  ", "d", ""]
+        );
+    }
+
+    #[test]
+    fn caret_and_selection() {
+        // "abc", each glyph 10 wide
+        let layout = TextLayoutInfo {
+            glyphs: glyph_row(3, 10.0),
+            size: Size::new(30.0, 10.0),
+            clip: None,
+            effects: Vec::new(),
+        };
+        assert_eq!(layout.caret_position(0, 0), Vec2::new(0.0, 5.0));
+        // between 'a' and 'b'
+        assert_eq!(layout.caret_position(0, 1), Vec2::new(10.0, 5.0));
+        assert_eq!(layout.caret_position(0, 3), Vec2::new(30.0, 5.0));
+        assert_eq!(layout.caret_position(0, 10), Vec2::new(30.0, 5.0));
+        let empty = TextLayoutInfo {
+            glyphs: Vec::new(),
+            size: Size::new(0.0, 0.0),
+            clip: None,
+            effects: Vec::new(),
+        };
+        assert_eq!(empty.caret_position(0, 0), Vec2::ZERO);
+        assert!(empty.selection_rects((0, 0)..(0, 2)).is_empty());
+
+        let rects = layout.selection_rects((0, 1)..(0, 3));
+        assert_eq!(rects.len(), 1);
+        assert_eq!(rects[0].min, Vec2::new(10.0, 0.0));
+        assert_eq!(rects[0].max, Vec2::new(30.0, 10.0));
+        assert!(layout.selection_rects((0, 2)..(0, 2)).is_empty());
+
+        // "abc" over "de"
+        let mut glyphs = glyph_row(3, 10.0);
+        for glyph in glyph_row(2, 10.0) {
+            glyphs.push(PositionedGlyph {
+                position: glyph.position - Vec2::new(0.0, 10.0),
+                byte_index: glyph.byte_index + 4,
+                ..glyph
+            });
+        }
+        let two_lines = TextLayoutInfo {
+            glyphs,
+            size: Size::new(30.0, 20.0),
+            clip: None,
+            effects: Vec::new(),
+        };
+        let rects = two_lines.selection_rects((0, 1)..(0, 10));
+        assert_eq!(rects.len(), 2);
+        assert_eq!(rects[0].min, Vec2::new(10.0, 0.0));
+        assert_eq!(rects[0].max, Vec2::new(30.0, 10.0));
+        assert_eq!(rects[1].min, Vec2::new(0.0, -10.0));
+        assert_eq!(rects[1].max, Vec2::new(20.0, 0.0));
+    }
+
+    #[test]
+    fn caret_and_selection_skip_spaces() {
+        // "a  b", each glyph 10 wide, and the spaces without a glyph
+        let layout = TextLayoutInfo {
+            glyphs: glyph_row(4, 10.0)
+                .into_iter()
+                .filter(|glyph| glyph.byte_index == 0 || glyph.byte_index == 3)
+                .collect(),
+            size: Size::new(40.0, 10.0),
+            clip: None,
+            effects: Vec::new(),
+        };
+        assert_eq!(layout.caret_position(0, 0), Vec2::new(0.0, 5.0));
+        assert_eq!(layout.caret_position(0, 1), Vec2::new(10.0, 5.0));
+        assert_eq!(layout.caret_position(0, 2), Vec2::new(20.0, 5.0));
+        assert_eq!(layout.caret_position(0, 3), Vec2::new(30.0, 5.0));
+        assert_eq!(layout.caret_position(0, 4), Vec2::new(40.0, 5.0));
+
+        // only the spaces
+        let rects = layout.selection_rects((0, 1)..(0, 3));
+        assert_eq!(rects.len(), 1);
+        assert_eq!(rects[0].min, Vec2::new(10.0, 0.0));
+        assert_eq!(rects[0].max, Vec2::new(30.0, 10.0));
+
+        let rects = layout.selection_rects((0, 0)..(0, 2));
+        assert_eq!(rects.len(), 1);
+        assert_eq!(rects[0].min, Vec2::new(0.0, 0.0));
+        assert_eq!(rects[0].max, Vec2::new(20.0, 10.0));
+    }
+
+    #[test]
+    fn caret_and_selection_across_sections() {
+        // "ab" and "  cd", each glyph 10 wide, and the spaces of the second section without a
+        // glyph
+        let mut glyphs = glyph_row(2, 10.0);
+        for glyph in glyph_row(6, 10.0).into_iter().skip(4) {
+            glyphs.push(PositionedGlyph {
+                section_index: 1,
+                byte_index: glyph.byte_index - 2,
+                ..glyph
+            });
+        }
+        let layout = TextLayoutInfo {
+            glyphs,
+            size: Size::new(60.0, 10.0),
+            clip: None,
+            effects: Vec::new(),
+        };
+        assert_eq!(layout.caret_position(0, 1), Vec2::new(10.0, 5.0));
+        // the end of the first section
+        assert_eq!(layout.caret_position(0, 2), Vec2::new(20.0, 5.0));
+        assert_eq!(layout.caret_position(1, 0), Vec2::new(20.0, 5.0));
+        assert_eq!(layout.caret_position(1, 1), Vec2::new(30.0, 5.0));
+        assert_eq!(layout.caret_position(1, 2), Vec2::new(40.0, 5.0));
+        assert_eq!(layout.caret_position(1, 3), Vec2::new(50.0, 5.0));
+        assert_eq!(layout.caret_position(2, 0), Vec2::new(60.0, 5.0));
+
+        let rects = layout.selection_rects((0, 1)..(1, 3));
+        assert_eq!(rects.len(), 1);
+        assert_eq!(rects[0].min, Vec2::new(10.0, 0.0));
+        assert_eq!(rects[0].max, Vec2::new(50.0, 10.0));
+        // only the spaces of the second section
+        let rects = layout.selection_rects((1, 0)..(1, 2));
+        assert_eq!(rects.len(), 1);
+        assert_eq!(rects[0].min, Vec2::new(20.0, 0.0));
+        assert_eq!(rects[0].max, Vec2::new(40.0, 10.0));
+    }
+
+    #[test]
+    fn uncollapsed_byte_indices_point_into_values() {
+        let collapsing = |value: &str| TextSection {
+            whitespace: WhitespaceHandling::Collapse,
+            ..section(value, 20.0)
+        };
+        let sections = [collapsing("  a   é\n b ")];
+        let values = collapse_whitespace(&sections);
+        assert_eq!(values[0], "a é b");
+        assert_eq!(
+            uncollapsed_byte_indices(&sections[0].value, &values[0]),
+            vec![2, 3, 6, 6, 8, 10]
+        );
+    }
+
+    #[test]
+    fn glyph_vertices_cover_atlas_rect() {
+        let atlas_size = Vec2::new(64.0, 32.0);
+        let atlas_rects = [
+            Rect {
+                min: Vec2::new(0.0, 0.0),
+                max: Vec2::new(10.0, 10.0),
+            },
+            Rect {
+                min: Vec2::new(10.0, 0.0),
+                max: Vec2::new(20.0, 10.0),
+            },
+        ];
+        let glyphs = glyph_row(2, 10.0);
+        let color = Color::RED.as_linear_rgba_f32();
+        let vertices: Vec<_> = glyphs
+            .iter()
+            .zip(atlas_rects)
+            .flat_map(|(glyph, rect)| glyph_vertices(glyph, rect, atlas_size, color))
+            .collect();
+
+        assert_eq!(vertices.len(), 12);
+        for vertex in vertices.iter() {
+            assert!(vertex.uv.iter().all(|uv| (0.0..=1.0).contains(uv)));
+            assert_eq!(vertex.color, color);
+        }
+        // the bottom left corner of the second glyph samples the bottom left of its rect
+        assert_eq!(vertices[6].position, [10.0, 0.0, 0.0]);
+        assert_eq!(vertices[6].uv, [10.0 / 64.0, 10.0 / 32.0]);
+    }
+}
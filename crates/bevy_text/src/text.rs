@@ -1,11 +1,11 @@
 use bevy_asset::Handle;
 use bevy_ecs::{prelude::Component, reflect::ReflectComponent};
-use bevy_math::Size;
+use bevy_math::{Size, Vec2};
 use bevy_reflect::{FromReflect, Reflect, ReflectDeserialize};
 use bevy_render::color::Color;
 use serde::{Deserialize, Serialize};
 
-use crate::Font;
+use crate::{Font, FontSmoothing};
 
 #[derive(Component, Debug, Default, Clone, Reflect)]
 #[reflect(Component)]
@@ -59,16 +59,37 @@ impl Text {
             sections: vec![TextSection {
                 value: value.into(),
                 style,
+                ..Default::default()
             }],
             alignment,
         }
     }
 }
 
-#[derive(Debug, Default, Clone, FromReflect, Reflect)]
+#[derive(Debug, Clone, FromReflect, Reflect)]
 pub struct TextSection {
     pub value: String,
     pub style: TextStyle,
+    /// Where the text of this section may be wrapped when it exceeds the bounds of the text.
+    pub linebreak_behavior: BreakLineOn,
+    /// How the whitespace of this section is laid out.
+    pub whitespace: WhitespaceHandling,
+    /// Whether the font's kerning adjusts the spacing between the glyphs of this section.
+    ///
+    /// Disabling it keeps glyphs evenly spaced, for example in code or tabular numbers.
+    pub kerning: bool,
+}
+
+impl Default for TextSection {
+    fn default() -> Self {
+        Self {
+            value: Default::default(),
+            style: Default::default(),
+            linebreak_behavior: Default::default(),
+            whitespace: Default::default(),
+            kerning: true,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Reflect)]
@@ -111,6 +132,49 @@ impl From<HorizontalAlign> for glyph_brush_layout::HorizontalAlign {
     }
 }
 
+/// Describes where a line of text may be broken when it exceeds the text bounds.
+/// Newlines always start a new line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, FromReflect, Serialize, Deserialize)]
+#[reflect_value(Serialize, Deserialize)]
+pub enum BreakLineOn {
+    /// Lines are broken between words, following the Unicode Standard Annex #14.
+    WordBoundary,
+    /// Lines are broken on any character.
+    AnyCharacter,
+    /// Lines are never broken within this section, it moves to a new line as a whole when it
+    /// doesn't fit.
+    NoWrap,
+}
+
+impl Default for BreakLineOn {
+    fn default() -> Self {
+        BreakLineOn::WordBoundary
+    }
+}
+
+/// Describes how the whitespace of a [`TextSection`] is laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, FromReflect, Serialize, Deserialize)]
+#[reflect_value(Serialize, Deserialize)]
+pub enum WhitespaceHandling {
+    /// Whitespace is laid out as is.
+    Preserve,
+    /// Runs of spaces, tabs and newlines are laid out as a single space, like CSS's
+    /// `white-space: normal`. Whitespace at the start and the end of the text is removed.
+    ///
+    /// Runs continue across consecutive collapsing sections.
+    ///
+    /// Unlike CSS, the whitespace at the lines broken by wrapping isn't removed: the space a run
+    /// is collapsed to stays in the text where it is broken, so with
+    /// [`BreakLineOn::AnyCharacter`] a wrapped line can start with it.
+    Collapse,
+}
+
+impl Default for WhitespaceHandling {
+    fn default() -> Self {
+        WhitespaceHandling::Preserve
+    }
+}
+
 /// Describes vertical alignment preference for positioning & bounds. Currently a placeholder
 /// for future functionality.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
@@ -151,6 +215,41 @@ impl Default for TextStyle {
     }
 }
 
+/// Effects drawn behind a text, made of copies of its glyphs in a single color.
+///
+/// Add this component next to a [`Text`] to give it a drop shadow or an outline. Without it, or
+/// with both effects set to `None`, no copies are laid out.
+#[derive(Component, Debug, Default, Clone, PartialEq)]
+pub struct TextEffects {
+    pub shadow: Option<TextShadow>,
+    pub outline: Option<TextOutline>,
+}
+
+/// A copy of a text offset behind it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextShadow {
+    /// The offset of the shadow, in logical pixels with the y axis pointing up.
+    pub offset: Vec2,
+    pub color: Color,
+}
+
+/// Copies of a text around it, giving its glyphs an outline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextOutline {
+    /// How far the copies are from the text, in logical pixels.
+    pub width: f32,
+    pub color: Color,
+}
+
+/// Global settings for text rendering.
+///
+/// Changing them re-lays out all text.
+#[derive(Debug, Default, Clone)]
+pub struct TextSettings {
+    /// How glyphs are rasterized into the font atlases.
+    pub font_smoothing: FontSmoothing,
+}
+
 #[derive(Component, Default, Copy, Clone, Debug, Reflect)]
 #[reflect(Component)]
 pub struct Text2dSize {
@@ -2,7 +2,7 @@ use bevy_asset::Assets;
 use bevy_ecs::{
     bundle::Bundle,
     entity::Entity,
-    query::{Changed, QueryState, With},
+    query::{Changed, Or, QueryState, With},
     system::{Local, Query, QuerySet, Res, ResMut},
 };
 use bevy_math::{Size, Vec3};
@@ -12,8 +12,8 @@ use bevy_transform::prelude::{GlobalTransform, Transform};
 use bevy_window::Windows;
 
 use crate::{
-    DefaultTextPipeline, Font, FontAtlasSet, HorizontalAlign, Text, Text2dSize, TextError,
-    VerticalAlign,
+    DefaultTextPipeline, Font, FontAtlasSet, HorizontalAlign, Text, Text2dSize, TextEffects,
+    TextError, TextSettings, VerticalAlign,
 };
 
 /// The bundle of components needed to draw text in a 2D scene via a 2D `OrthographicCameraBundle`.
@@ -63,7 +63,6 @@ pub fn extract_text2d_sprite(
         let (width, height) = (calculated_size.size.width, calculated_size.size.height);
 
         if let Some(text_layout) = text_pipeline.get_glyphs(&entity) {
-            let text_glyphs = &text_layout.glyphs;
             let alignment_offset = match text.alignment.vertical {
                 VerticalAlign::Top => Vec3::new(0.0, -height, 0.0),
                 VerticalAlign::Center => Vec3::new(0.0, -height * 0.5, 0.0),
@@ -77,11 +76,8 @@ pub fn extract_text2d_sprite(
             let mut text_transform = *transform;
             text_transform.scale /= scale_factor;
 
-            for text_glyph in text_glyphs {
-                let color = text.sections[text_glyph.section_index]
-                    .style
-                    .color
-                    .as_rgba_linear();
+            for (text_glyph, color) in text_layout.glyphs_with_color(&text.sections) {
+                let color = color.as_rgba_linear();
                 let atlas = texture_atlases
                     .get(text_glyph.atlas_info.texture_atlas.clone_weak())
                     .unwrap();
@@ -125,14 +121,23 @@ pub fn text2d_system(
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
     mut font_atlas_set_storage: ResMut<Assets<FontAtlasSet>>,
     mut text_pipeline: ResMut<DefaultTextPipeline>,
+    text_settings: Res<TextSettings>,
     mut text_queries: QuerySet<(
-        QueryState<Entity, (With<Text2dSize>, Changed<Text>)>,
-        QueryState<(&Text, &mut Text2dSize), With<Text2dSize>>,
+        QueryState<Entity, (With<Text2dSize>, Or<(Changed<Text>, Changed<TextEffects>)>)>,
+        QueryState<(&Text, Option<&TextEffects>, &mut Text2dSize), With<Text2dSize>>,
+        QueryState<Entity, With<Text2dSize>>,
     )>,
 ) {
-    // Adds all entities where the text or the style has changed to the local queue
-    for entity in text_queries.q0().iter_mut() {
-        queued_text.entities.push(entity);
+    if text_settings.is_changed() {
+        // If the text settings have changed, queue all text
+        for entity in text_queries.q2().iter() {
+            queued_text.entities.push(entity);
+        }
+    } else {
+        // Adds all entities where the text or the style has changed to the local queue
+        for entity in text_queries.q0().iter_mut() {
+            queued_text.entities.push(entity);
+        }
     }
 
     if queued_text.entities.is_empty() {
@@ -149,7 +154,7 @@ pub fn text2d_system(
     let mut new_queue = Vec::new();
     let mut query = text_queries.q1();
     for entity in queued_text.entities.drain(..) {
-        if let Ok((text, mut calculated_size)) = query.get_mut(entity) {
+        if let Ok((text, effects, mut calculated_size)) = query.get_mut(entity) {
             match text_pipeline.queue_text(
                 entity,
                 &fonts,
@@ -157,6 +162,9 @@ pub fn text2d_system(
                 scale_factor,
                 text.alignment,
                 Size::new(f32::MAX, f32::MAX),
+                None,
+                effects.unwrap_or(&TextEffects::default()),
+                text_settings.font_smoothing,
                 &mut *font_atlas_set_storage,
                 &mut *texture_atlases,
                 &mut *textures,
@@ -203,11 +203,9 @@ pub fn extract_text_uinodes(
             continue;
         }
         if let Some(text_layout) = text_pipeline.get_glyphs(&entity) {
-            let text_glyphs = &text_layout.glyphs;
             let alignment_offset = (uinode.size / -2.0).extend(0.0);
 
-            for text_glyph in text_glyphs {
-                let color = text.sections[text_glyph.section_index].style.color;
+            for (text_glyph, color) in text_layout.glyphs_with_color(&text.sections) {
                 let atlas = texture_atlases
                     .get(text_glyph.atlas_info.texture_atlas.clone_weak())
                     .unwrap();
@@ -354,6 +354,9 @@ impl Default for FlexWrap {
 pub struct CalculatedSize {
     /// The size of the node
     pub size: Size,
+    /// Whether `size` is an estimate, made while the fonts of a text are still loading, that
+    /// will be replaced once they are available
+    pub approximate: bool,
 }
 
 /// The color of the node
@@ -3,18 +3,24 @@ use bevy_asset::Assets;
 use bevy_ecs::{
     entity::Entity,
     prelude::QueryState,
-    query::{Changed, Or, With},
-    system::{Local, QuerySet, Res, ResMut},
+    query::{ChangeTrackers, Changed, Or, With},
+    system::{Local, QuerySet, RemovedComponents, Res, ResMut},
 };
 use bevy_math::Size;
 use bevy_render::texture::Image;
 use bevy_sprite::TextureAtlas;
-use bevy_text::{DefaultTextPipeline, Font, FontAtlasSet, Text, TextError};
+use bevy_text::{
+    estimate_text_size, DefaultTextPipeline, Font, FontAtlasSet, Text, TextEffects, TextError,
+    TextSettings,
+};
+use bevy_utils::HashMap;
 use bevy_window::Windows;
 
 #[derive(Debug, Default)]
 pub struct QueuedText {
     entities: Vec<Entity>,
+    /// The snapped bounds each text was last laid out with
+    bounds: HashMap<Entity, Size>,
 }
 
 fn scale_value(value: f32, factor: f64) -> f32 {
@@ -34,6 +40,19 @@ pub fn text_constraint(min_size: Val, size: Val, max_size: Val, scale_factor: f6
     }
 }
 
+/// Rounds a text bound computed by [`text_constraint`] down to whole physical pixels.
+///
+/// Sub-pixel changes of the bound, for example while a window is being resized, then don't cause
+/// the text to be laid out again.
+pub fn snap_text_bound(bound: f32) -> f32 {
+    if bound == f32::MAX {
+        bound
+    } else {
+        // Scaling by the scale factor can land just below a whole value
+        (bound + 1e-3).floor()
+    }
+}
+
 /// Updates the layout and size information whenever the text or style is changed.
 /// This information is computed by the `TextPipeline` on insertion, then stored.
 #[allow(clippy::too_many_arguments, clippy::type_complexity)]
@@ -46,12 +65,25 @@ pub fn text_system(
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
     mut font_atlas_set_storage: ResMut<Assets<FontAtlasSet>>,
     mut text_pipeline: ResMut<DefaultTextPipeline>,
+    text_settings: Res<TextSettings>,
     mut text_queries: QuerySet<(
-        QueryState<Entity, Or<(Changed<Text>, Changed<Style>)>>,
+        QueryState<Entity, Or<(Changed<Text>, Changed<TextEffects>, Changed<Style>)>>,
         QueryState<Entity, (With<Text>, With<Style>)>,
-        QueryState<(&Text, &Style, &mut CalculatedSize)>,
+        QueryState<(
+            &Text,
+            ChangeTrackers<Text>,
+            Option<(&TextEffects, ChangeTrackers<TextEffects>)>,
+            &Style,
+            &mut CalculatedSize,
+        )>,
     )>,
+    removed_text: RemovedComponents<Text>,
 ) {
+    let queued_text = &mut *queued_text;
+    for entity in removed_text.iter() {
+        queued_text.bounds.remove(&entity);
+    }
+
     let scale_factor = if let Some(window) = windows.get_primary() {
         window.scale_factor()
     } else {
@@ -61,13 +93,14 @@ pub fn text_system(
     let inv_scale_factor = 1. / scale_factor;
 
     #[allow(clippy::float_cmp)]
-    if *last_scale_factor == scale_factor {
+    if *last_scale_factor == scale_factor && !text_settings.is_changed() {
         // Adds all entities where the text or the style has changed to the local queue
         for entity in text_queries.q0().iter() {
             queued_text.entities.push(entity);
         }
     } else {
-        // If the scale factor has changed, queue all text
+        // If the scale factor or the text settings have changed, queue all text
+        queued_text.bounds.clear();
         for entity in text_queries.q1().iter() {
             queued_text.entities.push(entity);
         }
@@ -82,22 +115,33 @@ pub fn text_system(
     let mut new_queue = Vec::new();
     let mut query = text_queries.q2();
     for entity in queued_text.entities.drain(..) {
-        if let Ok((text, style, mut calculated_size)) = query.get_mut(entity) {
+        if let Ok((text, text_tracker, effects, style, mut calculated_size)) = query.get_mut(entity)
+        {
             let node_size = Size::new(
-                text_constraint(
+                snap_text_bound(text_constraint(
                     style.min_size.width,
                     style.size.width,
                     style.max_size.width,
                     scale_factor,
-                ),
-                text_constraint(
+                )),
+                snap_text_bound(text_constraint(
                     style.min_size.height,
                     style.size.height,
                     style.max_size.height,
                     scale_factor,
-                ),
+                )),
             );
 
+            // A style change that doesn't move the bounds to another whole pixel, like a window
+            // being resized, doesn't need the text to be laid out again
+            let effects_changed = effects.map_or(false, |(_, tracker)| tracker.is_changed());
+            if !text_tracker.is_changed()
+                && !effects_changed
+                && queued_text.bounds.get(&entity) == Some(&node_size)
+            {
+                continue;
+            }
+
             match text_pipeline.queue_text(
                 entity,
                 &fonts,
@@ -105,6 +149,9 @@ pub fn text_system(
                 scale_factor,
                 text.alignment,
                 node_size,
+                None,
+                effects.map_or(&TextEffects::default(), |(effects, _)| effects),
+                text_settings.font_smoothing,
                 &mut *font_atlas_set_storage,
                 &mut *texture_atlases,
                 &mut *textures,
@@ -113,18 +160,38 @@ pub fn text_system(
                     // There was an error processing the text layout, let's add this entity to the
                     // queue for further processing
                     new_queue.push(entity);
+                    // Until the fonts are loaded, use an estimate so that the layout doesn't
+                    // jump when the text is first displayed
+                    let size = estimate_text_size(&text.sections, scale_factor, node_size);
+                    let size = Size {
+                        width: scale_value(size.width, inv_scale_factor),
+                        height: scale_value(size.height, inv_scale_factor),
+                    };
+                    // The text is retried every frame until its fonts are loaded: only write
+                    // the estimate once so that the flex layout isn't updated every frame
+                    if calculated_size.size != size || !calculated_size.approximate {
+                        calculated_size.size = size;
+                        calculated_size.approximate = true;
+                    }
                 }
                 Err(e @ TextError::FailedToAddGlyph(_)) => {
                     panic!("Fatal error when processing text: {}.", e);
                 }
                 Ok(()) => {
+                    queued_text.bounds.insert(entity, node_size);
                     let text_layout_info = text_pipeline.get_glyphs(&entity).expect(
                         "Failed to get glyphs from the pipeline that have just been computed",
                     );
-                    calculated_size.size = Size {
+                    let size = Size {
                         width: scale_value(text_layout_info.size.width, inv_scale_factor),
                         height: scale_value(text_layout_info.size.height, inv_scale_factor),
                     };
+                    // Edits not affecting the layout, like a color change, keep the same size:
+                    // don't trigger a flex layout update for them
+                    if calculated_size.size != size || calculated_size.approximate {
+                        calculated_size.size = size;
+                        calculated_size.approximate = false;
+                    }
                 }
             }
         }
@@ -132,3 +199,30 @@ pub fn text_system(
 
     queued_text.entities = new_queue;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{snap_text_bound, text_constraint};
+    use crate::Val;
+
+    #[test]
+    fn sub_pixel_bounds_snap() {
+        let bound = |max_width: f32| {
+            snap_text_bound(text_constraint(
+                Val::Undefined,
+                Val::Undefined,
+                Val::Px(max_width),
+                1.5,
+            ))
+        };
+        // 200 physical pixels
+        let width = 200.0 / 1.5;
+        for nudge in 0..10 {
+            assert_eq!(bound(width + nudge as f32 * 0.05), 200.0);
+        }
+        assert_eq!(bound(201.0 / 1.5), 201.0);
+
+        let unbounded = text_constraint(Val::Auto, Val::Auto, Val::Auto, 1.5);
+        assert_eq!(snap_text_bound(unbounded), f32::MAX);
+    }
+}
@@ -137,6 +137,7 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                             font_size: 60.0,
                             color: Color::WHITE,
                         },
+                        ..Default::default()
                     },
                     TextSection {
                         value: "".to_string(),
@@ -145,6 +146,7 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                             font_size: 60.0,
                             color: Color::WHITE,
                         },
+                        ..Default::default()
                     },
                 ],
                 ..Default::default()
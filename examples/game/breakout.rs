@@ -95,6 +95,7 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         font_size: 40.0,
                         color: Color::rgb(0.5, 0.5, 1.0),
                     },
+                    ..Default::default()
                 },
                 TextSection {
                     value: "".to_string(),
@@ -103,6 +104,7 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         font_size: 40.0,
                         color: Color::rgb(1.0, 0.5, 0.5),
                     },
+                    ..Default::default()
                 },
             ],
             ..Default::default()
@@ -194,6 +194,7 @@ mod game {
                                     font_size: 60.0,
                                     color: Color::BLUE,
                                 },
+                                ..Default::default()
                             },
                             TextSection {
                                 value: " - ".to_string(),
@@ -202,6 +203,7 @@ mod game {
                                     font_size: 60.0,
                                     color: TEXT_COLOR,
                                 },
+                                ..Default::default()
                             },
                             TextSection {
                                 value: format!("volume: {:?}", *volume),
@@ -210,6 +212,7 @@ mod game {
                                     font_size: 60.0,
                                     color: Color::GREEN,
                                 },
+                                ..Default::default()
                             },
                         ],
                         ..Default::default()
@@ -76,6 +76,7 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                             font_size: 60.0,
                             color: Color::WHITE,
                         },
+                        ..Default::default()
                     },
                     TextSection {
                         value: "".to_string(),
@@ -84,6 +85,7 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                             font_size: 60.0,
                             color: Color::GOLD,
                         },
+                        ..Default::default()
                     },
                 ],
                 ..Default::default()
@@ -96,6 +96,7 @@ fn infotext_system(mut commands: Commands, asset_server: Res<AssetServer>) {
                             font_size: 30.0,
                             color: Color::WHITE,
                         },
+                        ..Default::default()
                     },
                     TextSection {
                         value: "\nThis text changes in the bottom right - ".to_string(),
@@ -104,6 +105,7 @@ fn infotext_system(mut commands: Commands, asset_server: Res<AssetServer>) {
                             font_size: 30.0,
                             color: Color::RED,
                         },
+                        ..Default::default()
                     },
                     TextSection {
                         value: "".to_string(),
@@ -112,6 +114,7 @@ fn infotext_system(mut commands: Commands, asset_server: Res<AssetServer>) {
                             font_size: 30.0,
                             color: Color::ORANGE_RED,
                         },
+                        ..Default::default()
                     },
                     TextSection {
                         value: " fps, ".to_string(),
@@ -120,6 +123,7 @@ fn infotext_system(mut commands: Commands, asset_server: Res<AssetServer>) {
                             font_size: 30.0,
                             color: Color::YELLOW,
                         },
+                        ..Default::default()
                     },
                     TextSection {
                         value: "".to_string(),
@@ -128,6 +132,7 @@ fn infotext_system(mut commands: Commands, asset_server: Res<AssetServer>) {
                             font_size: 30.0,
                             color: Color::GREEN,
                         },
+                        ..Default::default()
                     },
                     TextSection {
                         value: " ms/frame".to_string(),
@@ -136,6 +141,7 @@ fn infotext_system(mut commands: Commands, asset_server: Res<AssetServer>) {
                             font_size: 30.0,
                             color: Color::BLUE,
                         },
+                        ..Default::default()
                     },
                 ],
                 alignment: Default::default(),